@@ -277,3 +277,49 @@ pub fn sqvm_name_impl(input: DeriveInput) -> TokenStream {
     )
     .into()
 }
+
+pub fn convar_enum_impl(input: DeriveInput) -> TokenStream {
+    let DeriveInput {
+        attrs: _,
+        vis: _,
+        ident,
+        generics,
+        data,
+    } = input;
+
+    let varients = get_enum_varients(data);
+
+    if let Some(varient) = varients
+        .iter()
+        .find(|varient| !matches!(varient.fields, Fields::Unit))
+    {
+        return syn::Error::new(
+            varient.ident.span(),
+            "ConVarEnum can only be derived for unit-only enums",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let varient_idents: Vec<&Ident> = varients.iter().map(|varient| &varient.ident).collect();
+    let varient_indexes = 0..varient_idents.len();
+
+    quote!(
+        impl<#generics> rrplug::high::engine::convars::typed::ConVarEnum for #ident<#generics> {
+            const VARIANTS: &'static [(&'static str, Self)] = &[
+                #(
+                    (stringify!(#varient_idents), Self::#varient_idents),
+                )*
+            ];
+
+            fn variant_index(&self) -> usize {
+                match self {
+                    #(
+                        Self::#varient_idents => #varient_indexes,
+                    )*
+                }
+            }
+        }
+    )
+    .into()
+}
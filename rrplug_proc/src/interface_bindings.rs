@@ -0,0 +1,276 @@
+//! consumer bindings and headers for interfaces made with `#[as_interface]`
+
+use quote::ToTokens;
+use syn::{FnArg, GenericArgument, Ident, ImplItemFn, PathArguments, ReturnType, Type};
+
+use crate::parsing::get_arg_ident;
+
+const GENERATED_NOTE: &str = "generated by #[as_interface]";
+
+/// a `create_external_interface!` invocation with the same vtable as the interface
+pub fn rust_bindings(name: &Ident, funcs: &[ImplItemFn]) -> String {
+    let functions = funcs
+        .iter()
+        .map(|func| {
+            let inputs = arguments(func)
+                .map(|(ident, ty)| format!("{ident}: {}", rust_type(ty)))
+                .collect::<Vec<String>>()
+                .join(", ");
+            let output = match &func.sig.output {
+                ReturnType::Type(_, ty) => rust_type(ty),
+                ReturnType::Default => "()".to_string(),
+            };
+
+            format!("        pub fn {}({inputs}) -> {output};\n", func.sig.ident)
+        })
+        .collect::<String>();
+
+    format!(
+        "// {GENERATED_NOTE} from {name}; don't edit\n\
+         \n\
+         #[allow(unused_imports)]\n\
+         use std::ffi::*;\n\
+         \n\
+         rrplug::create_external_interface! {{\n    \
+             pub {name} + {name}Mod => {{\n\
+         {functions}    \
+             }}\n\
+         }}\n"
+    )
+}
+
+/// a header with a c struct with the same vtable as the interface and c++ methods which call through it
+///
+/// the c++ methods aren't `virtual` since msvc returns structs from member functions differently than from the `extern "C"` functions in the vtable
+pub fn c_header(name: &Ident, funcs: &[ImplItemFn]) -> String {
+    let mut undefined = Vec::new();
+    let mut uses_windows = false;
+    let mut c_type = |ty: &Type| {
+        let c_type = c_type(ty, &mut undefined);
+        uses_windows |= c_type.contains("HMODULE");
+        c_type
+    };
+
+    let mut pointers = String::new();
+    let mut methods = String::new();
+    for func in funcs {
+        let ident = &func.sig.ident;
+        let output = match &func.sig.output {
+            ReturnType::Type(_, ty) => c_type(ty),
+            ReturnType::Default => "void".to_string(),
+        };
+        let (idents, inputs): (Vec<String>, Vec<String>) = arguments(func)
+            .map(|(ident, ty)| (ident.clone(), format!("{} {ident}", c_type(ty))))
+            .unzip();
+
+        pointers += &format!(
+            "    {output} (*{ident})({});\n",
+            std::iter::once(format!("{name}* self"))
+                .chain(inputs.iter().cloned())
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+        methods += &format!(
+            "    {output} {ident}({}) {{ return vtable->{ident}({}); }}\n",
+            inputs.join(", "),
+            std::iter::once("this".to_string())
+                .chain(idents)
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+    }
+
+    undefined.sort();
+    undefined.dedup();
+    let undefined = match undefined.is_empty() {
+        true => String::new(),
+        false => format!(
+            "\n// these types have to be defined before this header is included: {}\n",
+            undefined.join(", ")
+        ),
+    };
+    let windows = match uses_windows {
+        true => "#include <windows.h>\n",
+        false => "",
+    };
+
+    format!(
+        "// {GENERATED_NOTE} from {name}; don't edit\n\
+         \n\
+         #pragma once\n\
+         \n\
+         #include <stdbool.h>\n\
+         #include <stddef.h>\n\
+         #include <stdint.h>\n\
+         {windows}{undefined}\n\
+         #ifdef __cplusplus\n\
+         struct {name};\n\
+         #else\n\
+         typedef struct {name} {name};\n\
+         #endif\n\
+         \n\
+         typedef struct {name}VTable\n\
+         {{\n\
+         {pointers}\
+         }} {name}VTable;\n\
+         \n\
+         struct {name}\n\
+         {{\n    \
+             {name}VTable const* vtable;\n\
+         #ifdef __cplusplus\n\
+         \n\
+         {methods}\
+         #endif\n\
+         }};\n"
+    )
+}
+
+fn arguments(func: &ImplItemFn) -> impl Iterator<Item = (String, &Type)> {
+    func.sig
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            FnArg::Typed(arg) => Some((input, &*arg.ty)),
+            FnArg::Receiver(_) => None,
+        })
+        .enumerate()
+        .map(|(index, (input, ty))| {
+            let ident = get_arg_ident(input)
+                .map(|ident| ident.to_string())
+                .unwrap_or_else(|| format!("arg{index}"));
+            (ident, ty)
+        })
+}
+
+fn rust_type(ty: &Type) -> String {
+    match ty {
+        Type::Ptr(ptr) => match ptr.mutability {
+            Some(_) => format!("*mut {}", rust_type(&ptr.elem)),
+            None => format!("*const {}", rust_type(&ptr.elem)),
+        },
+        Type::Reference(reference) => format!(
+            "&{}{}{}",
+            reference
+                .lifetime
+                .as_ref()
+                .map(|lifetime| format!("{lifetime} "))
+                .unwrap_or_default(),
+            reference.mutability.map(|_| "mut ").unwrap_or_default(),
+            rust_type(&reference.elem)
+        ),
+        Type::Tuple(tuple) if tuple.elems.is_empty() => "()".to_string(),
+        Type::Paren(paren) => rust_type(&paren.elem),
+        Type::Path(path) if path.qself.is_none() => {
+            let segments = path
+                .path
+                .segments
+                .iter()
+                .map(|segment| match &segment.arguments {
+                    PathArguments::AngleBracketed(args) => format!(
+                        "{}<{}>",
+                        segment.ident,
+                        args.args
+                            .iter()
+                            .map(|arg| match arg {
+                                GenericArgument::Type(ty) => rust_type(ty),
+                                arg => tokens(arg),
+                            })
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    ),
+                    _ => segment.ident.to_string(),
+                })
+                .collect::<Vec<String>>()
+                .join("::");
+
+            match path.path.leading_colon {
+                Some(_) => format!("::{segments}"),
+                None => segments,
+            }
+        }
+        ty => tokens(ty),
+    }
+}
+
+fn c_type(ty: &Type, undefined: &mut Vec<String>) -> String {
+    match ty {
+        Type::Ptr(ptr) => pointer_to(&ptr.elem, ptr.mutability.is_none(), undefined),
+        Type::Reference(reference) => {
+            pointer_to(&reference.elem, reference.mutability.is_none(), undefined)
+        }
+        Type::Tuple(tuple) if tuple.elems.is_empty() => "void".to_string(),
+        Type::Paren(paren) => c_type(&paren.elem, undefined),
+        // function pointers have the size of a pointer
+        Type::BareFn(_) => "void*".to_string(),
+        Type::Path(path) => {
+            let Some(segment) = path.path.segments.last() else {
+                return tokens(ty);
+            };
+
+            let inner = match &segment.arguments {
+                PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+                    GenericArgument::Type(ty) => Some(ty),
+                    _ => None,
+                }),
+                _ => None,
+            };
+
+            match (segment.ident.to_string().as_str(), inner) {
+                // the null pointer optimization makes these plain pointers
+                ("Option", Some(inner)) => c_type(inner, undefined),
+                ("NonNull", Some(inner)) => pointer_to(inner, false, undefined),
+                (name, _) => match primitive(name) {
+                    Some(c_type) => c_type.to_string(),
+                    None => {
+                        undefined.push(name.to_string());
+                        name.to_string()
+                    }
+                },
+            }
+        }
+        ty => tokens(ty),
+    }
+}
+
+fn pointer_to(elem: &Type, is_const: bool, undefined: &mut Vec<String>) -> String {
+    match is_const {
+        true => format!("{} const*", c_type(elem, undefined)),
+        false => format!("{}*", c_type(elem, undefined)),
+    }
+}
+
+fn primitive(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "i8" => "int8_t",
+        "i16" => "int16_t",
+        "i32" => "int32_t",
+        "i64" => "int64_t",
+        "isize" => "intptr_t",
+        "u8" => "uint8_t",
+        "u16" => "uint16_t",
+        "u32" => "uint32_t",
+        "u64" => "uint64_t",
+        "usize" => "size_t",
+        "f32" | "c_float" => "float",
+        "f64" | "c_double" => "double",
+        "bool" => "bool",
+        "c_char" => "char",
+        "c_schar" => "signed char",
+        "c_uchar" => "unsigned char",
+        "c_short" => "short",
+        "c_ushort" => "unsigned short",
+        "c_int" => "int",
+        "c_uint" => "unsigned int",
+        "c_long" => "long",
+        "c_ulong" => "unsigned long",
+        "c_longlong" => "long long",
+        "c_ulonglong" => "unsigned long long",
+        "c_void" => "void",
+        "HMODULE" => "HMODULE",
+        _ => return None,
+    })
+}
+
+fn tokens(tokens: &impl ToTokens) -> String {
+    tokens.to_token_stream().to_string()
+}
@@ -5,18 +5,19 @@ use proc_macro2::Span;
 use quote::{format_ident, quote, quote_spanned, ToTokens};
 use syn::{
     self, parse, parse_macro_input, parse_str, punctuated::Punctuated, spanned::Spanned,
-    AngleBracketedGenericArguments, DeriveInput, Error as SynError, FnArg, Ident, ImplItem,
-    ImplItemFn, ItemFn, ItemImpl, ReturnType, Stmt, Token, Type, TypePath,
+    DeriveInput, Error as SynError, FnArg, Generics, Ident, ImplItem, ImplItemFn, ItemFn, ItemImpl,
+    ReturnType, Stmt, Token, Type, TypePath,
 };
 
 #[macro_use]
 pub(crate) mod parsing;
 pub(crate) mod impl_traits;
+pub(crate) mod interface_bindings;
 
 use impl_traits::{
-    get_from_sqobject_impl_enum, get_from_sqobject_impl_struct, get_from_sqvm_impl_enum,
-    get_from_sqvm_impl_struct, impl_struct_or_enum, push_to_sqvm_impl_enum,
-    push_to_sqvm_impl_struct, sqvm_name_impl,
+    convar_enum_impl, get_from_sqobject_impl_enum, get_from_sqobject_impl_struct,
+    get_from_sqvm_impl_enum, get_from_sqvm_impl_struct, impl_struct_or_enum,
+    push_to_sqvm_impl_enum, push_to_sqvm_impl_struct, sqvm_name_impl,
 };
 use parsing::{filter_args, get_arg_ident, input_mapping, Args};
 
@@ -412,8 +413,34 @@ pub fn convar(_attr: TokenStream, item: TokenStream) -> TokenStream {
     .into()
 }
 
+/// turns an impl block into an interface which can be registered with `rrplug::interfaces::manager::register_interface`
+///
+/// every function becomes an entry of the vtable in the order they are defined in except `new` which has to create `Self`
+///
+/// ## generics
+/// the impl block can be generic; every registered type gets its own vtable. the functions themselves can't have generics
+///
+/// ## ffi safety
+/// the arguments and the return type of every function must implement `rrplug::interfaces::interface::FfiSafe`, types that don't are a compile error
+///
+/// ## attributes
+/// - **Bindings**
+///
+///     A path relative to the crate for a `create_external_interface!` with the same vtable so other plugins can `include!` it. Types should be fully qualified or be in scope where it's included
+/// - **Header**
+///
+///     A path relative to the crate for a C/C++ header with the same vtable. The C++ methods call through the C vtable instead of being `virtual` since msvc returns structs from member functions differently
+///
+/// the macro doesn't write any files; the contents are in the `INTERFACE_BINDINGS` and `INTERFACE_HEADER` consts of the interface
+/// and a generated test fails if the file at the path is different from them. the file is written by the test if it doesn't exist or if `RRPLUG_UPDATE_BINDINGS` is set
+///
+/// both don't work with generic impls since every type has its own vtable
 #[proc_macro_attribute]
-pub fn as_interface(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn as_interface(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = match attr.is_empty() {
+        true => Vec::new(),
+        false => parse_macro_input!(attr as Args).args,
+    };
     let input = parse_macro_input!(item as ItemImpl);
     let ItemImpl {
         attrs,
@@ -427,25 +454,6 @@ pub fn as_interface(_attr: TokenStream, item: TokenStream) -> TokenStream {
         items,
     } = input;
 
-    let self_ty_ident: Type = match parse_str(
-        self_ty
-            .to_token_stream()
-            .to_string()
-            .split('<')
-            .nth(0)
-            .unwrap(),
-    ) {
-        Ok(ty) => ty,
-        Err(err) => return err.to_compile_error().into(),
-    };
-    let generics_bracked: Option<AngleBracketedGenericArguments> = self_ty
-        .to_token_stream()
-        .to_string()
-        .split_once('<')
-        .map(|(_, generics)| format!("<{}>", generics))
-        .map(|generic_str| parse_str(&generic_str).ok())
-        .flatten();
-
     if let Some(trait_) = trait_ {
         let error_site = trait_.1.span();
         return quote_spanned! {error_site => compile_error!("interfaces must not be a implementation for a trait");}.into();
@@ -509,10 +517,23 @@ pub fn as_interface(_attr: TokenStream, item: TokenStream) -> TokenStream {
             .into();
     }
 
+    // a vtable entry can only point to one instance of a function
+    if let Some(error_site) = funcs
+        .iter()
+        .find(|func| !func.sig.generics.params.is_empty())
+        .map(|func| func.sig.generics.span())
+    {
+        return quote_spanned! {error_site => compile_error!("interface functions can't be generic; put the generics on the impl block instead");}.into();
+    }
+
     let function_idents = funcs
         .iter()
         .map(|func| &func.sig.ident)
         .collect::<Vec<&Ident>>();
+    let wrapper_idents = function_idents
+        .iter()
+        .map(|ident| format_ident!("__interface_{}", ident))
+        .collect::<Vec<Ident>>();
 
     if let Some(error_site) = funcs
         .iter()
@@ -536,6 +557,11 @@ pub fn as_interface(_attr: TokenStream, item: TokenStream) -> TokenStream {
         return quote_spanned! {error_site => compile_error!("functions must have &self");}.into();
     }
 
+    let generated_bindings = match interface_bindings(&args, &generics, &self_ty, &funcs) {
+        Ok(generated_bindings) => generated_bindings,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
     let extern_inputs = funcs
         .iter()
         .map(|func| {
@@ -563,30 +589,66 @@ pub fn as_interface(_attr: TokenStream, item: TokenStream) -> TokenStream {
         .map(|func| &func.sig.output)
         .collect::<Vec<&ReturnType>>();
 
+    // errors point at the types that can't cross the vtable instead of being improper_ctypes warnings
+    let ffi_checks = funcs
+        .iter()
+        .map(|func| {
+            let inputs = func.sig.inputs.iter().filter_map(|input| match input {
+                FnArg::Typed(arg) => Some(&*arg.ty),
+                FnArg::Receiver(_) => None,
+            });
+            let output = match &func.sig.output {
+                ReturnType::Type(_, ty) => Some(&**ty),
+                ReturnType::Default => None,
+            };
+
+            inputs
+                .chain(output)
+                .map(|ty| {
+                    quote_spanned! {ty.span()=>
+                        rrplug::interfaces::interface::assert_ffi_safe::<#ty>();
+                    }
+                })
+                .collect::<proc_macro2::TokenStream>()
+        })
+        .collect::<Vec<proc_macro2::TokenStream>>();
+
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
     quote! {
         #(#attrs)*
-        #impl_token #generics #self_ty {
+        #impl_token #impl_generics #self_ty #where_clause {
             #(#funcs)*
             #new_func
         }
 
         #(#attrs)*
-        impl #generics rrplug::interfaces::interface::AsInterface for #self_ty {
-            fn to_interface(self) -> rrplug::interfaces::interface::Interface<Self> {
-                    // make these extern c wrapped and offset the self since it will have the vtable
-                // TODO: make generic functions work (big headache)
-                #(
-                    #[allow(unsafe_op_in_unsafe_fn)]
-                    unsafe extern "C" fn #function_idents #generics(self_: *const std::ffi::c_void, #extern_inputs) #extern_outputs {
-                        // transmute because I want to be lazy here
-                        #self_ty_ident::#generics_bracked #function_idents(self_.cast::<usize>().add(1).cast::<#self_ty_ident>().as_ref().expect("how!"), #extern_inputs_idents )
-                    }
-                )*
+        #[doc(hidden)]
+        #[allow(non_snake_case, improper_ctypes_definitions)]
+        #impl_token #impl_generics #self_ty #where_clause {
+            // make these extern c wrapped and offset the self since it will have the vtable
+            #(
+                unsafe extern "C" fn #wrapper_idents(self_: *const std::ffi::c_void, #extern_inputs) #extern_outputs {
+                    #ffi_checks
+
+                    Self::#function_idents(
+                        unsafe { (*self_.cast::<rrplug::interfaces::interface::Interface<Self>>()).data() },
+                        #extern_inputs_idents
+                    )
+                }
+            )*
 
-                const VTABLE: &[*const std::ffi::c_void] = &[#(#function_idents #generics_bracked as *const std::ffi::c_void,)*];
+            // an associated const so generic impls get a vtable for every type
+            const __INTERFACE_VTABLE: &'static [*const std::ffi::c_void] = &[#(Self::#wrapper_idents as *const std::ffi::c_void,)*];
+        }
+
+        #generated_bindings
 
+        #(#attrs)*
+        #impl_token #impl_generics rrplug::interfaces::interface::AsInterface for #self_ty #where_clause {
+            fn to_interface(self) -> rrplug::interfaces::interface::Interface<Self> {
                 rrplug::interfaces::interface::Interface::new(
-                    unsafe { std::ptr::NonNull::new_unchecked(VTABLE.as_ptr().cast_mut()) },
+                    unsafe { std::ptr::NonNull::new_unchecked(Self::__INTERFACE_VTABLE.as_ptr().cast_mut()) },
                     self
                 )
             }
@@ -595,6 +657,77 @@ pub fn as_interface(_attr: TokenStream, item: TokenStream) -> TokenStream {
     .into()
 }
 
+fn interface_bindings(
+    args: &[parsing::Arg],
+    generics: &Generics,
+    self_ty: &Type,
+    funcs: &[ImplItemFn],
+) -> Result<proc_macro2::TokenStream, SynError> {
+    let Some(arg) = args.first() else {
+        return Ok(proc_macro2::TokenStream::new());
+    };
+
+    if !generics.params.is_empty() {
+        return Err(SynError::new(
+            arg.ident.span(),
+            "bindings can't be generated for generic interfaces since every type has its own vtable",
+        ));
+    }
+
+    let name = match self_ty {
+        Type::Path(path) => path.path.segments.last().map(|segment| &segment.ident),
+        _ => None,
+    }
+    .ok_or_else(|| SynError::new(self_ty.span(), "the interface has to be a named type"))?;
+
+    // writing the files here would depend on when the macro is expanded so they are checked by a test instead
+    let mut generated = proc_macro2::TokenStream::new();
+    for arg in args {
+        let (contents, const_ident, doc) = match &arg.ident.to_string()[..] {
+            "Bindings" => (
+                interface_bindings::rust_bindings(name, funcs),
+                format_ident!("INTERFACE_BINDINGS"),
+                "a `create_external_interface!` with the same vtable as this interface",
+            ),
+            "Header" => (
+                interface_bindings::c_header(name, funcs),
+                format_ident!("INTERFACE_HEADER"),
+                "a C/C++ header with the same vtable as this interface",
+            ),
+            _ => {
+                return Err(SynError::new(
+                    arg.ident.span(),
+                    format!("wrong arg {}; expected Bindings or Header", arg.ident),
+                ))
+            }
+        };
+
+        let path = &arg.arg;
+        let test_ident = format_ident!(
+            "__check_{}_{}",
+            name.to_string().to_lowercase(),
+            const_ident.to_string().to_lowercase()
+        );
+        generated.extend(quote! {
+            impl #self_ty {
+                #[doc = #doc]
+                pub const #const_ident: &'static str = #contents;
+            }
+
+            #[cfg(test)]
+            #[test]
+            fn #test_ident() {
+                rrplug::interfaces::interface::check_generated_file(
+                    concat!(env!("CARGO_MANIFEST_DIR"), "/", #path),
+                    <#self_ty>::#const_ident,
+                );
+            }
+        });
+    }
+
+    Ok(generated)
+}
+
 /// implements `GetFromSquirrelVm` for structs or enums
 ///
 /// the fields of the struct must implement `GetFromSQObject`
@@ -644,3 +777,13 @@ pub fn sqvm_name_macro(item: TokenStream) -> TokenStream {
 
     sqvm_name_impl(input)
 }
+
+/// implements `ConVarEnum` for unit-only enums so they can be used with `TypedConVar`
+///
+/// the variants are matched by name or by index and stored as the index
+#[proc_macro_derive(ConVarEnum)]
+pub fn convar_enum_macro(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+
+    convar_enum_impl(input)
+}
@@ -1,9 +1,9 @@
 use proc_macro::TokenStream;
 use quote::{quote, spanned::Spanned, ToTokens};
 use syn::{
-    self, parse::Parse, parse::ParseStream, punctuated::Punctuated, token::Comma, FnArg, Ident,
-    LitStr, Result as SynResult, Token, Type, __private::TokenStream2, parse_quote, parse_str,
-    Error as SynError,
+    self, __private::TokenStream2, parse::Parse, parse::ParseStream, parse_quote, parse_str,
+    punctuated::Punctuated, token::Comma, Error as SynError, FnArg, Ident, LitStr,
+    Result as SynResult, Token, Type,
 };
 
 pub struct Arg {
@@ -1,5 +1,4 @@
-{% if example %}use rrplug::bindings::cvar::convar::FCVAR_GAMEDLL;
-use rrplug::prelude::*;
+{% if example %}use rrplug::prelude::*;
 
 pub struct ExamplePlugin;
 
@@ -24,7 +23,7 @@ impl Plugin for ExamplePlugin {
             ..ConVarRegister::mandatory(
                 "basic_convar",
                 "48",
-                FCVAR_GAMEDLL.try_into().unwrap(),
+                CVarFlags::GAMEDLL,
                 "basic_convar",
             )
         };
@@ -35,7 +34,7 @@ impl Plugin for ExamplePlugin {
             "basic_command",
             basic_command_callback,
             "basic_command",
-            FCVAR_GAMEDLL.try_into().unwrap(),
+            CVarFlags::GAMEDLL,
         );
     }
 }
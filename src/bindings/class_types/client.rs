@@ -1,5 +1,6 @@
-use std::ffi::{c_char, c_void};
+use std::ffi::c_char;
 
+use super::keyvalues::KeyValues;
 use crate::offset_struct;
 
 const PERSISTENCE_MAX_SIZE: usize = 0xDDCD;
@@ -9,7 +10,7 @@ offset_struct! {
         __size: () where offset(0x2D728),
         edict: u16 where offset(0x14),
         name: [c_char;64] where offset(0x16),
-        con_vars: *const c_void where offset(0x258), // TODO: add KeyValues later
+        con_vars: *const KeyValues where offset(0x258),
         // net_channel: *const c_void where offset(0x290), this seams to be invalid :/
         signon: SignonState where offset(0x2A0),
         clan_tag: [c_char;16] where offset(0x358),
@@ -37,7 +38,7 @@ pub enum SignonState {
 }
 
 #[repr(i8)]
-#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub enum PersistenceReady {
     #[default]
     NotReady, // todo: check if this correct
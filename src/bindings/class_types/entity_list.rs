@@ -0,0 +1,29 @@
+//! entity lists of the server and client dlls
+
+use std::ffi::c_void;
+
+use crate::create_external_interface;
+
+/// the amount of bits used by the index of an entity handle
+pub const NUM_ENT_ENTRY_BITS: u32 = 12;
+/// the max amount of entities
+pub const NUM_ENT_ENTRIES: usize = 1 << NUM_ENT_ENTRY_BITS;
+/// masks the index out of an entity handle
+pub const ENT_ENTRY_MASK: u32 = (1 << NUM_ENT_ENTRY_BITS) - 1;
+/// an entity handle which doesn't point to anything
+pub const INVALID_EHANDLE_INDEX: u32 = 0xFFFFFFFF;
+
+// layout from the source sdk; not verified against titanfall 2's client.dll yet so the lookups using it are unsafe
+create_external_interface! {
+    pub RawClientEntityList + ClientEntityListMod => {
+        pub fn get_client_networkable(index: i32) -> *mut c_void;
+        pub fn get_client_networkable_from_handle(handle: u32) -> *mut c_void;
+        pub fn get_client_unknown_from_handle(handle: u32) -> *mut c_void;
+        pub fn get_client_entity(index: i32) -> *mut c_void;
+        pub fn get_client_entity_from_handle(handle: u32) -> *mut c_void;
+        pub fn number_of_entities(include_non_networkable: bool) -> i32;
+        pub fn get_highest_entity_index() -> i32;
+        pub fn set_max_entities(max: i32) -> ();
+        pub fn get_max_entities() -> i32;
+    }
+}
@@ -1 +1,19 @@
-// todo: add CGlobalVars
+use std::ffi::c_char;
+
+use crate::offset_struct;
+
+offset_struct! {
+    pub struct CGlobalVars {
+        real_time: f64 where offset(0x0),
+        frame_count: i32 where offset(0x8),
+        absolute_frame_time: f32 where offset(0xC),
+        cur_time: f32 where offset(0x10),
+        frame_time: f32 where offset(0x30),
+        max_clients: i32 where offset(0x34),
+        game_mode: i32 where offset(0x38),
+        tick_count: u32 where offset(0x3C),
+        interval_per_tick: f32 where offset(0x40),
+        map_name: *const c_char where offset(0x60),
+        map_version: i32 where offset(0x68),
+    }
+}
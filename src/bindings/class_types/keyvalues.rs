@@ -0,0 +1,106 @@
+//! source's `KeyValues` trees and the system which owns their key names
+
+use std::ffi::{c_char, c_void};
+
+use crate::create_external_interface;
+
+/// a node of a `KeyValues` tree
+///
+/// the name of the key is a symbol which has to be resolved with [`IKeyValuesSystem`]
+#[repr(C)]
+#[derive(Debug)]
+pub struct KeyValues {
+    /// the low 24 bits are the symbol of the key name
+    pub key_name: u32,
+    pub value_string: *mut c_char,
+    pub value_wstring: *mut u16,
+    /// `int`, `float`, `void*` or a color depending on [`KeyValues::value_type`]
+    pub value: KeyValuesValue,
+    pub short_name: [c_char; 8],
+    /// a [`KeyValuesType`] which is kept raw since the engine could write any byte here
+    pub data_type: u8,
+    pub has_escape_sequences: c_char,
+    pub key_name_case_sensitive: u16,
+    pub peer: *mut KeyValues,
+    pub sub: *mut KeyValues,
+    pub chain: *mut KeyValues,
+}
+
+impl KeyValues {
+    /// the symbol of the key name
+    pub const fn key_symbol(&self) -> i32 {
+        (self.key_name & 0xFFFFFF) as i32
+    }
+
+    /// the type of the value or [`None`] if the engine wrote an unknown type
+    pub fn value_type(&self) -> Option<KeyValuesType> {
+        KeyValuesType::try_from(self.data_type).ok()
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union KeyValuesValue {
+    pub int: i32,
+    pub float: f32,
+    pub ptr: *mut c_void,
+    pub color: [u8; 4],
+}
+
+impl std::fmt::Debug for KeyValuesValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("KeyValuesValue")
+            .field(unsafe { &self.ptr })
+            .finish()
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[repr(u8)]
+#[derive(Clone, Debug, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum KeyValuesType {
+    #[default]
+    NONE = 0, // has children instead of a value
+    STRING = 1,
+    INT = 2,
+    FLOAT = 3,
+    PTR = 4,
+    WSTRING = 5,
+    COLOR = 6,
+    UINT64 = 7, // the value is behind value_string
+    COMPILED_INT_BYTE = 8,
+    COMPILED_INT_0 = 9,
+    COMPILED_INT_1 = 10,
+}
+
+impl TryFrom<u8> for KeyValuesType {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Self::NONE,
+            1 => Self::STRING,
+            2 => Self::INT,
+            3 => Self::FLOAT,
+            4 => Self::PTR,
+            5 => Self::WSTRING,
+            6 => Self::COLOR,
+            7 => Self::UINT64,
+            8 => Self::COMPILED_INT_BYTE,
+            9 => Self::COMPILED_INT_0,
+            10 => Self::COMPILED_INT_1,
+            _ => Err(value)?,
+        })
+    }
+}
+
+// layout from the source sdk; recheck this
+create_external_interface! {
+    pub IKeyValuesSystem + KeyValuesSystemMod => {
+        pub fn register_sizeof_key_values(size: i32) -> ();
+        pub fn alloc_key_values_memory(size: i32) -> *mut c_void;
+        pub fn free_key_values_memory(memory: *mut c_void) -> ();
+        pub fn get_symbol_for_string(name: *const c_char, create: bool) -> i32;
+        pub fn get_string_for_symbol(symbol: i32) -> *const c_char;
+    }
+}
@@ -1,4 +1,6 @@
 pub mod c_player;
 pub mod client;
 pub mod cplayer;
+pub mod entity_list;
 pub mod globalvars;
+pub mod keyvalues;
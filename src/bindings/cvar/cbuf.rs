@@ -0,0 +1,48 @@
+//! types used by the engine's command buffer (Cbuf)
+
+/// which command buffer text is added to
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ECommandTarget {
+    /// the buffer of the first local player
+    FirstPlayer = 0,
+    /// the buffer of the last local player (splitscreen)
+    LastPlayer = 1,
+    /// the buffer of the server
+    #[default]
+    Server = 2,
+}
+
+impl TryFrom<i32> for ECommandTarget {
+    type Error = i32;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Self::FirstPlayer,
+            1 => Self::LastPlayer,
+            2 => Self::Server,
+            _ => Err(value)?,
+        })
+    }
+}
+
+/// where a command came from
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CmdSource {
+    /// executed by code
+    #[default]
+    Code = 0,
+    /// sent by a client with `ClientCmd`
+    ClientCmd = 1,
+    /// typed in by the user
+    UserInput = 2,
+    /// received from a client over the network
+    NetClient = 3,
+    /// received from the server over the network
+    NetServer = 4,
+    /// played back from a demo
+    DemoFile = 5,
+    /// invalid source
+    Invalid = -1,
+}
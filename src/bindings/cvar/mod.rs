@@ -1,14 +1,68 @@
-use crate::{create_external_interface, impl_vmethods};
+use bitflags::bitflags;
 use std::ffi::{c_char, c_void};
 
+use crate::{create_external_interface, impl_vmethods};
+
+pub mod cbuf;
 pub mod command;
 pub mod convar;
 
 use self::{
     command::{ConCommand, ConCommandBase},
-    convar::ConVar,
+    convar::*,
 };
 
+bitflags! {
+    /// flags of convars and concommands; mirrors the `FCVAR_*` constants in [`convar`]
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+    pub struct CVarFlags: i32 {
+        /// same as [`CVarFlags::empty`]
+        const NONE = FCVAR_NONE as i32;
+        const UNREGISTERED = FCVAR_UNREGISTERED as i32;
+        const DEVELOPMENTONLY = FCVAR_DEVELOPMENTONLY as i32;
+        const GAMEDLL = FCVAR_GAMEDLL as i32;
+        const CLIENTDLL = FCVAR_CLIENTDLL as i32;
+        const HIDDEN = FCVAR_HIDDEN as i32;
+        const PROTECTED = FCVAR_PROTECTED as i32;
+        const SPONLY = FCVAR_SPONLY as i32;
+        const ARCHIVE = FCVAR_ARCHIVE as i32;
+        const NOTIFY = FCVAR_NOTIFY as i32;
+        const USERINFO = FCVAR_USERINFO as i32;
+        const PRINTABLEONLY = FCVAR_PRINTABLEONLY as i32;
+        /// same bit as [`CVarFlags::PRINTABLEONLY`]
+        const GAMEDLL_FOR_REMOTE_CLIENTS = FCVAR_GAMEDLL_FOR_REMOTE_CLIENTS as i32;
+        const UNLOGGED = FCVAR_UNLOGGED as i32;
+        const NEVER_AS_STRING = FCVAR_NEVER_AS_STRING as i32;
+        const REPLICATED = FCVAR_REPLICATED as i32;
+        const CHEAT = FCVAR_CHEAT as i32;
+        const SS = FCVAR_SS as i32;
+        const DEMO = FCVAR_DEMO as i32;
+        const DONTRECORD = FCVAR_DONTRECORD as i32;
+        const SS_ADDED = FCVAR_SS_ADDED as i32;
+        const RELEASE = FCVAR_RELEASE as i32;
+        const RELOAD_MATERIALS = FCVAR_RELOAD_MATERIALS as i32;
+        const RELOAD_TEXTURES = FCVAR_RELOAD_TEXTURES as i32;
+        const NOT_CONNECTED = FCVAR_NOT_CONNECTED as i32;
+        const MATERIAL_SYSTEM_THREAD = FCVAR_MATERIAL_SYSTEM_THREAD as i32;
+        const ARCHIVE_PLAYERPROFILE = FCVAR_ARCHIVE_PLAYERPROFILE as i32;
+        const ACCESSIBLE_FROM_THREADS = FCVAR_ACCESSIBLE_FROM_THREADS as i32;
+        const SERVER_CAN_EXECUTE = FCVAR_SERVER_CAN_EXECUTE as i32;
+        const SERVER_CANNOT_QUERY = FCVAR_SERVER_CANNOT_QUERY as i32;
+        const CLIENTCMD_CAN_EXECUTE = FCVAR_CLIENTCMD_CAN_EXECUTE as i32;
+    }
+}
+
+impl std::fmt::Display for CVarFlags {
+    /// prints the names of the flags like `GAMEDLL | CHEAT` or `NONE`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            f.write_str("NONE")
+        } else {
+            bitflags::parser::to_writer(self, f)
+        }
+    }
+}
+
 create_external_interface! {
     pub RawCVar + Fffsf => {
         pub(self) fn unk0() -> ();
@@ -21,8 +75,8 @@ create_external_interface! {
         pub(self) fn unk7() -> ();
         pub(self) fn unk8() -> ();
         pub(self) fn unk9() -> ();
-        pub(self) fn unk10() -> ();
-        pub(self) fn unk11() -> ();
+        pub fn register_concommand_base(command_base: *mut ConCommandBase) -> (); // 0x10
+        pub fn unregister_concommand_base(command_base: *mut ConCommandBase) -> (); // 0x11
         pub(self) fn unk12() -> ();
         pub(self) fn unk13() -> ();
         pub fn find_command_base(command_name: *const c_char) -> *mut ConCommandBase; // 0x14
@@ -31,8 +85,8 @@ create_external_interface! {
         pub(self) fn unk16() -> ();
         pub fn find_concommand(concommand_name: *const c_char) -> *mut ConCommand; // 0x18
         pub(self) fn unk17() -> ();
-        pub(self) fn unk18() -> ();
-        pub(self) fn unk19() -> ();
+        pub fn install_global_change_callback(callback: FnChangeCallback_t) -> (); // 0x20
+        pub fn remove_global_change_callback(callback: FnChangeCallback_t) -> (); // 0x21
         pub(self) fn unk20() -> ();
         pub(self) fn unk21() -> ();
         pub(self) fn unk22() -> ();
@@ -71,11 +125,18 @@ impl From<*const c_void> for RawCvarIterator {
     }
 }
 
+impl RawCvarIterator {
+    /// the pointer to the engine iterator
+    pub const fn as_ptr(&self) -> *const c_void {
+        self.class
+    }
+}
+
 impl_vmethods! {
     impl WRAPPER RawCvarIterator {
         pub fn set_first() -> () where offset(0);
         pub fn next() -> () where offset(1);
-        pub fn is_valid() -> () where offset(2);
+        pub fn is_valid() -> bool where offset(2);
         pub fn get() -> *const ConCommandBase where offset(3);
     }
 }
@@ -37,6 +37,16 @@ impl<T, const U: usize> OffsetStructField<T, U> {
     pub fn get_inner_mut(&mut self) -> &mut T {
         &mut self.value
     }
+
+    /// a pointer to the field which isn't derived from a reference to the whole struct
+    ///
+    /// # Safety
+    ///
+    /// `this` has to point to a valid struct
+    pub const unsafe fn field_ptr(this: *mut std::mem::ManuallyDrop<Self>) -> *mut T {
+        // ManuallyDrop is transparent
+        unsafe { std::ptr::addr_of_mut!((*this.cast::<Self>()).value) }
+    }
 }
 
 impl<T: Copy + Clone, const U: usize> OffsetStructField<T, U> {
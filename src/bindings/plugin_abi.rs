@@ -5,6 +5,17 @@ use windows::Win32::Foundation::HMODULE;
 pub type CreateInterface =
     unsafe extern "C" fn(*const c_char, *mut InterfaceStatus) -> *const c_void;
 
+/// exported by rrplug plugins to list their interfaces; returns `false` once `index` is past the last interface
+pub type GetInterfaceInfo = unsafe extern "C" fn(usize, *mut RawInterfaceInfo) -> bool;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct RawInterfaceInfo {
+    pub name: *const c_char,
+    pub major: u32,
+    pub minor: u32,
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub enum InterfaceStatus {
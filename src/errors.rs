@@ -41,6 +41,14 @@ pub enum CVarQueryError {
 
     #[error("the cvar interface doesn't exists yet?")]
     NoCVarInterface,
+
+    /// only convars and concommands registered by this plugin can be unregistered
+    #[error("the cvar wasn't registered by this plugin")]
+    NotRegisteredByPlugin,
+
+    /// only concommands with a `FnCommandCallback_t` callback can be hooked
+    #[error("the concommand uses an old style or interface callback")]
+    UnsupportedCallback,
 }
 
 impl CVarQueryError {
@@ -50,6 +58,132 @@ impl CVarQueryError {
     }
 }
 
+/// Errors from parsing and scanning for patterns
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PatternError {
+    /// something in the pattern wasn't a hex byte or a wildcard
+    #[error("{0} is not a hex byte or a wildcard")]
+    InvalidByte(String),
+
+    /// the pattern has no concrete bytes
+    #[error("the pattern doesn't have any bytes")]
+    Empty,
+
+    /// the pattern wasn't found in the code sections of the dll
+    #[error("the pattern {0} wasn't found")]
+    NotFound(String),
+}
+
+impl PatternError {
+    /// logs the error with the builtin logger
+    pub fn log(&self) {
+        log::error!("{}", self)
+    }
+}
+
+/// Errors from checking that a loaded dll is the expected version
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum DllValidationError {
+    /// the headers of the dll couldn't be parsed
+    #[error("the dll doesn't have valid pe headers")]
+    InvalidHeaders,
+
+    /// the `TimeDateStamp` of the dll is different
+    #[error("expected the timestamp {expected:#X} but found {found:#X}")]
+    TimestampMismatch {
+        /// the expected timestamp
+        expected: u32,
+        /// the timestamp of the loaded dll
+        found: u32,
+    },
+
+    /// the `SizeOfImage` of the dll is different
+    #[error("expected the image size {expected:#X} but found {found:#X}")]
+    SizeMismatch {
+        /// the expected size
+        expected: u32,
+        /// the size of the loaded dll
+        found: u32,
+    },
+
+    /// the bytes at an offset didn't match a signature
+    #[error("the signature at {0:#X} didn't match")]
+    SignatureMismatch(usize),
+
+    /// an offset which should be a function isn't in an executable section
+    #[error("{0:#X} isn't in an executable section")]
+    NotCode(usize),
+
+    /// a pattern was invalid or wasn't found
+    #[error(transparent)]
+    Pattern(#[from] PatternError),
+}
+
+impl DllValidationError {
+    /// logs the error with the builtin logger
+    pub fn log(&self) {
+        log::error!("{}", self)
+    }
+}
+
+/// Errors that may happen when dumping convars and concommands
+#[derive(Error, Debug)]
+pub enum CVarDumpError {
+    /// the convars and concommands couldn't be collected
+    #[error(transparent)]
+    Query(#[from] CVarQueryError),
+
+    /// the dump couldn't be written
+    #[error("failed to write the dump: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl CVarDumpError {
+    /// logs the error with the builtin logger
+    pub fn log(&self) {
+        log::error!("{}", self)
+    }
+}
+
+/// Errors that may happen when running concommands
+#[derive(Error, Debug)]
+pub enum CommandError {
+    /// invalid cstring
+    #[error("some argument contained a null char")]
+    InvalidCString(#[from] NulError),
+
+    /// the command buffer functions weren't found
+    #[error("the cbuf functions weren't found yet")]
+    NoCbufFunctions,
+
+    /// a command can have at most 64 arguments including the command name
+    #[error("the command had too many arguments")]
+    TooManyArgs,
+
+    /// a command can be at most 512 characters long
+    #[error("the command was too long")]
+    TooLong,
+
+    /// the engine's tokenizer can't escape quotes so an arg can't contain one
+    #[error("some argument contained a quote")]
+    QuoteInArg,
+
+    /// the concommand has no callback to dispatch to
+    #[error("the concommand doesn't have a callback")]
+    NoCallback,
+
+    /// the concommand was unregistered
+    #[error("the concommand was unregistered")]
+    NotRegistered,
+}
+
+impl CommandError {
+    /// logs the error with the builtin logger
+    pub fn log(&self) {
+        log::error!("{}", self)
+    }
+}
+
 /// Errors created by calls to sqvm functions
 #[derive(Error, Debug)]
 pub enum CallError {
@@ -128,3 +262,266 @@ impl CompletionError {
         log::error!("{}", self)
     }
 }
+
+/// Errors that may happen when changing the protection of memory or allocating executable memory
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MemoryError {
+    /// the protection of the page couldn't be changed
+    #[error("couldn't change the protection of {0:#X}")]
+    Protect(usize),
+
+    /// executable memory couldn't be allocated
+    #[error("couldn't allocate {0} bytes of executable memory")]
+    Alloc(usize),
+}
+
+impl MemoryError {
+    /// logs the error with the builtin logger
+    pub fn log(&self) {
+        log::error!("{}", self)
+    }
+}
+
+/// Errors that may happen when creating or toggling hooks
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum HookError {
+    /// the memory of the hook couldn't be changed
+    #[error(transparent)]
+    Memory(#[from] MemoryError),
+
+    /// a pointer passed to the hook was null
+    #[error("the hook target was null")]
+    NullPointer,
+
+    /// something else already hooks this target
+    #[error("{0:#X} is already hooked")]
+    AlreadyHooked(usize),
+
+    /// the hook was already removed
+    #[error("the hook was already removed")]
+    NotHooked,
+
+    /// an instruction at the start of the function couldn't be decoded or moved
+    #[error("unsupported instruction at {0:#X}")]
+    UnsupportedInstruction(usize),
+
+    /// an instruction at the start of the function uses a rip relative operand which can't be moved
+    #[error("rip relative instruction at {0:#X} can't be moved")]
+    RipRelativeInstruction(usize),
+}
+
+impl HookError {
+    /// logs the error with the builtin logger
+    pub fn log(&self) {
+        log::error!("{}", self)
+    }
+}
+
+/// Errors that may happen when patching memory
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PatchError {
+    /// the memory couldn't be written
+    #[error(transparent)]
+    Memory(#[from] MemoryError),
+
+    /// the expected pattern was invalid
+    #[error(transparent)]
+    Pattern(#[from] PatternError),
+
+    /// the patch address was null
+    #[error("the patch address was null")]
+    NullPointer,
+
+    /// the patch isn't inside of the dll
+    #[error("{0:#X} is outside of the dll")]
+    OutOfBounds(usize),
+
+    /// the patch is longer than the expected bytes it replaces
+    #[error("the patch is {patch} bytes long but only {expected} bytes are checked")]
+    TooLong {
+        /// the length of the expected pattern
+        expected: usize,
+        /// the length of the patch
+        patch: usize,
+    },
+
+    /// the bytes at the address didn't match the expected pattern
+    #[error("the bytes at {0:#X} didn't match the expected pattern")]
+    Mismatch(usize),
+
+    /// another patch already changes some of these bytes
+    #[error("{0:#X} is already patched")]
+    AlreadyPatched(usize),
+}
+
+impl PatchError {
+    /// logs the error with the builtin logger
+    pub fn log(&self) {
+        log::error!("{}", self)
+    }
+}
+
+/// Errors from parsing a pdef schema
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PdefError {
+    /// a line couldn't be parsed
+    #[error("line {line}: expected {expected}")]
+    Syntax {
+        /// the line number starting at 1
+        line: usize,
+        /// what was expected instead
+        expected: &'static str,
+    },
+
+    /// a member used a type which isn't defined above it
+    #[error("line {0}: unknown type {1}")]
+    UnknownType(usize, String),
+
+    /// an enum, struct, variant or member was defined twice
+    #[error("line {0}: {1} is already defined")]
+    Duplicate(usize, String),
+
+    /// an array was sized with something that isn't a number or an enum
+    #[error("line {0}: invalid array size {1}")]
+    InvalidArraySize(usize, String),
+
+    /// an enum or a struct was never closed
+    #[error("{0} is never closed")]
+    Unterminated(String),
+
+    /// the size of a member or of the whole persistence doesn't fit in a usize
+    #[error("line {0}: the size is too large")]
+    TooLarge(usize),
+}
+
+impl PdefError {
+    /// logs the error with the builtin logger
+    pub fn log(&self) {
+        log::error!("{}", self)
+    }
+}
+
+/// Errors from reading or writing persistence with a pdef schema
+#[derive(Error, Debug, PartialEq)]
+pub enum PersistenceError {
+    /// the path couldn't be parsed
+    #[error("{0} isn't a valid path")]
+    InvalidPath(String),
+
+    /// the schema doesn't have the member
+    #[error("{0} doesn't exist")]
+    UnknownMember(String),
+
+    /// an array index is too big or isn't a variant of the enum the array is sized with
+    #[error("{index} is out of bounds for {path}")]
+    OutOfBounds {
+        /// the path to the array
+        path: String,
+        /// the index
+        index: String,
+    },
+
+    /// the path points to a struct or to an array without an index
+    #[error("{0} isn't a single value")]
+    NotAValue(String),
+
+    /// the value doesn't have the type of the member
+    #[error("{path} can't be set to {value:?}")]
+    TypeMismatch {
+        /// the path to the member
+        path: String,
+        /// the rejected value
+        value: crate::high::pdef::PdefValue,
+    },
+
+    /// a string is too long for the member; one byte is always kept for the null terminator
+    #[error("{path} can hold at most {max} bytes")]
+    StringTooLong {
+        /// the path to the member
+        path: String,
+        /// the max length
+        max: usize,
+    },
+
+    /// an enum member holds a value which isn't a variant
+    #[error("{path} has an invalid enum value {value}")]
+    InvalidEnumValue {
+        /// the path to the member
+        path: String,
+        /// the stored value
+        value: u8,
+    },
+
+    /// the buffer is smaller than the schema
+    #[error("the buffer is {len} bytes long but the schema needs {needed} bytes")]
+    BufferTooSmall {
+        /// the size of the schema
+        needed: usize,
+        /// the length of the buffer
+        len: usize,
+    },
+
+    /// the persistence of the client hasn't been received yet
+    #[error("the persistence isn't ready")]
+    NotReady,
+}
+
+impl PersistenceError {
+    /// logs the error with the builtin logger
+    pub fn log(&self) {
+        log::error!("{}", self)
+    }
+}
+
+/// Errors from looking up interfaces of this plugin or of other modules
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum InterfaceError {
+    /// invalid cstring
+    #[error("some attribute contained a null char")]
+    InvalidCString(#[from] NulError),
+
+    /// the module isn't loaded
+    #[error("{0} isn't loaded")]
+    ModuleNotFound(String),
+
+    /// the module doesn't export `CreateInterface`
+    #[error("{0} doesn't export CreateInterface")]
+    NoCreateInterface(String),
+
+    /// the module doesn't have the interface
+    #[error("{module} doesn't have the interface {interface}")]
+    NotFound {
+        /// the name of the module
+        module: String,
+        /// the name of the interface
+        interface: String,
+    },
+
+    /// this plugin didn't register the interface
+    #[error("the interface {0} isn't registered by this plugin")]
+    NotRegistered(String),
+
+    /// the interface was registered with another type
+    #[error("the interface {0} was registered with another type")]
+    TypeMismatch(String),
+
+    /// the interface exists but no version of it is compatible
+    #[error("no version of {interface} is compatible with {required}")]
+    IncompatibleVersion {
+        /// the name of the interface
+        interface: String,
+        /// the required version
+        required: crate::interfaces::interface::InterfaceVersion,
+    },
+
+    /// the module doesn't export a list of its interfaces; only rrplug plugins do
+    #[error("{0} doesn't export GetInterfaceInfo")]
+    NotEnumerable(String),
+}
+
+impl InterfaceError {
+    /// logs the error with the builtin logger
+    pub fn log(&self) {
+        log::error!("{}", self)
+    }
+}
@@ -0,0 +1,72 @@
+//! running commands through the engine's command buffer
+//!
+//! text added to a command buffer is executed by the engine on its next frame or when [`execute`] is called
+//!
+//! ```no_run
+//! use rrplug::prelude::*;
+//! use rrplug::high::engine::cbuf;
+//!
+//! # let engine_token = unsafe { EngineToken::new_unchecked() };
+//! cbuf::execute_command("map mp_forwardbase_kodai", engine_token).unwrap();
+//! ```
+
+use super::EngineToken;
+use crate::{
+    bindings::cvar::cbuf::{CmdSource, ECommandTarget},
+    errors::CommandError,
+    mid::{
+        engine::concommands::{CbufFunctions, CBUF_FUNCTIONS},
+        utils::try_cstring,
+    },
+};
+
+/// adds text to a command buffer
+///
+/// multiple commands can be separated by `;` or new lines
+///
+/// # Errors
+///
+/// this function will return an error if the cbuf functions weren't found or if the text contains a null char
+pub fn add_text(
+    text: &str,
+    target: ECommandTarget,
+    source: CmdSource,
+    _: EngineToken,
+) -> Result<(), CommandError> {
+    let text = try_cstring(text)?;
+    unsafe { (get_cbuf_functions()?.cbuf_add_text)(target as i32, text.as_ptr(), source as i32) };
+
+    Ok(())
+}
+
+/// queues a command on the server's command buffer like it was run from code
+///
+/// # Errors
+///
+/// this function will return an error if the cbuf functions weren't found or if the command contains a null char
+pub fn execute_command(command: &str, token: EngineToken) -> Result<(), CommandError> {
+    add_text(command, ECommandTarget::Server, CmdSource::Code, token)
+}
+
+/// executes everything in the command buffers now instead of waiting for the next frame
+///
+/// # Errors
+///
+/// this function will return an error if the cbuf functions weren't found
+pub fn execute(_: EngineToken) -> Result<(), CommandError> {
+    unsafe { (get_cbuf_functions()?.cbuf_execute)() };
+    Ok(())
+}
+
+/// the command buffer of the local player currently executing commands
+///
+/// # Errors
+///
+/// this function will return an error if the cbuf functions weren't found
+pub fn current_player(_: EngineToken) -> Result<Option<ECommandTarget>, CommandError> {
+    Ok(ECommandTarget::try_from(unsafe { (get_cbuf_functions()?.cbuf_get_current_player)() }).ok())
+}
+
+fn get_cbuf_functions() -> Result<&'static CbufFunctions, CommandError> {
+    CBUF_FUNCTIONS.get().ok_or(CommandError::NoCbufFunctions)
+}
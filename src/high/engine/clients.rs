@@ -0,0 +1,306 @@
+//! safe views over the server's connected clients
+//!
+//! ```no_run
+//! use rrplug::prelude::*;
+//! use rrplug::high::engine::clients::clients;
+//!
+//! # let engine_token = unsafe { EngineToken::new_unchecked() };
+//! for client in clients(engine_token).filter(|client| !client.is_fake()) {
+//!     log::info!(
+//!         "[{}] {} ({}) is {:?}",
+//!         client.clan_tag(),
+//!         client.name(),
+//!         client.uid(),
+//!         client.signon_state()
+//!     );
+//! }
+//! ```
+
+use std::{
+    ffi::c_char,
+    ptr::{addr_of_mut, NonNull},
+};
+
+use super::{
+    globalvars::global_vars,
+    keyvalues::{key_values_system, KeyValuesRef},
+    EngineToken,
+};
+use crate::{
+    bindings::{
+        class_types::client::{CClient, PersistenceReady, SignonState},
+        OffsetStructField,
+    },
+    errors::PersistenceError,
+    high::pdef::{persistence::PlayerPersistence, Pdef},
+    mid::{server::CLIENT_ARRAY, utils::str_from_char_array},
+};
+
+/// iterator over the occupied slots of the server's client array
+///
+/// created with [`clients`]
+#[derive(Clone)]
+pub struct Clients {
+    array: *const CClient,
+    max_clients: usize,
+    index: usize,
+    token: EngineToken,
+}
+
+/// a client in a slot of the server's client array
+#[derive(Clone, Copy)]
+pub struct Client {
+    client: NonNull<CClient>,
+    index: usize,
+    token: EngineToken,
+}
+
+/// iterates the connected clients of the server
+///
+/// the iterator is empty if engine.dll isn't loaded yet
+pub fn clients(token: EngineToken) -> Clients {
+    let array = CLIENT_ARRAY
+        .get()
+        .map(|array| array.client_array.cast_const())
+        .unwrap_or(std::ptr::null());
+    let max_clients = global_vars(token)
+        .map(|globals| globals.max_clients().max(0) as usize)
+        .unwrap_or_default();
+
+    // the client array is a static of engine.dll with room for every player
+    unsafe { Clients::new(array, max_clients, token) }
+}
+
+/// the client in a slot
+///
+/// returns [`None`] if the slot is empty or out of bounds
+pub fn client_by_index(index: usize, token: EngineToken) -> Option<Client> {
+    let mut clients = clients(token);
+    clients.index = index;
+    clients.next().filter(|client| client.index() == index)
+}
+
+impl Clients {
+    /// # Safety
+    ///
+    /// `array` has to be null or point to at least `max_clients` clients
+    pub(crate) const unsafe fn new(
+        array: *const CClient,
+        max_clients: usize,
+        token: EngineToken,
+    ) -> Self {
+        Self {
+            array,
+            max_clients,
+            index: 0,
+            token,
+        }
+    }
+}
+
+impl Iterator for Clients {
+    type Item = Client;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.array.is_null() {
+            return None;
+        }
+
+        while self.index < self.max_clients {
+            let index = self.index;
+            self.index += 1;
+
+            let client = Client {
+                // the array isn't null so neither are its slots
+                client: unsafe { NonNull::new_unchecked(self.array.add(index).cast_mut()) },
+                index,
+                token: self.token,
+            };
+
+            if client.signon_state() != SignonState::NONE {
+                return Some(client);
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.max_clients.saturating_sub(self.index)))
+    }
+}
+
+impl Client {
+    /// the slot of the client
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// the raw client
+    pub const fn get_raw(&self) -> &CClient {
+        unsafe { self.client.as_ref() }
+    }
+
+    /// the edict of the player entity
+    pub fn edict(&self) -> u16 {
+        unsafe { self.get_raw().edict.copy_inner() }
+    }
+
+    /// the name of the player
+    pub fn name(&self) -> &str {
+        str_from_char_array(unsafe { self.get_raw().name.get_inner() })
+    }
+
+    /// the unique id of the player
+    pub fn uid(&self) -> &str {
+        str_from_char_array(unsafe { self.get_raw().uid.get_inner() })
+    }
+
+    /// the clan tag of the player
+    pub fn clan_tag(&self) -> &str {
+        str_from_char_array(unsafe { self.get_raw().clan_tag.get_inner() })
+    }
+
+    /// how far the client is in connecting
+    pub fn signon_state(&self) -> SignonState {
+        unsafe { self.get_raw().signon.copy_inner() }
+    }
+
+    /// returns [`true`] for bots
+    pub fn is_fake(&self) -> bool {
+        unsafe { self.get_raw().fake_player.copy_inner() }
+    }
+
+    /// returns [`PersistenceReady::NotReady`] until the persistence of the client was received
+    pub fn persistence_ready(&self) -> PersistenceReady {
+        unsafe { self.get_raw().persistence_ready.copy_inner() }
+    }
+
+    /// the persistence of the client read with a schema
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the persistence isn't ready or if the schema is bigger than the buffer
+    pub fn persistence<'a, 'b>(
+        &'b self,
+        pdef: &'a Pdef,
+    ) -> Result<PlayerPersistence<'a, &'b [u8]>, PersistenceError> {
+        self.check_persistence_ready()?;
+
+        let buffer = unsafe { self.get_raw().persistence_buffer.get_inner() };
+        PlayerPersistence::new(pdef, unsafe {
+            std::slice::from_raw_parts(buffer.as_ptr().cast::<u8>(), buffer.len())
+        })
+    }
+
+    /// the persistence of the client which can be written with a schema
+    ///
+    /// # Safety
+    ///
+    /// nothing else can access the persistence buffer of this client while the returned value exists
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the persistence isn't ready or if the schema is bigger than the buffer
+    pub unsafe fn persistence_mut<'a, 'b>(
+        &'b mut self,
+        pdef: &'a Pdef,
+    ) -> Result<PlayerPersistence<'a, &'b mut [u8]>, PersistenceError> {
+        self.check_persistence_ready()?;
+
+        // built from the pointer into the client array since writing through a shared reference isn't allowed
+        let buffer = unsafe {
+            OffsetStructField::field_ptr(addr_of_mut!((*self.client.as_ptr()).persistence_buffer))
+        };
+        let buffer: *mut [c_char] = buffer;
+        PlayerPersistence::new(pdef, unsafe {
+            std::slice::from_raw_parts_mut(buffer.cast::<u8>(), buffer.len())
+        })
+    }
+
+    fn check_persistence_ready(&self) -> Result<(), PersistenceError> {
+        match self.persistence_ready() {
+            PersistenceReady::Ready | PersistenceReady::ReadyRemote => Ok(()),
+            PersistenceReady::NotReady => Err(PersistenceError::NotReady),
+        }
+    }
+
+    /// the user info convars the client sent like `name` and its `cl_` settings
+    ///
+    /// returns [`None`] if the client hasn't sent them yet
+    ///
+    /// # Safety
+    ///
+    /// the engine replaces the whole tree when the client resends its convars so the returned ref can't be kept past the current engine callback;
+    /// the [`KeyValues`](crate::bindings::class_types::keyvalues::KeyValues) and `IKeyValuesSystem` layouts are from the source sdk and aren't verified against titanfall 2 yet
+    pub unsafe fn con_vars(&self) -> Option<KeyValuesRef<'_>> {
+        let con_vars = unsafe { self.get_raw().con_vars.copy_inner().as_ref()? };
+
+        // the tree is owned by the client and replaced as a whole when it sends new convars
+        Some(unsafe { KeyValuesRef::with_system(con_vars, key_values_system()?, self.token) })
+    }
+}
+
+impl std::fmt::Debug for Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Client")
+            .field("index", &self.index())
+            .field("name", &self.name())
+            .field("uid", &self.uid())
+            .field("clan_tag", &self.clan_tag())
+            .field("signon_state", &self.signon_state())
+            .field("is_fake", &self.is_fake())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::high::engine::test::{fake_struct, write};
+
+    const CLIENT_SIZE: usize = std::mem::size_of::<CClient>();
+
+    #[test]
+    fn iterate_clients() {
+        let mut array = fake_struct(CLIENT_SIZE * 3);
+
+        write(&mut array, 0x16, b"cat_or_not\0");
+        write(&mut array, 0xF500, b"1007270968017\0");
+        write(&mut array, 0x358, b"RRP\0");
+        write(&mut array, 0x2A0, &(SignonState::FULL as i32).to_le_bytes());
+        write(&mut array, CLIENT_SIZE * 2 + 0x16, b"bot\0");
+        write(&mut array, CLIENT_SIZE * 2 + 0x484, &[1]);
+        write(
+            &mut array,
+            CLIENT_SIZE * 2 + 0x2A0,
+            &(SignonState::SPAWN as i32).to_le_bytes(),
+        );
+
+        let token = unsafe { EngineToken::new_unchecked() };
+        let clients =
+            unsafe { Clients::new(array.as_ptr().cast(), 3, token) }.collect::<Vec<Client>>();
+        assert_eq!(clients.len(), 2);
+
+        assert_eq!(clients[0].index(), 0);
+        assert_eq!(clients[0].name(), "cat_or_not");
+        assert_eq!(clients[0].uid(), "1007270968017");
+        assert_eq!(clients[0].clan_tag(), "RRP");
+        assert_eq!(clients[0].signon_state(), SignonState::FULL);
+        assert!(!clients[0].is_fake());
+
+        assert_eq!(clients[1].index(), 2);
+        assert_eq!(clients[1].name(), "bot");
+        assert_eq!(clients[1].clan_tag(), "");
+        assert!(clients[1].is_fake());
+
+        assert_eq!(
+            unsafe { Clients::new(array.as_ptr().cast(), 2, token) }.count(),
+            1
+        );
+        assert_eq!(
+            unsafe { Clients::new(std::ptr::null(), 2, token) }.count(),
+            0
+        );
+    }
+}
@@ -0,0 +1,452 @@
+//! hooks for existing concommands
+//!
+//! [`hook_concommand`] swaps the callback of a concommand for a rrplug trampoline which calls the hooks around the original callback.
+//! the original callback is restored when the last hook of a concommand is removed or when the plugin unloads unless something else replaced the trampoline in the meantime.
+//!
+//! only concommands using the `FnCommandCallback_t` callback can be hooked; ones with the old argless or the interface callback are rejected.
+//!
+//! ```no_run
+//! use rrplug::prelude::*;
+//! use rrplug::high::engine::concommand_hooks::{hook_concommand, HookKind, HookResult};
+//!
+//! # let engine_token = unsafe { EngineToken::new_unchecked() };
+//! let hook = hook_concommand(
+//!     "kick",
+//!     HookKind::Before,
+//!     |command, _| {
+//!         log::info!("kick called with {:?}", command.get_args());
+//!
+//!         if command.get_arg(0) == Some("cat_or_not") {
+//!             HookResult::Cancel
+//!         } else {
+//!             HookResult::Continue
+//!         }
+//!     },
+//!     engine_token,
+//! )
+//! .unwrap();
+//!
+//! // later
+//! hook.unhook();
+//! ```
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use super::{concommands::CCommandResult, EngineToken};
+use crate::{
+    bindings::cvar::command::{CCommand, ConCommand, FnCommandCallback_t},
+    errors::CVarQueryError,
+    high::UnsafeHandle,
+    mid::engine::concommands::find_concommand,
+};
+
+/// when a hook is called relative to the original callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookKind {
+    /// called before the original callback
+    Before,
+    /// called after the original callback
+    After,
+    /// called instead of the original callback
+    Replace,
+}
+
+/// returned by hooks to decide if the concommand should continue executing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum HookResult {
+    /// keep executing the hooks and the original callback
+    #[default]
+    Continue,
+    /// stops the execution here; the original callback and the remaining hooks are skipped
+    Cancel,
+}
+
+type Hook = Arc<dyn Fn(&CCommandResult, EngineToken) -> HookResult + Send + Sync>;
+
+struct HookedConCommand {
+    command: UnsafeHandle<*mut ConCommand>,
+    original: FnCommandCallback_t,
+    hooks: Vec<(u64, HookKind, Hook)>,
+}
+
+/// `m_nCallbackFlags` bits from the source sdk
+const USING_NEW_COMMAND_CALLBACK: i32 = 1 << 1;
+const USING_COMMAND_CALLBACK_INTERFACE: i32 = 1 << 2;
+
+/// hooked concommands keyed by their lowercase name
+static HOOKED_CONCOMMANDS: Lazy<Mutex<HashMap<String, HookedConCommand>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static NEXT_HOOK_ID: AtomicU64 = AtomicU64::new(0);
+
+/// handle to a hook added with [`hook_concommand`]
+///
+/// dropping the handle doesn't remove the hook
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct ConCommandHook {
+    command: String,
+    id: u64,
+}
+
+impl ConCommandHook {
+    /// removes the hook and restores the original callback if it was the last hook of the concommand
+    ///
+    /// returns [`false`] if the hook was already removed
+    pub fn unhook(self) -> bool {
+        let mut hooked = HOOKED_CONCOMMANDS.lock();
+        let Some(hooked_command) = hooked.get_mut(&self.command) else {
+            return false;
+        };
+
+        let len = hooked_command.hooks.len();
+        hooked_command.hooks.retain(|(id, _, _)| *id != self.id);
+        let removed = hooked_command.hooks.len() != len;
+
+        // if the trampoline was hooked by something else the entry stays so the trampoline can still call the original callback
+        if hooked_command.hooks.is_empty() && unsafe { hooked_command.restore() } {
+            hooked.remove(&self.command);
+        }
+
+        removed
+    }
+
+    /// returns [`true`] if the hook is still called
+    pub fn is_hooked(&self) -> bool {
+        HOOKED_CONCOMMANDS
+            .lock()
+            .get(&self.command)
+            .map(|hooked| hooked.hooks.iter().any(|(id, _, _)| *id == self.id))
+            .unwrap_or_default()
+    }
+}
+
+impl HookedConCommand {
+    /// restores the original callback if the trampoline is still in place
+    ///
+    /// returns [`false`] if the callback was replaced by something else
+    unsafe fn restore(&self) -> bool {
+        let command = self.command.copy();
+        let is_trampoline = unsafe { (*command).m_pCommandCallback }.is_some_and(|callback| {
+            callback as usize == concommand_hook_trampoline as unsafe extern "C" fn(_) as usize
+        });
+
+        if is_trampoline {
+            unsafe { (*command).m_pCommandCallback = self.original };
+        }
+
+        is_trampoline
+    }
+}
+
+/// hooks a concommand by name
+///
+/// the hook gets the parsed command and can cancel the execution with [`HookResult::Cancel`]
+///
+/// # Errors
+///
+/// this function will return an error if the concommand doesn't exist, if the cvar interface doesn't exist yet or if the concommand doesn't use a `FnCommandCallback_t`
+pub fn hook_concommand(
+    name: &str,
+    kind: HookKind,
+    hook: impl Fn(&CCommandResult, EngineToken) -> HookResult + Send + Sync + 'static,
+    _: EngineToken,
+) -> Result<ConCommandHook, CVarQueryError> {
+    let command = find_concommand(name)?;
+    add_hook(command, name, kind, Arc::new(hook))
+}
+
+fn add_hook(
+    command: *mut ConCommand,
+    name: &str,
+    kind: HookKind,
+    hook: Hook,
+) -> Result<ConCommandHook, CVarQueryError> {
+    // the trampoline only has the signature of the new callback
+    let callback_flags = unsafe { (*command).m_nCallbackFlags };
+    if callback_flags & USING_NEW_COMMAND_CALLBACK == 0
+        || callback_flags & USING_COMMAND_CALLBACK_INTERFACE != 0
+    {
+        return Err(CVarQueryError::UnsupportedCallback);
+    }
+
+    let name = name.to_lowercase();
+    let id = NEXT_HOOK_ID.fetch_add(1, Ordering::Relaxed);
+
+    HOOKED_CONCOMMANDS
+        .lock()
+        .entry(name.clone())
+        .or_insert_with(|| {
+            let original = unsafe { (*command).m_pCommandCallback };
+            unsafe { (*command).m_pCommandCallback = Some(concommand_hook_trampoline) };
+
+            HookedConCommand {
+                command: UnsafeHandle::internal_new(command),
+                original,
+                hooks: Vec::new(),
+            }
+        })
+        .hooks
+        .push((id, kind, hook));
+
+    Ok(ConCommandHook { command: name, id })
+}
+
+/// removes every hook and restores the original callbacks
+pub(crate) fn unhook_all() {
+    for (name, hooked_command) in HOOKED_CONCOMMANDS.lock().drain() {
+        if !unsafe { hooked_command.restore() } {
+            log::warn!(
+                "couldn't restore the callback of {name} since it was hooked by something else"
+            );
+        }
+    }
+}
+
+/// the callback of every hooked concommand
+unsafe extern "C" fn concommand_hook_trampoline(ccommand: *const CCommand) {
+    let command = unsafe { CCommandResult::new(ccommand) };
+
+    // cloned so hooks can add or remove hooks
+    let (original, hooks) = match HOOKED_CONCOMMANDS
+        .lock()
+        .get(&command.get_command().to_lowercase())
+    {
+        Some(hooked) => (hooked.original, hooked.hooks.clone()),
+        None => return,
+    };
+
+    let token = unsafe { EngineToken::new_unchecked() };
+    let run_hooks = |kind: HookKind| {
+        hooks
+            .iter()
+            .filter(|(_, hook_kind, _)| *hook_kind == kind)
+            .all(|(_, _, hook)| hook(&command, token) == HookResult::Continue)
+    };
+
+    if !run_hooks(HookKind::Before) {
+        return;
+    }
+
+    if hooks.iter().any(|(_, kind, _)| *kind == HookKind::Replace) {
+        if !run_hooks(HookKind::Replace) {
+            return;
+        }
+    } else if let Some(original) = original {
+        unsafe { original(ccommand) }
+    }
+
+    run_hooks(HookKind::After);
+}
+
+#[cfg(test)]
+mod test {
+    use std::{ffi::CString, mem::MaybeUninit};
+
+    use super::*;
+
+    static CALLS: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+    unsafe extern "C" fn original_callback(_: *const CCommand) {
+        CALLS.lock().push("original")
+    }
+
+    #[test]
+    fn hook_order_and_restore() {
+        let mut command = unsafe { MaybeUninit::<ConCommand>::zeroed().assume_init() };
+        command.m_pCommandCallback = Some(original_callback);
+        command.m_nCallbackFlags = USING_NEW_COMMAND_CALLBACK;
+        let command_ptr: *mut ConCommand = &mut command;
+
+        let mut ccommand = unsafe { MaybeUninit::<CCommand>::zeroed().assume_init() };
+        let args = CString::new("test_hooked_command arg").unwrap();
+        for (i, c) in args.as_bytes_with_nul().iter().enumerate() {
+            ccommand.m_pArgSBuffer[i] = *c as i8;
+        }
+        ccommand.m_nArgv0Size = "test_hooked_command".len() as i64;
+
+        let before = add_hook(
+            command_ptr,
+            "test_hooked_command",
+            HookKind::Before,
+            Arc::new(|command, _| {
+                CALLS.lock().push("before");
+                if command.get_arg(0) == Some("cancel") {
+                    HookResult::Cancel
+                } else {
+                    HookResult::Continue
+                }
+            }),
+        )
+        .unwrap();
+        let after = add_hook(
+            command_ptr,
+            "test_hooked_command",
+            HookKind::After,
+            Arc::new(|_, _| {
+                CALLS.lock().push("after");
+                HookResult::Continue
+            }),
+        )
+        .unwrap();
+        assert_eq!(
+            unsafe { (*command_ptr).m_pCommandCallback }.map(|callback| callback as usize),
+            Some(concommand_hook_trampoline as unsafe extern "C" fn(_) as usize)
+        );
+
+        unsafe { concommand_hook_trampoline(&ccommand) };
+        assert_eq!(*CALLS.lock(), ["before", "original", "after"]);
+
+        assert!(before.unhook());
+        assert!(after.is_hooked());
+        assert!(after.unhook());
+        assert_eq!(
+            unsafe { (*command_ptr).m_pCommandCallback }.map(|callback| callback as usize),
+            Some(original_callback as unsafe extern "C" fn(_) as usize)
+        );
+    }
+
+    static CANCEL_CALLS: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+    unsafe extern "C" fn cancel_original(_: *const CCommand) {
+        CANCEL_CALLS.lock().push("original")
+    }
+
+    unsafe extern "C" fn other_callback(_: *const CCommand) {}
+
+    fn fake_command(
+        callback: unsafe extern "C" fn(*const CCommand),
+        args: &str,
+    ) -> (Box<ConCommand>, Box<CCommand>) {
+        let mut command = Box::new(unsafe { MaybeUninit::<ConCommand>::zeroed().assume_init() });
+        command.m_pCommandCallback = Some(callback);
+        command.m_nCallbackFlags = USING_NEW_COMMAND_CALLBACK;
+
+        let mut ccommand = Box::new(unsafe { MaybeUninit::<CCommand>::zeroed().assume_init() });
+        for (i, c) in CString::new(args)
+            .unwrap()
+            .as_bytes_with_nul()
+            .iter()
+            .enumerate()
+        {
+            ccommand.m_pArgSBuffer[i] = *c as i8;
+        }
+        ccommand.m_nArgv0Size = args.split(' ').next().unwrap_or_default().len() as i64;
+
+        (command, ccommand)
+    }
+
+    fn push_hook(name: &'static str, result: HookResult) -> Hook {
+        Arc::new(move |_, _| {
+            CANCEL_CALLS.lock().push(name);
+            result
+        })
+    }
+
+    #[test]
+    fn cancel_and_replace() {
+        let (mut command, ccommand) = fake_command(cancel_original, "test_cancel_command");
+        let command_ptr: *mut ConCommand = &mut *command;
+        let name = "test_cancel_command";
+
+        // a canceling before hook skips everything after it
+        let before = add_hook(
+            command_ptr,
+            name,
+            HookKind::Before,
+            push_hook("before", HookResult::Cancel),
+        )
+        .unwrap();
+        let after = add_hook(
+            command_ptr,
+            name,
+            HookKind::After,
+            push_hook("after", HookResult::Continue),
+        )
+        .unwrap();
+        unsafe { concommand_hook_trampoline(&*ccommand) };
+        assert_eq!(std::mem::take(&mut *CANCEL_CALLS.lock()), ["before"]);
+        assert!(before.unhook());
+
+        // a replace hook runs instead of the original
+        let replace = add_hook(
+            command_ptr,
+            name,
+            HookKind::Replace,
+            push_hook("replace", HookResult::Continue),
+        )
+        .unwrap();
+        unsafe { concommand_hook_trampoline(&*ccommand) };
+        assert_eq!(
+            std::mem::take(&mut *CANCEL_CALLS.lock()),
+            ["replace", "after"]
+        );
+        assert!(replace.unhook());
+
+        // and can cancel the after hooks
+        let replace = add_hook(
+            command_ptr,
+            name,
+            HookKind::Replace,
+            push_hook("replace", HookResult::Cancel),
+        )
+        .unwrap();
+        unsafe { concommand_hook_trampoline(&*ccommand) };
+        assert_eq!(std::mem::take(&mut *CANCEL_CALLS.lock()), ["replace"]);
+        assert!(replace.unhook());
+
+        unsafe { concommand_hook_trampoline(&*ccommand) };
+        assert_eq!(
+            std::mem::take(&mut *CANCEL_CALLS.lock()),
+            ["original", "after"]
+        );
+        assert!(after.unhook());
+    }
+
+    #[test]
+    fn restore_and_unsupported_callbacks() {
+        let (mut command, _) = fake_command(other_callback, "test_restore_command");
+        let command_ptr: *mut ConCommand = &mut *command;
+
+        // something else hooked the trampoline so it has to stay in place
+        let hook = add_hook(
+            command_ptr,
+            "test_restore_command",
+            HookKind::Before,
+            Arc::new(|_, _| HookResult::Continue),
+        )
+        .unwrap();
+        command.m_pCommandCallback = Some(cancel_original);
+        assert!(hook.unhook());
+        assert_eq!(
+            command.m_pCommandCallback.map(|callback| callback as usize),
+            Some(cancel_original as unsafe extern "C" fn(_) as usize)
+        );
+        assert!(HOOKED_CONCOMMANDS
+            .lock()
+            .contains_key("test_restore_command"));
+        HOOKED_CONCOMMANDS.lock().remove("test_restore_command");
+
+        for flags in [
+            0,
+            USING_NEW_COMMAND_CALLBACK | USING_COMMAND_CALLBACK_INTERFACE,
+        ] {
+            command.m_nCallbackFlags = flags;
+            assert!(matches!(
+                add_hook(
+                    command_ptr,
+                    "test_unsupported_command",
+                    HookKind::Before,
+                    Arc::new(|_, _| HookResult::Continue),
+                ),
+                Err(CVarQueryError::UnsupportedCallback)
+            ));
+        }
+    }
+}
@@ -12,7 +12,7 @@
 //!         return;
 //!     };
 //!
-//!     engine_data.register_concommand("boom", explode, "displays a explosion in the console", 0, engine_token); // register the concommand
+//!     engine_data.register_concommand("boom", explode, "displays a explosion in the console", CVarFlags::empty(), engine_token); // register the concommand
 //! }
 //!
 //! // concommand use callback
@@ -39,14 +39,24 @@
 
 use std::ffi::{c_char, CStr};
 
+use super::EngineToken;
 use crate::{
     bindings::cvar::command::{
-        CCommand, COMMAND_COMPLETION_ITEM_LENGTH, COMMAND_COMPLETION_MAXITEMS,
+        CCommand, ConCommand, COMMAND_COMPLETION_ITEM_LENGTH, COMMAND_COMPLETION_MAXITEMS,
+    },
+    errors::{CVarQueryError, CommandError, CompletionError},
+    mid::{
+        engine::{concommands::build_ccommand, tracking},
+        utils::set_c_char_array,
     },
-    errors::CompletionError,
-    mid::utils::set_c_char_array,
 };
 
+/// handle to a concommand registered by this plugin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConCommandHandle {
+    command: *mut ConCommand,
+}
+
 /// [`CCommandResult`] gets all the usefull stuff from [`*const CCommand`] and puts in this struct
 #[derive(Debug, Default)]
 pub struct CCommandResult {
@@ -145,6 +155,75 @@ impl CommandCompletion<'_> {
     }
 }
 
+impl ConCommandHandle {
+    pub(crate) const fn new(command: *mut ConCommand) -> Self {
+        Self { command }
+    }
+
+    /// returns a pointer to [`ConCommand`] which is unsafe to access
+    pub const fn get_ptr(&self) -> *mut ConCommand {
+        self.command
+    }
+
+    /// returns [`true`] if the concommand wasn't unregistered yet
+    pub fn is_registered(&self) -> bool {
+        tracking::is_tracked(self.command.cast())
+    }
+
+    /// unregisters the concommand and frees it
+    ///
+    /// concommands are also unregistered automatically when the plugin unloads
+    ///
+    /// # Safety
+    ///
+    /// the pointer from [`ConCommandHandle::get_ptr`] or copies of this handle cannot be used after this
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the concommand was already unregistered
+    pub unsafe fn unregister(self, _: EngineToken) -> Result<(), CVarQueryError> {
+        unsafe { tracking::unregister(self.command.cast()) }
+    }
+
+    /// calls the concommand with `args`; see [`ConCommand::dispatch`]
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the concommand was unregistered or if it couldn't be dispatched
+    pub fn dispatch(&self, args: &[&str], token: EngineToken) -> Result<(), CommandError> {
+        if !self.is_registered() {
+            return Err(CommandError::NotRegistered);
+        }
+
+        unsafe { &*self.command }.dispatch(args, token)
+    }
+}
+
+impl ConCommand {
+    /// calls the callback of the concommand directly with `args` instead of going through the command buffer
+    ///
+    /// the command name is added as the first arg of the [`CCommand`]
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the concommand has no callback or if a [`CCommand`] couldn't be built from the args
+    pub fn dispatch(&self, args: &[&str], _: EngineToken) -> Result<(), CommandError> {
+        let callback = self.m_pCommandCallback.ok_or(CommandError::NoCallback)?;
+        let name = if self._base.m_pszName.is_null() {
+            ""
+        } else {
+            unsafe { CStr::from_ptr(self._base.m_pszName) }
+                .to_str()
+                .unwrap_or_default()
+        };
+
+        let ccommand = build_ccommand(&[&[name], args].concat())?;
+        unsafe { callback(&*ccommand) };
+
+        Ok(())
+    }
+}
+
 impl CurrentCommand<'_> {
     pub fn new(partial: *const c_char) -> Option<Self> {
         let partial = unsafe { CStr::from_ptr(partial).to_str() }.ok()?;
@@ -0,0 +1,267 @@
+//! closure listeners for convar changes
+//!
+//! unlike the [`crate::convar`] callback, any amount of listeners can be added to a convar from anywhere in the plugin.
+//! this also works for convars owned by the engine or by other plugins.
+//!
+//! the old and new values are decoded into the type requested by the listener with [`ConVarValue`]
+//!
+//! ```no_run
+//! use rrplug::prelude::*;
+//!
+//! # let engine_token = unsafe { EngineToken::new_unchecked() };
+//! let convar = ConVarStruct::find_convar_by_name("sv_cheats", engine_token).unwrap();
+//!
+//! let listener = convar
+//!     .on_change(
+//!         |old: bool, new: bool, _| log::info!("sv_cheats changed from {old} to {new}"),
+//!         engine_token,
+//!     )
+//!     .unwrap();
+//!
+//! // later
+//! listener.unsubscribe();
+//! ```
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    ffi::{c_char, CStr},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use super::{
+    typed::{ConVarValue, TypedConVar},
+    ConVarStruct,
+};
+use crate::{
+    bindings::cvar::convar::ConVar, errors::CVarQueryError, high::engine::EngineToken,
+    mid::engine::get_engine_data,
+};
+
+type Listener = Arc<dyn Fn(&ConVarStruct, &str, f32, EngineToken) + Send + Sync>;
+type ListenerMap = HashMap<usize, Vec<(u64, Listener)>>;
+
+/// listeners keyed by the address of their convar
+static CONVAR_LISTENERS: Lazy<Mutex<ListenerMap>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static NEXT_LISTENER_ID: AtomicU64 = AtomicU64::new(0);
+static DISPATCH_INSTALLED: Mutex<bool> = Mutex::new(false);
+
+/// handle to a listener added with [`ConVarStruct::on_change`]
+///
+/// dropping the handle doesn't remove the listener
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct ConVarListener {
+    convar: usize,
+    id: u64,
+}
+
+impl ConVarListener {
+    /// removes the listener from its convar
+    ///
+    /// returns [`false`] if the listener was already removed
+    pub fn unsubscribe(self) -> bool {
+        let mut listeners = CONVAR_LISTENERS.lock();
+        let Some(convar_listeners) = listeners.get_mut(&self.convar) else {
+            return false;
+        };
+
+        let len = convar_listeners.len();
+        convar_listeners.retain(|(id, _)| *id != self.id);
+        let removed = convar_listeners.len() != len;
+
+        if convar_listeners.is_empty() {
+            listeners.remove(&self.convar);
+        }
+
+        removed
+    }
+
+    /// returns [`true`] if the listener is still called on changes
+    pub fn is_subscribed(&self) -> bool {
+        CONVAR_LISTENERS
+            .lock()
+            .get(&self.convar)
+            .map(|listeners| listeners.iter().any(|(id, _)| *id == self.id))
+            .unwrap_or_default()
+    }
+}
+
+impl ConVarStruct {
+    /// adds a listener which is called after the value of the convar changes
+    ///
+    /// the old and new values are decoded as `T`; the listener is skipped if one of them can't be decoded
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the cvar interface doesn't exist yet
+    pub fn on_change<T: ConVarValue + 'static>(
+        &self,
+        listener: impl Fn(T, T, EngineToken) + Send + Sync + 'static,
+        token: EngineToken,
+    ) -> Result<ConVarListener, CVarQueryError> {
+        install_dispatch(token)?;
+
+        Ok(add_listener(
+            &*self.inner as *const ConVar as usize,
+            Arc::new(move |convar, old_value, old_float, token| {
+                let (Some(old), Some(new)) = (
+                    T::from_old_value(old_value, old_float),
+                    T::get_from_convar(convar),
+                ) else {
+                    return;
+                };
+
+                listener(old, new, token)
+            }),
+        ))
+    }
+
+    /// removes every listener of this convar
+    pub fn clear_listeners(&self) {
+        CONVAR_LISTENERS
+            .lock()
+            .remove(&(&*self.inner as *const ConVar as usize));
+    }
+}
+
+impl<T: ConVarValue + 'static> TypedConVar<T> {
+    /// adds a listener which is called after the value of the convar changes
+    ///
+    /// refer to [`ConVarStruct::on_change`]
+    pub fn on_change(
+        &self,
+        listener: impl Fn(T, T, EngineToken) + Send + Sync + 'static,
+        token: EngineToken,
+    ) -> Result<ConVarListener, CVarQueryError> {
+        self.get_convar_struct().on_change(listener, token)
+    }
+}
+
+fn install_dispatch(_: EngineToken) -> Result<(), CVarQueryError> {
+    let mut installed = DISPATCH_INSTALLED.lock();
+    if !*installed {
+        let cvar = get_engine_data()
+            .ok_or(CVarQueryError::NoCVarInterface)?
+            .get_cvar();
+
+        unsafe { cvar.install_global_change_callback(Some(convar_change_dispatch)) };
+        *installed = true;
+    }
+
+    Ok(())
+}
+
+/// removes the global change callback so the engine doesn't call into an unloaded plugin
+pub(crate) fn uninstall_dispatch() {
+    let mut installed = DISPATCH_INSTALLED.lock();
+    if let (true, Some(engine_data)) = (*installed, get_engine_data()) {
+        unsafe {
+            engine_data
+                .get_cvar()
+                .remove_global_change_callback(Some(convar_change_dispatch))
+        };
+        *installed = false;
+    }
+
+    CONVAR_LISTENERS.lock().clear();
+}
+
+fn add_listener(convar: usize, listener: Listener) -> ConVarListener {
+    let id = NEXT_LISTENER_ID.fetch_add(1, Ordering::Relaxed);
+
+    CONVAR_LISTENERS
+        .lock()
+        .entry(convar)
+        .or_default()
+        .push((id, listener));
+
+    ConVarListener { convar, id }
+}
+
+/// the global change callback which calls the listeners of the convar
+unsafe extern "C" fn convar_change_dispatch(
+    convar: *mut ConVar,
+    old_value: *const c_char,
+    old_float: f32,
+) {
+    // cloned so listeners can add or remove listeners and change convars
+    let listeners = match CONVAR_LISTENERS.lock().get(&(convar as usize)) {
+        Some(listeners) => listeners
+            .iter()
+            .map(|(_, listener)| listener.clone())
+            .collect::<Vec<Listener>>(),
+        None => return,
+    };
+
+    let Some(convar) = (unsafe { convar.as_mut() }) else {
+        return;
+    };
+    let convar = ConVarStruct { inner: convar };
+
+    let old_value = if old_value.is_null() {
+        "".into()
+    } else {
+        unsafe { CStr::from_ptr(old_value) }.to_string_lossy()
+    };
+
+    let token = unsafe { EngineToken::new_unchecked() };
+    for listener in listeners {
+        listener(&convar, &old_value, old_float, token)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        ffi::CString,
+        mem::MaybeUninit,
+        sync::atomic::{AtomicI32, Ordering},
+    };
+
+    use super::*;
+
+    #[test]
+    fn dispatch_to_listeners() {
+        static LAST_CHANGE: Mutex<Option<(i32, i32)>> = Mutex::new(None);
+        static CALLS: AtomicI32 = AtomicI32::new(0);
+
+        let new_value = CString::new("8").unwrap();
+        let old_value = CString::new("4").unwrap();
+
+        let mut convar = unsafe { MaybeUninit::<ConVar>::zeroed().assume_init() };
+        convar.m_Value.m_pszString = new_value.as_ptr();
+        convar.m_Value.m_nValue = 8;
+        let convar_ptr: *mut ConVar = &mut convar;
+
+        let first = add_listener(
+            convar_ptr as usize,
+            Arc::new(|convar, old_value, old_float, _| {
+                *LAST_CHANGE.lock() = Some((
+                    i32::from_old_value(old_value, old_float).unwrap(),
+                    i32::get_from_convar(convar).unwrap(),
+                ))
+            }),
+        );
+        let second = add_listener(
+            convar_ptr as usize,
+            Arc::new(|_, _, _, _| _ = CALLS.fetch_add(1, Ordering::Relaxed)),
+        );
+
+        unsafe { convar_change_dispatch(convar_ptr, old_value.as_ptr(), 4.) };
+        assert_eq!(*LAST_CHANGE.lock(), Some((4, 8)));
+        assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+
+        assert!(first.is_subscribed());
+        assert!(first.unsubscribe());
+
+        unsafe { convar_change_dispatch(convar_ptr, old_value.as_ptr(), 4.) };
+        assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+
+        assert!(second.unsubscribe());
+        assert!(!CONVAR_LISTENERS.lock().contains_key(&(convar_ptr as usize)));
+    }
+}
@@ -5,6 +5,12 @@
 //! 2. `int` (`i32`)
 //! 3. `float` (`f32`)
 //!
+//! [`typed::TypedConVar`] can be used to bind a convar to a single rust type
+//!
+//! changes of any convar can be listened to with [`ConVarStruct::on_change`]
+//!
+//! values of plugin convars can be saved between launches with [`persistence::ConVarPersistence`]
+//!
 //! ## Safety
 //!
 //! convars are also being accessed by the sqvm or the engine so it is unsafe to read or edit them from threads.
@@ -18,6 +24,8 @@
 //!
 //! convars can be created at any time after engine load but its better to create them when the engine loads
 //!
+//! the simplest way is to declare them at module scope with [`crate::static_convar`] which registers them when the engine loads
+//!
 //! ```no_run
 //! use rrplug::prelude::*;
 //! use rrplug::exports::OnceCell; // just as a example
@@ -32,7 +40,7 @@
 //!         ..ConVarRegister::mandatory(
 //!         "cool_convar",
 //!         "cool_default",
-//!         0,
+//!         CVarFlags::empty(),
 //!         "this is a cool convar",
 //!     )
 //!     };
@@ -64,12 +72,19 @@ use std::{
     ptr::addr_of_mut,
 };
 
+pub mod listeners;
+pub mod persistence;
+pub mod typed;
+
 use super::EngineData;
 use crate::{
-    bindings::cvar::convar::{ConVar, FnChangeCallback_t, FCVAR_NEVER_AS_STRING},
-    errors::{CStringPtrError, RegisterError},
+    bindings::cvar::{
+        convar::{ConVar, FnChangeCallback_t},
+        CVarFlags,
+    },
+    errors::{CStringPtrError, CVarQueryError, RegisterError},
     mid::{
-        engine::{get_engine_data, ENGINE_DATA},
+        engine::{get_engine_data, tracking, ENGINE_DATA},
         source_alloc::SOURCE_ALLOC,
         utils::{to_cstring, try_cstring},
     },
@@ -103,10 +118,10 @@ pub struct ConVarRegister {
     ///
     /// This is **required**
     pub default_value: String,
-    /// any flags like [`crate::bindings::cvar::convar::FCVAR_GAMEDLL`]
+    /// any flags like [`CVarFlags::GAMEDLL`]
     ///
     /// This is **required**
-    pub flags: i32,
+    pub flags: CVarFlags,
     /// the help string
     pub help_string: &'static str,
     /// should use min or not
@@ -126,7 +141,7 @@ impl ConVarRegister {
     pub fn new(
         name: impl Into<String>,
         default_value: impl Into<String>,
-        flags: i32,
+        flags: CVarFlags,
         help_string: &'static str,
     ) -> Self {
         Self::mandatory(name, default_value, flags, help_string)
@@ -141,7 +156,7 @@ impl ConVarRegister {
     ///     ..ConVarRegister::mandatory(
     ///     "a_convar",
     ///     "default_value",
-    ///     0,
+    ///     CVarFlags::empty(),
     ///     "this is a convar",
     /// )
     /// };
@@ -149,7 +164,7 @@ impl ConVarRegister {
     pub fn mandatory(
         name: impl Into<String>,
         default_value: impl Into<String>,
-        flags: i32,
+        flags: CVarFlags,
         help_string: &'static str,
     ) -> Self {
         Self {
@@ -198,7 +213,7 @@ impl ConVarStruct {
     ///     ..ConVarRegister::mandatory(
     ///     "a_convar",
     ///     "default_value",
-    ///     0,
+    ///     CVarFlags::empty(),
     ///     "this is a convar",
     /// )
     /// };
@@ -216,6 +231,15 @@ impl ConVarStruct {
         engine_data: &EngineData,
         register_info: &ConVarRegister,
     ) -> Result<Self, RegisterError> {
+        debug_assert!(!register_info.name.is_empty());
+
+        // TODO: could be optimized to not allocated a cstring
+
+        // converted first so nothing is leaked if a string is invalid
+        let name = try_cstring(&register_info.name)?.into_bytes_with_nul();
+        let default_value = try_cstring(&register_info.default_value)?.into_bytes_with_nul();
+        let help_string = try_cstring(register_info.help_string)?.into_bytes_with_nul();
+
         let convar_classes = engine_data.convar;
         let convar = unsafe {
             let convar = SOURCE_ALLOC.alloc(Layout::new::<ConVar>()) as *mut ConVar;
@@ -232,11 +256,6 @@ impl ConVarStruct {
             convar
         };
 
-        debug_assert!(!register_info.name.is_empty());
-
-        // TODO: could be optimized to not allocated a cstring
-
-        let name = try_cstring(&register_info.name)?.into_bytes_with_nul();
         let name_ptr =
             unsafe {
                 SOURCE_ALLOC.alloc(Layout::array::<c_char>(name.len()).expect(
@@ -245,7 +264,6 @@ impl ConVarStruct {
             };
         unsafe { name_ptr.copy_from_nonoverlapping(name.as_ptr(), name.len()) };
 
-        let default_value = try_cstring(&register_info.default_value)?.into_bytes_with_nul();
         let default_value_ptr =
             unsafe {
                 SOURCE_ALLOC.alloc(Layout::array::<c_char>(default_value.len()).expect(
@@ -256,7 +274,6 @@ impl ConVarStruct {
             default_value_ptr.copy_from_nonoverlapping(default_value.as_ptr(), default_value.len())
         };
 
-        let help_string = try_cstring(register_info.help_string)?.into_bytes_with_nul();
         let help_string_ptr =
             unsafe {
                 SOURCE_ALLOC.alloc(Layout::array::<c_char>(help_string.len()).expect(
@@ -272,7 +289,7 @@ impl ConVarStruct {
                 convar,
                 name_ptr as *const i8,
                 default_value_ptr as *const i8,
-                register_info.flags,
+                register_info.flags.bits(),
                 help_string_ptr as *const i8,
                 register_info.bmin,
                 register_info.fmin,
@@ -282,6 +299,8 @@ impl ConVarStruct {
             )
         }
 
+        tracking::track_convar(convar);
+
         log::info!("Registering ConVar {}", register_info.name);
 
         Ok(Self {
@@ -303,6 +322,33 @@ impl ConVarStruct {
         })
     }
 
+    /// unregisters the convar and frees it
+    ///
+    /// only convars created by this plugin can be unregistered; they are also unregistered automatically when the plugin unloads
+    ///
+    /// # Safety
+    ///
+    /// no other [`ConVarStruct`] or [`typed::TypedConVar`] of this convar can be used after this
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the convar wasn't registered by this plugin
+    pub unsafe fn unregister(self, _: EngineToken) -> Result<(), CVarQueryError> {
+        self.clear_listeners();
+        unsafe { tracking::unregister(&self.inner.m_ConCommandBase) }
+    }
+
+    /// wraps a raw convar pointer
+    ///
+    /// # Safety
+    ///
+    /// the pointer must be null or point to a valid registered convar
+    pub(crate) unsafe fn from_raw(convar: *mut ConVar) -> Option<Self> {
+        Some(Self {
+            inner: unsafe { convar.as_mut()? },
+        })
+    }
+
     /// get the name of the convar
     pub fn get_name(&self) -> String {
         unsafe {
@@ -317,18 +363,14 @@ impl ConVarStruct {
         unsafe {
             let value = &self.inner.m_Value;
 
-            let string = if !value.m_pszString.is_null()
-                && !self.has_flags(
-                    FCVAR_NEVER_AS_STRING
-                        .try_into()
-                        .expect("supposed to always work"),
-                ) {
-                CStr::from_ptr(value.m_pszString)
-                    .to_string_lossy()
-                    .to_string()
-            } else {
-                "".to_string()
-            };
+            let string =
+                if !value.m_pszString.is_null() && !self.has_flags(CVarFlags::NEVER_AS_STRING) {
+                    CStr::from_ptr(value.m_pszString)
+                        .to_string_lossy()
+                        .to_string()
+                } else {
+                    "".to_string()
+                };
 
             ConVarValues {
                 value: string,
@@ -342,13 +384,7 @@ impl ConVarStruct {
         unsafe {
             let value = &self.inner.m_Value;
 
-            if value.m_pszString.is_null()
-                || self.has_flags(
-                    FCVAR_NEVER_AS_STRING
-                        .try_into()
-                        .expect("supposed to always work"),
-                )
-            {
+            if value.m_pszString.is_null() || self.has_flags(CVarFlags::NEVER_AS_STRING) {
                 return None;
             }
 
@@ -381,6 +417,22 @@ impl ConVarStruct {
             .map_err(|err| err.into())
     }
 
+    /// get the default value as a [`str`]
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the string is null or if it's not utf-8 valid.
+    pub fn get_default_value_str(&self) -> Result<&str, CStringPtrError> {
+        let default_value = self.inner.m_pszDefaultValue;
+        if default_value.is_null() {
+            return Err(CStringPtrError::None);
+        }
+
+        unsafe { CStr::from_ptr(default_value) }
+            .to_str()
+            .map_err(|err| err.into())
+    }
+
     /// get the value as a i32
     pub fn get_value_i32(&self) -> i32 {
         self.inner.m_Value.m_nValue
@@ -450,7 +502,7 @@ impl ConVarStruct {
     /// only safe on the titanfall thread
     pub fn set_value_string(&self, new_value: impl AsRef<str>, _: EngineToken) {
         unsafe {
-            if self.has_flags(FCVAR_NEVER_AS_STRING.try_into().unwrap()) {
+            if self.has_flags(CVarFlags::NEVER_AS_STRING) {
                 return;
             }
 
@@ -477,21 +529,44 @@ impl ConVarStruct {
         self.inner.m_ConCommandBase.m_bRegistered
     }
 
-    /// returns [`true`] if the given flags are set for this convar
-    pub fn has_flags(&self, flags: i32) -> bool {
-        self.inner.m_ConCommandBase.m_nFlags & flags != 0
+    /// the flags of this convar
+    pub const fn get_flags(&self) -> CVarFlags {
+        CVarFlags::from_bits_retain(self.inner.m_ConCommandBase.m_nFlags)
+    }
+
+    /// returns [`true`] if any of the given flags are set for this convar
+    pub const fn has_flags(&self, flags: CVarFlags) -> bool {
+        self.get_flags().intersects(flags)
+    }
+
+    /// the minimum value of the convar if it has one
+    pub const fn get_min(&self) -> Option<f32> {
+        if self.inner.m_bHasMin {
+            Some(self.inner.m_fMinVal)
+        } else {
+            None
+        }
+    }
+
+    /// the maximum value of the convar if it has one
+    pub const fn get_max(&self) -> Option<f32> {
+        if self.inner.m_bHasMax {
+            Some(self.inner.m_fMaxVal)
+        } else {
+            None
+        }
     }
 
     /// adds flags to the convar
-    pub fn add_flags(&mut self, flags: i32, _: EngineToken) {
-        self.inner.m_ConCommandBase.m_nFlags |= flags
+    pub fn add_flags(&mut self, flags: CVarFlags, _: EngineToken) {
+        self.inner.m_ConCommandBase.m_nFlags |= flags.bits()
     }
 
     /// removes flags from the convar
     ///
     /// only safe on the titanfall thread
-    pub fn remove_flags(&mut self, flags: i32, _: EngineToken) {
-        self.inner.m_ConCommandBase.m_nFlags &= !flags // TODO: figure out if this still needs fixing
+    pub fn remove_flags(&mut self, flags: CVarFlags, _: EngineToken) {
+        self.inner.m_ConCommandBase.m_nFlags &= !flags.bits() // TODO: figure out if this still needs fixing
     }
 
     /// exposes the raw pointer to the [`ConVar`] class
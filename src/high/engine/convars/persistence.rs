@@ -0,0 +1,453 @@
+//! opt-in persistence for plugin convars
+//!
+//! convars only keep their values between launches if they are flagged as archive and even then they end up in the game's config.
+//!
+//! [`ConVarPersistence`] saves the values of the convars it tracks to its own [`ConVarStorage`] (usually a [`CfgFileStorage`]) whenever they change
+//! and applies the saved values when a convar starts being tracked.
+//!
+//! ```no_run
+//! use rrplug::prelude::*;
+//! use rrplug::high::engine::convars::persistence::{CfgFileStorage, ConVarPersistence};
+//! use rrplug::exports::OnceCell;
+//!
+//! static PERSISTENCE: OnceCell<ConVarPersistence> = OnceCell::new();
+//!
+//! // inside Plugin impl
+//! fn on_dll_load(engine_data: Option<&EngineData>, _dll_ptr: &DLLPointer, engine_token: EngineToken) {
+//!     let Some(_) = engine_data else {
+//!         return;
+//!     };
+//!
+//!     let persistence = PERSISTENCE.get_or_init(|| {
+//!         ConVarPersistence::new(CfgFileStorage::new("R2Northstar/cfg/cool_plugin.cfg"))
+//!     });
+//!
+//!     let convar = ConVarStruct::try_new(
+//!         &ConVarRegister::new("cool_convar", "cool_default", CVarFlags::empty(), "this is a cool convar"),
+//!         engine_token,
+//!     )
+//!     .unwrap();
+//!
+//!     persistence.persist(&convar, engine_token).unwrap();
+//!     persistence
+//!         .register_reset_concommand("cool_plugin_reset_convars", engine_token)
+//!         .unwrap();
+//! }
+//! ```
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use super::{listeners::ConVarListener, ConVarStruct};
+use crate::{
+    bindings::cvar::CVarFlags,
+    errors::{CVarQueryError, RegisterError},
+    high::engine::{concommands::CCommandResult, EngineToken},
+    mid::engine::get_engine_data,
+    rrplug,
+};
+
+/// reset concommands keyed by their name
+static RESET_COMMANDS: Lazy<Mutex<HashMap<String, &'static ConVarPersistence>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// the convar side of the persistence
+///
+/// implemented for [`ConVarStruct`] but can be implemented for anything else to test the persistence without the engine
+pub trait ConVarBackend: Send + Sync {
+    /// the name of the convar
+    fn get_name(&self) -> String;
+
+    /// the current value as a string
+    fn get_value_string(&self) -> String;
+
+    /// the default value as a string
+    fn get_default_value_string(&self) -> Option<String>;
+
+    /// sets the value from a string
+    fn set_value_string(&self, value: &str, token: EngineToken);
+}
+
+impl ConVarBackend for ConVarStruct {
+    fn get_name(&self) -> String {
+        ConVarStruct::get_name(self)
+    }
+
+    fn get_value_string(&self) -> String {
+        ConVarStruct::get_value_string(self)
+    }
+
+    fn get_default_value_string(&self) -> Option<String> {
+        self.get_default_value_str().ok().map(str::to_string)
+    }
+
+    fn set_value_string(&self, value: &str, token: EngineToken) {
+        ConVarStruct::set_value_string(self, value, token)
+    }
+}
+
+/// where the saved values are kept
+pub trait ConVarStorage: Send {
+    /// reads every saved value
+    fn load(&mut self) -> io::Result<BTreeMap<String, String>>;
+
+    /// replaces every saved value
+    fn save(&mut self, values: &BTreeMap<String, String>) -> io::Result<()>;
+}
+
+/// stores the values in a source style cfg file
+///
+/// each line is `name "value"` so the file can also be `exec`ed by the game
+#[derive(Debug, Clone)]
+pub struct CfgFileStorage {
+    path: PathBuf,
+}
+
+impl CfgFileStorage {
+    /// creates a new [`CfgFileStorage`]; the file is created on the first save
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// the path of the cfg file
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl ConVarStorage for CfgFileStorage {
+    fn load(&mut self) -> io::Result<BTreeMap<String, String>> {
+        match fs::read_to_string(&self.path) {
+            Ok(cfg) => Ok(parse_cfg(&cfg)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn save(&mut self, values: &BTreeMap<String, String>) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(&self.path, write_cfg(values))
+    }
+}
+
+/// keeps the values in memory
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    /// the saved values
+    pub values: BTreeMap<String, String>,
+}
+
+impl ConVarStorage for MemoryStorage {
+    fn load(&mut self) -> io::Result<BTreeMap<String, String>> {
+        Ok(self.values.clone())
+    }
+
+    fn save(&mut self, values: &BTreeMap<String, String>) -> io::Result<()> {
+        self.values = values.clone();
+        Ok(())
+    }
+}
+
+/// saves and restores the values of convars
+///
+/// only values which differ from the default value of the convar are saved
+pub struct ConVarPersistence {
+    storage: Mutex<Box<dyn ConVarStorage>>,
+    values: Mutex<BTreeMap<String, String>>,
+    tracked: Mutex<Vec<Arc<dyn ConVarBackend>>>,
+}
+
+impl ConVarPersistence {
+    /// creates a new [`ConVarPersistence`] and loads the saved values from the storage
+    ///
+    /// errors from the storage are logged
+    pub fn new(storage: impl ConVarStorage + 'static) -> Self {
+        let mut storage = Box::new(storage);
+        let values = storage.load().unwrap_or_else(|err| {
+            log::error!("failed to load saved convars: {err}");
+            BTreeMap::new()
+        });
+
+        Self {
+            storage: Mutex::new(storage),
+            values: Mutex::new(values),
+            tracked: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// the saved value of a convar
+    pub fn get_saved_value(&self, name: &str) -> Option<String> {
+        self.values.lock().get(name).cloned()
+    }
+
+    /// starts tracking a convar and applies its saved value
+    ///
+    /// changes to the convar are not saved automatically; call [`ConVarPersistence::update`] or use [`ConVarPersistence::persist`] instead
+    pub fn track(&self, convar: impl ConVarBackend + 'static, token: EngineToken) {
+        if let Some(value) = self.get_saved_value(&convar.get_name()) {
+            convar.set_value_string(&value, token);
+        }
+
+        self.tracked.lock().push(Arc::new(convar));
+    }
+
+    /// tracks a convar and saves its value every time it changes
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the cvar interface doesn't exist yet or if the convar isn't registered
+    pub fn persist(
+        &'static self,
+        convar: &ConVarStruct,
+        token: EngineToken,
+    ) -> Result<ConVarListener, CVarQueryError> {
+        let name = convar.get_name();
+        self.track(
+            ConVarStruct::find_convar_by_name(&name, token).ok_or(CVarQueryError::NotFound)?,
+            token,
+        );
+
+        convar.on_change(
+            move |_: String, new: String, _| self.update(&name, new),
+            token,
+        )
+    }
+
+    /// records the new value of a tracked convar and saves it
+    pub fn update(&self, name: &str, value: String) {
+        let is_default = self
+            .tracked
+            .lock()
+            .iter()
+            .find(|convar| convar.get_name() == name)
+            .and_then(|convar| convar.get_default_value_string())
+            .map(|default| default == value)
+            .unwrap_or_default();
+
+        let mut values = self.values.lock();
+        let changed = if is_default {
+            values.remove(name).is_some()
+        } else {
+            values.insert(name.to_string(), value.clone()) != Some(value)
+        };
+
+        if changed {
+            self.save_values(&values);
+        }
+    }
+
+    /// sets every tracked convar back to its default value and removes the saved values
+    pub fn reset(&self, token: EngineToken) {
+        // setting a value runs the change callbacks which end up in `update` so the lock can't be held here
+        let tracked = self.tracked.lock().clone();
+        for convar in tracked {
+            if let Some(default) = convar.get_default_value_string() {
+                convar.set_value_string(&default, token);
+            }
+        }
+
+        let mut values = self.values.lock();
+        values.clear();
+        self.save_values(&values);
+    }
+
+    /// registers a concommand which calls [`ConVarPersistence::reset`]
+    pub fn register_reset_concommand(
+        &'static self,
+        name: &str,
+        token: EngineToken,
+    ) -> Result<(), RegisterError> {
+        get_engine_data()
+            .ok_or(RegisterError::NoneFunction)?
+            .register_concommand(
+                name,
+                reset_convars,
+                "resets the convars of this plugin to their default values",
+                CVarFlags::empty(),
+                token,
+            )?;
+
+        RESET_COMMANDS.lock().insert(name.to_string(), self);
+        Ok(())
+    }
+
+    fn save_values(&self, values: &BTreeMap<String, String>) {
+        if let Err(err) = self.storage.lock().save(values) {
+            log::error!("failed to save convars: {err}");
+        }
+    }
+}
+
+#[rrplug::concommand]
+fn reset_convars(command: CCommandResult) {
+    let persistence = RESET_COMMANDS.lock().get(command.get_command()).copied();
+
+    if let Some(persistence) = persistence {
+        persistence.reset(engine_token);
+        log::info!("reset convars to their default values");
+    }
+}
+
+/// parses a cfg file made of `name "value"` lines
+///
+/// comments and empty lines are skipped
+pub fn parse_cfg(cfg: &str) -> BTreeMap<String, String> {
+    cfg.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("//"))
+        .map(|line| {
+            let (name, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+
+            (name.to_string(), value.to_string())
+        })
+        .collect()
+}
+
+/// writes values as a cfg file made of `name "value"` lines
+///
+/// cfg files have no escapes so entries containing quotes or line breaks are skipped and logged
+pub fn write_cfg(values: &BTreeMap<String, String>) -> String {
+    values
+        .iter()
+        .filter(|(name, value)| {
+            let valid =
+                is_cfg_safe(name) && !name.contains(char::is_whitespace) && is_cfg_safe(value);
+            if !valid {
+                log::warn!("not saving convar {name:?} since its value can't be written to a cfg");
+            }
+            valid
+        })
+        .map(|(name, value)| format!("{name} \"{value}\"\n"))
+        .collect()
+}
+
+fn is_cfg_safe(value: &str) -> bool {
+    !value.contains(['"', '\n', '\r'])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct TestConVar {
+        name: &'static str,
+        default: &'static str,
+        value: Arc<Mutex<String>>,
+        /// runs the change callback like the engine does
+        listener: Option<&'static ConVarPersistence>,
+    }
+
+    impl ConVarBackend for TestConVar {
+        fn get_name(&self) -> String {
+            self.name.to_string()
+        }
+
+        fn get_value_string(&self) -> String {
+            self.value.lock().clone()
+        }
+
+        fn get_default_value_string(&self) -> Option<String> {
+            Some(self.default.to_string())
+        }
+
+        fn set_value_string(&self, value: &str, _: EngineToken) {
+            *self.value.lock() = value.to_string();
+            if let Some(persistence) = self.listener {
+                persistence.update(self.name, value.to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn cfg_round_trip() {
+        let cfg = "// saved convars\n\ncool_convar \"cool value\"\nother_convar 4\n";
+        let values = parse_cfg(cfg);
+
+        assert_eq!(values["cool_convar"], "cool value");
+        assert_eq!(values["other_convar"], "4");
+        assert_eq!(parse_cfg(&write_cfg(&values)), values);
+    }
+
+    #[test]
+    fn cfg_rejects_quotes() {
+        let values = BTreeMap::from([
+            ("fine".to_string(), "ok".to_string()),
+            ("quoted".to_string(), "a\"; quit; \"".to_string()),
+            ("newline".to_string(), "a\nquit".to_string()),
+        ]);
+
+        assert_eq!(write_cfg(&values), "fine \"ok\"\n");
+    }
+
+    #[test]
+    fn persist_in_memory() {
+        let token = unsafe { EngineToken::new_unchecked() };
+        let value = Arc::new(Mutex::new("1".to_string()));
+
+        let persistence = ConVarPersistence::new(MemoryStorage {
+            values: BTreeMap::from([("test_convar".to_string(), "5".to_string())]),
+        });
+
+        persistence.track(
+            TestConVar {
+                name: "test_convar",
+                default: "1",
+                value: value.clone(),
+                listener: None,
+            },
+            token,
+        );
+        assert_eq!(*value.lock(), "5");
+
+        persistence.update("test_convar", "7".to_string());
+        assert_eq!(
+            persistence.get_saved_value("test_convar").as_deref(),
+            Some("7")
+        );
+
+        persistence.update("test_convar", "1".to_string());
+        assert_eq!(persistence.get_saved_value("test_convar"), None);
+
+        persistence.update("test_convar", "3".to_string());
+        persistence.reset(token);
+        assert_eq!(*value.lock(), "1");
+        assert_eq!(persistence.get_saved_value("test_convar"), None);
+    }
+
+    #[test]
+    fn reset_with_listener() {
+        let token = unsafe { EngineToken::new_unchecked() };
+        let value = Arc::new(Mutex::new("1".to_string()));
+        let persistence: &'static ConVarPersistence =
+            Box::leak(Box::new(ConVarPersistence::new(MemoryStorage::default())));
+
+        persistence.track(
+            TestConVar {
+                name: "test_convar",
+                default: "1",
+                value: value.clone(),
+                listener: Some(persistence),
+            },
+            token,
+        );
+
+        persistence.update("test_convar", "3".to_string());
+        persistence.reset(token);
+        assert_eq!(*value.lock(), "1");
+        assert_eq!(persistence.get_saved_value("test_convar"), None);
+    }
+}
@@ -0,0 +1,513 @@
+//! convars that are bound to a rust type
+//!
+//! [`TypedConVar`] stores the type of the convar so the value can only be read and written as that type.
+//!
+//! the supported types are
+//! 1. `bool`
+//! 2. integers (`i8` to `i64`, `u8` to `u64`, `isize` and `usize`)
+//! 3. `f32`
+//! 4. `String`
+//! 5. unit enums deriving [`ConVarEnum`]
+//! 6. [`Color`]
+//!
+//! ```no_run
+//! use rrplug::prelude::*;
+//! use rrplug::high::engine::convars::typed::{ConVarEnum, TypedConVar, TypedConVarRegister};
+//!
+//! #[derive(Clone, Copy, ConVarEnum)]
+//! enum Speed {
+//!     Slow,
+//!     Fast,
+//! }
+//!
+//! # let engine_token = unsafe { EngineToken::new_unchecked() };
+//! let speed = TypedConVar::try_new(
+//!     &TypedConVarRegister::new("cool_speed", Speed::Slow, CVarFlags::empty(), "can be Slow or Fast"),
+//!     engine_token,
+//! )
+//! .unwrap();
+//!
+//! let scale = TypedConVar::try_new(
+//!     &TypedConVarRegister::new("cool_scale", 1.0f32, CVarFlags::empty(), "a scale between 0 and 10")
+//!         .with_min(0.0)
+//!         .with_max(10.0),
+//!     engine_token,
+//! )
+//! .unwrap();
+//!
+//! if let Speed::Fast = speed.get() {
+//!     scale.set(5.0, engine_token);
+//! }
+//! ```
+
+pub use rrplug_proc::ConVarEnum;
+
+use super::{ConVarRegister, ConVarStruct};
+use crate::{
+    bindings::cvar::{
+        convar::{Color, FnChangeCallback_t},
+        CVarFlags,
+    },
+    errors::RegisterError,
+    high::engine::EngineToken,
+};
+
+/// trait for types that can be stored in a convar
+///
+/// the value is always stored as a string by the engine; numeric types also use the float and int values
+pub trait ConVarValue: Sized + Clone {
+    /// parses the value from the string representation of the convar
+    fn from_convar_str(value: &str) -> Option<Self>;
+
+    /// the string representation used for the default value and for writing the value
+    fn to_convar_string(&self) -> String;
+
+    /// reads the old value passed to change callbacks
+    fn from_old_value(old_value: &str, _old_float: f32) -> Option<Self> {
+        Self::from_convar_str(old_value)
+    }
+
+    /// reads the value from the convar
+    fn get_from_convar(convar: &ConVarStruct) -> Option<Self> {
+        Self::from_convar_str(convar.get_value_str().ok()?)
+    }
+
+    /// writes the value to the convar
+    ///
+    /// only safe on the titanfall thread
+    fn set_to_convar(&self, convar: &ConVarStruct, token: EngineToken) {
+        convar.set_value_string(self.to_convar_string(), token)
+    }
+
+    /// min and max that are always applied to the convar unless overwritten
+    fn implied_bounds() -> (Option<f32>, Option<f32>) {
+        (None, None)
+    }
+}
+
+/// a [`ConVarValue`] which can be used as the min or max of a convar
+pub trait ConVarBound: ConVarValue {
+    /// converts the value to the float used by the engine for clamping
+    fn to_bound(&self) -> f32;
+}
+
+/// trait for unit enums that can be stored in a convar
+///
+/// the convar accepts either the name of the variant (case insensitive) or its index
+/// and it's stored as the index so scripts can read it as an int
+///
+/// should be implemented with the derive macro
+pub trait ConVarEnum: Copy + 'static {
+    /// the names of the variants in order of declaration
+    const VARIANTS: &'static [(&'static str, Self)];
+
+    /// the position of the variant in [`ConVarEnum::VARIANTS`]
+    fn variant_index(&self) -> usize;
+}
+
+impl ConVarValue for bool {
+    fn from_convar_str(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("true") {
+            Some(true)
+        } else if value.eq_ignore_ascii_case("false") {
+            Some(false)
+        } else {
+            // same as the engine which reads bools as ints
+            value.parse::<f32>().ok().map(|value| value as i32 != 0)
+        }
+    }
+
+    fn to_convar_string(&self) -> String {
+        (*self as i32).to_string()
+    }
+
+    fn get_from_convar(convar: &ConVarStruct) -> Option<Self> {
+        Some(convar.get_value_bool())
+    }
+
+    fn set_to_convar(&self, convar: &ConVarStruct, token: EngineToken) {
+        convar.set_value_i32(*self as i32, token)
+    }
+}
+
+macro_rules! impl_convar_value_int {
+    ( $( $t:ty ),* ) => { $(
+        impl ConVarValue for $t {
+            fn from_convar_str(value: &str) -> Option<Self> {
+                let value = value.trim();
+                value.parse().ok().or_else(|| {
+                    // whole floats like `4.000000` are fine but nothing that would be truncated or clamped
+                    let value = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|value| value.fract() == 0.0 && value.abs() < 2f64.powi(127))?;
+                    Self::try_from(value as i128).ok()
+                })
+            }
+
+            fn to_convar_string(&self) -> String {
+                self.to_string()
+            }
+
+            fn get_from_convar(convar: &ConVarStruct) -> Option<Self> {
+                // the int value of the convar is only a i32 so the string is prefered
+                convar
+                    .get_value_str()
+                    .ok()
+                    .and_then(Self::from_convar_str)
+                    .or_else(|| convar.get_value_i32().try_into().ok())
+            }
+        }
+
+        impl ConVarBound for $t {
+            fn to_bound(&self) -> f32 {
+                *self as f32
+            }
+        }
+    )* };
+}
+
+impl_convar_value_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl ConVarValue for f32 {
+    fn from_convar_str(value: &str) -> Option<Self> {
+        value.trim().parse().ok()
+    }
+
+    fn from_old_value(_old_value: &str, old_float: f32) -> Option<Self> {
+        Some(old_float)
+    }
+
+    fn to_convar_string(&self) -> String {
+        self.to_string()
+    }
+
+    fn get_from_convar(convar: &ConVarStruct) -> Option<Self> {
+        Some(convar.get_value_f32())
+    }
+
+    fn set_to_convar(&self, convar: &ConVarStruct, token: EngineToken) {
+        convar.set_value_f32(*self, token)
+    }
+}
+
+impl ConVarBound for f32 {
+    fn to_bound(&self) -> f32 {
+        *self
+    }
+}
+
+impl ConVarValue for String {
+    fn from_convar_str(value: &str) -> Option<Self> {
+        Some(value.to_string())
+    }
+
+    fn to_convar_string(&self) -> String {
+        self.clone()
+    }
+
+    fn get_from_convar(convar: &ConVarStruct) -> Option<Self> {
+        Some(convar.get_value_string())
+    }
+}
+
+/// stored like the engine does it : `"r g b a"` where alpha is optional
+impl ConVarValue for Color {
+    fn from_convar_str(value: &str) -> Option<Self> {
+        let mut channels = value
+            .split_whitespace()
+            .map(|channel| channel.parse::<u8>());
+
+        let r = channels.next()?.ok()?;
+        let g = channels.next()?.ok()?;
+        let b = channels.next()?.ok()?;
+        let a = channels.next().unwrap_or(Ok(u8::MAX)).ok()?;
+
+        channels.next().is_none().then_some(Color {
+            _color: [r, g, b, a],
+        })
+    }
+
+    fn to_convar_string(&self) -> String {
+        let [r, g, b, a] = self._color;
+        format!("{r} {g} {b} {a}")
+    }
+}
+
+impl<T: ConVarEnum> ConVarValue for T {
+    fn from_convar_str(value: &str) -> Option<Self> {
+        let value = value.trim();
+        Self::VARIANTS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(value))
+            .or_else(|| Self::VARIANTS.get(value.parse::<usize>().ok()?))
+            .map(|(_, variant)| *variant)
+    }
+
+    fn to_convar_string(&self) -> String {
+        self.variant_index().to_string()
+    }
+
+    fn get_from_convar(convar: &ConVarStruct) -> Option<Self> {
+        convar
+            .get_value_str()
+            .ok()
+            .and_then(Self::from_convar_str)
+            .or_else(|| {
+                Self::VARIANTS
+                    .get(usize::try_from(convar.get_value_i32()).ok()?)
+                    .map(|(_, variant)| *variant)
+            })
+    }
+
+    fn implied_bounds() -> (Option<f32>, Option<f32>) {
+        (
+            Some(0.),
+            Some(Self::VARIANTS.len().saturating_sub(1) as f32),
+        )
+    }
+}
+
+/// [`TypedConVarRegister`] is the builder struct for [`TypedConVar`]
+///
+/// ```
+/// # use rrplug::prelude::*;
+/// _ = TypedConVarRegister::new("a_convar", 4, CVarFlags::empty(), "this is a convar")
+///     .with_min(0)
+///     .with_max(8);
+/// ```
+pub struct TypedConVarRegister<T: ConVarValue> {
+    name: String,
+    default_value: T,
+    flags: CVarFlags,
+    help_string: &'static str,
+    min: Option<f32>,
+    max: Option<f32>,
+    callback: FnChangeCallback_t,
+}
+
+impl<T: ConVarValue> TypedConVarRegister<T> {
+    /// creates a new [`TypedConVarRegister`] with all the required fields
+    pub fn new(
+        name: impl Into<String>,
+        default_value: T,
+        flags: CVarFlags,
+        help_string: &'static str,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            default_value,
+            flags,
+            help_string,
+            min: None,
+            max: None,
+            callback: None,
+        }
+    }
+
+    /// sets the callback called when the convar is changed; it should be created with [`crate::convar`]
+    pub fn with_callback(mut self, callback: FnChangeCallback_t) -> Self {
+        self.callback = callback;
+        self
+    }
+
+    /// the default value of the convar
+    pub const fn get_default_value(&self) -> &T {
+        &self.default_value
+    }
+
+    /// converts this to the untyped [`ConVarRegister`]
+    pub fn to_register(&self) -> ConVarRegister {
+        let (implied_min, implied_max) = T::implied_bounds();
+        let min = self.min.or(implied_min);
+        let max = self.max.or(implied_max);
+
+        ConVarRegister {
+            bmin: min.is_some(),
+            fmin: min.unwrap_or_default(),
+            bmax: max.is_some(),
+            fmax: max.unwrap_or_default(),
+            callback: self.callback,
+            ..ConVarRegister::mandatory(
+                self.name.clone(),
+                self.default_value.to_convar_string(),
+                self.flags,
+                self.help_string,
+            )
+        }
+    }
+}
+
+impl<T: ConVarBound> TypedConVarRegister<T> {
+    /// sets the min value of the convar
+    pub fn with_min(mut self, min: T) -> Self {
+        self.min = Some(min.to_bound());
+        self
+    }
+
+    /// sets the max value of the convar
+    pub fn with_max(mut self, max: T) -> Self {
+        self.max = Some(max.to_bound());
+        self
+    }
+}
+
+/// [`ConVarStruct`] which can only be read and written as `T`
+pub struct TypedConVar<T: ConVarValue> {
+    inner: ConVarStruct,
+    default_value: T,
+}
+
+impl<T: ConVarValue> TypedConVar<T> {
+    /// creates and registers a convar from [`TypedConVarRegister`]
+    pub fn try_new(
+        register_info: &TypedConVarRegister<T>,
+        token: EngineToken,
+    ) -> Result<Self, RegisterError> {
+        Ok(Self {
+            inner: ConVarStruct::try_new(&register_info.to_register(), token)?,
+            default_value: register_info.default_value.clone(),
+        })
+    }
+
+    /// finds a convar by name
+    ///
+    /// returns [`None`] if the convar doesn't exist or if its default value cannot be read as `T`
+    pub fn find_convar_by_name(name: &str, token: EngineToken) -> Option<Self> {
+        Self::from_convar_struct(ConVarStruct::find_convar_by_name(name, token)?)
+    }
+
+    /// wraps a [`ConVarStruct`]
+    ///
+    /// returns [`None`] if its default value cannot be read as `T`
+    pub fn from_convar_struct(convar: ConVarStruct) -> Option<Self> {
+        let default_value = T::from_convar_str(convar.get_default_value_str().ok()?)?;
+
+        Some(Self {
+            inner: convar,
+            default_value,
+        })
+    }
+
+    /// get the value of the convar
+    ///
+    /// falls back to the default value if the current value cannot be read as `T`
+    pub fn get(&self) -> T {
+        self.try_get().unwrap_or_else(|| self.default_value.clone())
+    }
+
+    /// get the value of the convar if it can be read as `T`
+    pub fn try_get(&self) -> Option<T> {
+        T::get_from_convar(&self.inner)
+    }
+
+    /// set the value of the convar
+    ///
+    /// only safe on the titanfall thread
+    pub fn set(&self, value: T, token: EngineToken) {
+        value.set_to_convar(&self.inner, token)
+    }
+
+    /// sets the convar back to its default value
+    ///
+    /// only safe on the titanfall thread
+    pub fn reset(&self, token: EngineToken) {
+        self.set(self.default_value.clone(), token)
+    }
+
+    /// the default value of the convar
+    pub const fn get_default_value(&self) -> &T {
+        &self.default_value
+    }
+
+    /// the untyped [`ConVarStruct`]
+    pub const fn get_convar_struct(&self) -> &ConVarStruct {
+        &self.inner
+    }
+
+    /// consumes the [`TypedConVar`] and returns the untyped [`ConVarStruct`]
+    pub fn into_convar_struct(self) -> ConVarStruct {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rrplug;
+
+    #[derive(Clone, Copy, Debug, PartialEq, ConVarEnum)]
+    enum TestSpeed {
+        Slow,
+        Normal,
+        Fast,
+    }
+
+    #[test]
+    fn parse_convar_values() {
+        assert_eq!(bool::from_convar_str("1"), Some(true));
+        assert_eq!(bool::from_convar_str("0.5"), Some(false));
+        assert_eq!(bool::from_convar_str("True"), Some(true));
+        assert_eq!(bool::from_convar_str("yes"), None);
+
+        assert_eq!(i32::from_convar_str(" -12 "), Some(-12));
+        assert_eq!(i32::from_convar_str("3.7"), None);
+        assert_eq!(i32::from_convar_str("4.000000"), Some(4));
+        assert_eq!(u8::from_convar_str("-1"), None);
+        assert_eq!(u8::from_convar_str("300"), None);
+        assert_eq!(u8::from_convar_str("255.0"), Some(255));
+        assert_eq!(u8::from_convar_str("nan"), None);
+        assert_eq!(i64::from_convar_str("inf"), None);
+        assert_eq!(u8::from_convar_str("abc"), None);
+        assert_eq!(i64::from_convar_str("9000000000"), Some(9_000_000_000));
+
+        assert_eq!(f32::from_convar_str("0.25"), Some(0.25));
+        assert_eq!(
+            String::from_convar_str("some string"),
+            Some("some string".to_string())
+        );
+
+        assert_eq!(
+            Color::from_convar_str("255 128 0").map(|color| color._color),
+            Some([255, 128, 0, 255])
+        );
+        assert_eq!(
+            Color::from_convar_str("1 2 3 4").map(|color| color._color),
+            Some([1, 2, 3, 4])
+        );
+        assert!(Color::from_convar_str("1 2").is_none());
+        assert!(Color::from_convar_str("1 2 3 4 5").is_none());
+        assert!(Color::from_convar_str("1 2 300").is_none());
+    }
+
+    #[test]
+    fn parse_convar_enum() {
+        assert_eq!(TestSpeed::from_convar_str("fast"), Some(TestSpeed::Fast));
+        assert_eq!(TestSpeed::from_convar_str("1"), Some(TestSpeed::Normal));
+        assert_eq!(TestSpeed::from_convar_str("3"), None);
+        assert_eq!(TestSpeed::from_convar_str("faster"), None);
+        assert_eq!(TestSpeed::Fast.to_convar_string(), "2");
+        assert_eq!(TestSpeed::implied_bounds(), (Some(0.), Some(2.)));
+    }
+
+    #[test]
+    fn typed_register_to_register() {
+        let register = TypedConVarRegister::new("test_convar", 5u8, CVarFlags::empty(), "test")
+            .with_min(1)
+            .to_register();
+
+        assert_eq!(register.default_value, "5");
+        assert!(register.bmin);
+        assert_eq!(register.fmin, 1.);
+        assert!(!register.bmax);
+
+        let register =
+            TypedConVarRegister::new("test_convar", TestSpeed::Normal, CVarFlags::empty(), "test")
+                .to_register();
+
+        assert_eq!(register.default_value, "1");
+        assert!(register.bmin && register.bmax);
+        assert_eq!(register.fmax, 2.);
+    }
+}
@@ -0,0 +1,328 @@
+//! dumps every registered convar and concommand to a json or markdown file
+//!
+//! ```no_run
+//! use rrplug::prelude::*;
+//! use rrplug::high::engine::cvar_dump::{dump_cvars, register_dump_concommand, DumpFormat};
+//!
+//! # let engine_token = unsafe { EngineToken::new_unchecked() };
+//! // straight from rust
+//! dump_cvars("R2Northstar/cvars.md", DumpFormat::Markdown, engine_token).unwrap();
+//!
+//! // or from the console with `cool_plugin_dump_cvars R2Northstar/cvars.json`
+//! register_dump_concommand("cool_plugin_dump_cvars", engine_token).unwrap();
+//! ```
+
+use std::{fmt::Write, fs, path::Path, str::FromStr};
+
+use super::{
+    concommands::{CCommandResult, ConCommandHandle},
+    cvar_iter::{cvar_iter, CVarEntry},
+    EngineToken,
+};
+use crate::{
+    bindings::cvar::CVarFlags,
+    errors::{CVarDumpError, CVarQueryError, RegisterError},
+    mid::engine::{get_engine_data, tracking},
+    rrplug,
+};
+
+/// the format of a dump
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DumpFormat {
+    /// a json array of objects
+    Json,
+    /// a markdown table
+    #[default]
+    Markdown,
+}
+
+impl DumpFormat {
+    /// guesses the format from the extension of a path
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        path.as_ref().extension()?.to_str()?.parse().ok()
+    }
+}
+
+impl FromStr for DumpFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "md" | "markdown" => Ok(Self::Markdown),
+            _ => Err(()),
+        }
+    }
+}
+
+/// a snapshot of a convar or concommand
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CVarDumpEntry {
+    /// the name
+    pub name: String,
+    /// [`true`] for concommands
+    pub is_command: bool,
+    /// the default value of a convar
+    pub default_value: Option<String>,
+    /// the current value of a convar
+    pub value: Option<String>,
+    /// the flags
+    pub flags: CVarFlags,
+    /// the minimum value of a convar
+    pub min: Option<f32>,
+    /// the maximum value of a convar
+    pub max: Option<f32>,
+    /// the help text
+    pub help_text: String,
+    /// [`true`] if it was registered by this plugin
+    pub registered_by_plugin: bool,
+}
+
+impl From<&CVarEntry> for CVarDumpEntry {
+    fn from(entry: &CVarEntry) -> Self {
+        let convar = entry.as_convar();
+
+        Self {
+            name: entry.get_name().into_owned(),
+            is_command: entry.is_command(),
+            default_value: convar
+                .as_ref()
+                .and_then(|convar| convar.get_default_value_str().ok().map(ToString::to_string)),
+            value: convar.as_ref().map(|convar| convar.get_value_string()),
+            flags: entry.get_flags(),
+            min: convar.as_ref().and_then(|convar| convar.get_min()),
+            max: convar.as_ref().and_then(|convar| convar.get_max()),
+            help_text: entry.get_help_text().into_owned(),
+            registered_by_plugin: tracking::is_tracked(entry.get_raw()),
+        }
+    }
+}
+
+/// takes a snapshot of every registered convar and concommand sorted by name
+///
+/// # Errors
+///
+/// this function will return an error if the cvar interface doesn't exist yet
+pub fn collect_cvars(token: EngineToken) -> Result<Vec<CVarDumpEntry>, CVarQueryError> {
+    let mut entries = cvar_iter(token)?
+        .map(|entry| CVarDumpEntry::from(&entry))
+        .collect::<Vec<CVarDumpEntry>>();
+    entries.sort_by_cached_key(|entry| entry.name.to_lowercase());
+
+    Ok(entries)
+}
+
+/// writes every registered convar and concommand to `path`
+///
+/// returns the amount of entries written
+///
+/// # Errors
+///
+/// this function will return an error if the cvar interface doesn't exist yet or if the file couldn't be written
+pub fn dump_cvars(
+    path: impl AsRef<Path>,
+    format: DumpFormat,
+    token: EngineToken,
+) -> Result<usize, CVarDumpError> {
+    let entries = collect_cvars(token)?;
+
+    if let Some(parent) = path.as_ref().parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, format_entries(&entries, format))?;
+
+    Ok(entries.len())
+}
+
+/// formats entries in the given format
+pub fn format_entries(entries: &[CVarDumpEntry], format: DumpFormat) -> String {
+    match format {
+        DumpFormat::Json => to_json(entries),
+        DumpFormat::Markdown => to_markdown(entries),
+    }
+}
+
+/// formats entries as a json array
+pub fn to_json(entries: &[CVarDumpEntry]) -> String {
+    fn json_string(s: &str) -> String {
+        let mut escaped = String::with_capacity(s.len() + 2);
+        escaped.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                c if c.is_control() => _ = write!(escaped, "\\u{:04x}", c as u32),
+                c => escaped.push(c),
+            }
+        }
+        escaped.push('"');
+        escaped
+    }
+
+    fn json_option<T>(value: Option<T>, f: impl FnOnce(T) -> String) -> String {
+        value.map(f).unwrap_or_else(|| "null".to_string())
+    }
+
+    fn json_number(value: f32) -> String {
+        if value.is_finite() {
+            value.to_string()
+        } else {
+            "null".to_string()
+        }
+    }
+
+    let objects = entries
+        .iter()
+        .map(|entry| {
+            format!(
+                "  {{\"name\": {}, \"is_command\": {}, \"default_value\": {}, \"value\": {}, \"flags\": {}, \"min\": {}, \"max\": {}, \"help_text\": {}, \"registered_by_plugin\": {}}}",
+                json_string(&entry.name),
+                entry.is_command,
+                json_option(entry.default_value.as_deref(), json_string),
+                json_option(entry.value.as_deref(), json_string),
+                json_string(&entry.flags.to_string()),
+                json_option(entry.min, json_number),
+                json_option(entry.max, json_number),
+                json_string(&entry.help_text),
+                entry.registered_by_plugin,
+            )
+        })
+        .collect::<Vec<String>>();
+
+    if objects.is_empty() {
+        "[]\n".to_string()
+    } else {
+        format!("[\n{}\n]\n", objects.join(",\n"))
+    }
+}
+
+/// formats entries as a markdown table
+pub fn to_markdown(entries: &[CVarDumpEntry]) -> String {
+    fn cell(s: &str) -> String {
+        s.replace('\\', "\\\\")
+            .replace('|', "\\|")
+            .replace("\r\n", "<br>")
+            .replace('\n', "<br>")
+    }
+
+    fn optional_cell<T: ToString>(value: Option<T>) -> String {
+        value
+            .map(|value| cell(&value.to_string()))
+            .unwrap_or_default()
+    }
+
+    let mut markdown = String::from(
+        "| name | kind | default | value | flags | min | max | plugin | help |\n|---|---|---|---|---|---|---|---|---|\n",
+    );
+
+    for entry in entries {
+        _ = writeln!(
+            markdown,
+            "| `{}` | {} | {} | {} | {} | {} | {} | {} | {} |",
+            cell(&entry.name),
+            if entry.is_command {
+                "concommand"
+            } else {
+                "convar"
+            },
+            optional_cell(entry.default_value.as_deref()),
+            optional_cell(entry.value.as_deref()),
+            cell(&entry.flags.to_string()),
+            optional_cell(entry.min),
+            optional_cell(entry.max),
+            if entry.registered_by_plugin {
+                "yes"
+            } else {
+                "no"
+            },
+            cell(&entry.help_text),
+        );
+    }
+
+    markdown
+}
+
+/// registers a concommand which dumps every convar and concommand
+///
+/// usage: `<name> [path] [json|md]`; the format is guessed from the extension of the path if it's not given
+pub fn register_dump_concommand(
+    name: &str,
+    token: EngineToken,
+) -> Result<ConCommandHandle, RegisterError> {
+    get_engine_data()
+        .ok_or(RegisterError::NoneFunction)?
+        .register_concommand(
+            name,
+            dump_cvars_command,
+            "dumps every convar and concommand to a file; usage: <path> [json|md]",
+            CVarFlags::empty(),
+            token,
+        )
+}
+
+#[rrplug::concommand]
+fn dump_cvars_command(command: CCommandResult) {
+    let path = command.get_arg(0).unwrap_or("cvars.md");
+    let format = command
+        .get_arg(1)
+        .and_then(|format| format.parse().ok())
+        .or_else(|| DumpFormat::from_path(path))
+        .unwrap_or_default();
+
+    match dump_cvars(path, format, engine_token) {
+        Ok(count) => log::info!("dumped {count} convars and concommands to {path}"),
+        Err(err) => err.log(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entries() -> Vec<CVarDumpEntry> {
+        vec![
+            CVarDumpEntry {
+                name: "cool_convar".to_string(),
+                default_value: Some("1".to_string()),
+                value: Some("say \"hi\"".to_string()),
+                flags: CVarFlags::ARCHIVE,
+                min: Some(0.),
+                help_text: "a | cool\nconvar".to_string(),
+                registered_by_plugin: true,
+                ..Default::default()
+            },
+            CVarDumpEntry {
+                name: "boom".to_string(),
+                is_command: true,
+                help_text: "explodes".to_string(),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn dump_formats() {
+        let json = to_json(&entries());
+        assert!(json.contains(r#""name": "cool_convar""#));
+        assert!(json.contains(r#""value": "say \"hi\"""#));
+        assert!(json.contains(r#""min": 0, "max": null"#));
+        assert!(json.contains(r#""help_text": "a | cool\nconvar""#));
+        assert!(json.contains(r#""flags": "NONE""#));
+        assert_eq!(to_json(&[]), "[]\n");
+
+        let markdown = to_markdown(&entries());
+        assert_eq!(markdown.lines().count(), 4);
+        assert!(markdown.contains("| `cool_convar` | convar | 1 | say \"hi\" | ARCHIVE | 0 |  | yes | a \\| cool<br>convar |"));
+        assert!(markdown.contains("| `boom` | concommand |"));
+
+        assert_eq!(DumpFormat::from_path("cvars.JSON"), Some(DumpFormat::Json));
+        assert_eq!(
+            DumpFormat::from_path("cvars.md"),
+            Some(DumpFormat::Markdown)
+        );
+        assert_eq!(DumpFormat::from_path("cvars"), None);
+    }
+}
@@ -0,0 +1,266 @@
+//! safe iteration over every convar and concommand known to the engine
+//!
+//! ```no_run
+//! use rrplug::prelude::*;
+//! use rrplug::high::engine::cvar_iter::{cvar_iter, CVarFilters};
+//!
+//! # let engine_token = unsafe { EngineToken::new_unchecked() };
+//! for convar in cvar_iter(engine_token).unwrap().convars().search("spewlog") {
+//!     log::info!("{} = {:?}", convar.get_name(), convar.get_value());
+//! }
+//! ```
+
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    borrow::Cow,
+    ffi::{c_void, CStr},
+};
+
+use super::{convars::ConVarStruct, EngineToken};
+use crate::{
+    bindings::cvar::{command::ConCommandBase, convar::ConVar, CVarFlags, RawCvarIterator},
+    errors::CVarQueryError,
+    mid::{engine::get_engine_data, source_alloc::SOURCE_ALLOC},
+};
+
+/// creates a [`CVarIter`] over every registered convar and concommand
+///
+/// # Errors
+///
+/// this function will return an error if the cvar interface doesn't exist yet or if the engine didn't give an iterator
+pub fn cvar_iter(token: EngineToken) -> Result<CVarIter, CVarQueryError> {
+    let cvar = get_engine_data()
+        .ok_or(CVarQueryError::NoCVarInterface)?
+        .get_cvar();
+
+    let iterator = unsafe { cvar.get_cvar_raw_interator() } as *const c_void;
+    if iterator.is_null() {
+        return Err(CVarQueryError::NotFound);
+    }
+
+    Ok(CVarIter {
+        iterator: RawCvarIterator::from(iterator),
+        started: false,
+        _token: token,
+    })
+}
+
+/// iterator over every registered convar and concommand created by [`cvar_iter`]
+///
+/// the engine iterator is freed with the source allocator when this is dropped
+pub struct CVarIter {
+    iterator: RawCvarIterator,
+    started: bool,
+    _token: EngineToken,
+}
+
+/// filtering helpers for iterators over [`CVarEntry`] like [`CVarIter`]
+pub trait CVarFilters: Iterator<Item = CVarEntry> + Sized {
+    /// only yields convars
+    fn convars(self) -> impl Iterator<Item = CVarEntry> {
+        self.filter(CVarEntry::is_convar)
+    }
+
+    /// only yields concommands
+    fn concommands(self) -> impl Iterator<Item = CVarEntry> {
+        self.filter(CVarEntry::is_command)
+    }
+
+    /// only yields entries with any of the `flags`
+    fn with_flags(self, flags: CVarFlags) -> impl Iterator<Item = CVarEntry> {
+        self.filter(move |entry| entry.has_flags(flags))
+    }
+
+    /// only yields entries which contain `pattern` in their name or help text, ignoring case
+    fn search(self, pattern: &str) -> impl Iterator<Item = CVarEntry> {
+        let pattern = pattern.to_lowercase();
+        self.filter(move |entry| entry.matches(&pattern))
+    }
+}
+
+impl<I: Iterator<Item = CVarEntry>> CVarFilters for I {}
+
+impl Iterator for CVarIter {
+    type Item = CVarEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        unsafe {
+            if self.started {
+                self.iterator.next();
+            } else {
+                self.iterator.set_first();
+                self.started = true;
+            }
+
+            if !self.iterator.is_valid() {
+                return None;
+            }
+
+            let base = self.iterator.get().as_ref()?;
+            Some(CVarEntry {
+                base,
+                is_command: is_command(base),
+            })
+        }
+    }
+}
+
+impl Drop for CVarIter {
+    fn drop(&mut self) {
+        // the iterator was allocated by the engine with IMemAlloc so it has to be freed by it
+        unsafe { SOURCE_ALLOC.dealloc(self.iterator.as_ptr() as *mut u8, Layout::new::<u8>()) }
+    }
+}
+
+/// a convar or a concommand yielded by [`CVarIter`]
+#[derive(Clone, Copy)]
+pub struct CVarEntry {
+    base: &'static ConCommandBase,
+    is_command: bool,
+}
+
+impl CVarEntry {
+    /// the name of the convar or concommand
+    pub fn get_name(&self) -> Cow<'static, str> {
+        unsafe { cstr_or_empty(self.base.m_pszName) }
+    }
+
+    /// the help text of the convar or concommand
+    pub fn get_help_text(&self) -> Cow<'static, str> {
+        unsafe { cstr_or_empty(self.base.m_pszHelpString) }
+    }
+
+    /// the flags of the convar or concommand
+    pub const fn get_flags(&self) -> CVarFlags {
+        CVarFlags::from_bits_retain(self.base.m_nFlags)
+    }
+
+    /// returns [`true`] if any of the `flags` are set
+    pub const fn has_flags(&self, flags: CVarFlags) -> bool {
+        self.get_flags().intersects(flags)
+    }
+
+    /// returns [`true`] if this is a concommand
+    pub const fn is_command(&self) -> bool {
+        self.is_command
+    }
+
+    /// returns [`true`] if this is a convar
+    pub const fn is_convar(&self) -> bool {
+        !self.is_command
+    }
+
+    /// the current value if this is a convar
+    pub fn get_value(&self) -> Option<String> {
+        self.as_convar().map(|convar| convar.get_value_string())
+    }
+
+    /// the convar if this is a convar
+    pub fn as_convar(&self) -> Option<ConVarStruct> {
+        if self.is_command {
+            return None;
+        }
+
+        // the ConCommandBase is the first field of ConVar
+        let convar = self.base as *const ConCommandBase as *mut ConVar;
+        unsafe { ConVarStruct::from_raw(convar) }
+    }
+
+    /// the underlying [`ConCommandBase`]
+    pub const fn get_raw(&self) -> &'static ConCommandBase {
+        self.base
+    }
+
+    /// returns [`true`] if the name or the help text contains `lowercase_pattern`
+    pub fn matches(&self, lowercase_pattern: &str) -> bool {
+        self.get_name().to_lowercase().contains(lowercase_pattern)
+            || self
+                .get_help_text()
+                .to_lowercase()
+                .contains(lowercase_pattern)
+    }
+}
+
+impl std::fmt::Debug for CVarEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CVarEntry")
+            .field("name", &self.get_name())
+            .field("flags", &self.get_flags().to_string())
+            .field("is_command", &self.is_command)
+            .finish()
+    }
+}
+
+/// calls `ConCommandBase::IsCommand` which comes right after the destructor in the vtable
+///
+/// # Safety
+///
+/// `base` has to be a convar or a concommand created by the engine or a game dll
+unsafe fn is_command(base: &ConCommandBase) -> bool {
+    const IS_COMMAND: usize = 1;
+
+    unsafe {
+        let vtable = base.m_pConCommandBaseVTable as *const usize;
+        let is_command = std::mem::transmute::<
+            usize,
+            unsafe extern "C" fn(*const ConCommandBase) -> bool,
+        >(*vtable.add(IS_COMMAND));
+
+        is_command(base)
+    }
+}
+
+unsafe fn cstr_or_empty(ptr: *const std::ffi::c_char) -> Cow<'static, str> {
+    if ptr.is_null() {
+        Cow::Borrowed("")
+    } else {
+        unsafe { CStr::from_ptr(ptr) }.to_string_lossy()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{ffi::CString, mem::MaybeUninit};
+
+    use super::*;
+
+    extern "C" fn concommand_is_command(_: *const ConCommandBase) -> bool {
+        true
+    }
+
+    #[test]
+    fn entry_filters() {
+        let name = CString::new("cool_convar").unwrap();
+        let help = CString::new("A Cool Convar").unwrap();
+
+        let mut base = unsafe { MaybeUninit::<ConCommandBase>::zeroed().assume_init() };
+        base.m_pszName = name.as_ptr();
+        base.m_pszHelpString = help.as_ptr();
+        base.m_nFlags = (CVarFlags::UNREGISTERED | CVarFlags::GAMEDLL).bits();
+
+        let vtable: &'static [usize] = Box::leak(Box::new([
+            0,
+            concommand_is_command as extern "C" fn(_) -> _ as usize,
+        ]));
+        base.m_pConCommandBaseVTable = vtable.as_ptr() as *mut c_void;
+
+        let base = Box::leak(Box::new(base));
+        let entry = CVarEntry {
+            base,
+            is_command: unsafe { is_command(base) },
+        };
+        assert!(entry.is_command());
+
+        assert_eq!(entry.get_name(), "cool_convar");
+        assert!(entry.has_flags(CVarFlags::GAMEDLL));
+        assert!(!entry.has_flags(CVarFlags::DEVELOPMENTONLY));
+        assert_eq!(entry.get_flags().to_string(), "UNREGISTERED | GAMEDLL");
+        assert!(entry.matches("cool"));
+        assert!(entry.matches("a cool"));
+        assert!(!entry.matches("hot"));
+        assert!(entry.as_convar().is_none());
+        assert!(entry.get_value().is_none());
+        assert_eq!(std::iter::once(entry).convars().count(), 0);
+        assert_eq!(std::iter::once(entry).search("COOL").count(), 1);
+    }
+}
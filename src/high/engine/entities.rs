@@ -0,0 +1,381 @@
+//! entity lookup by index and by handle on the server and the client
+//!
+//! handles are the `i32`s stored in fields like [`CPlayer`]'s `pet_titan` or `observer_target`; [`Player`] has getters for them
+//!
+//! the client entity list and the `GetRefEHandle` vtable slot used to check handles are laid out like in the source sdk and haven't been verified against titanfall 2 yet,
+//! so every function relying on them is unsafe until they are
+//!
+//! ```no_run
+//! use rrplug::prelude::*;
+//! use rrplug::high::engine::entities::{server_entity_by_index, server_entity_from_handle};
+//!
+//! # let engine_token = unsafe { EngineToken::new_unchecked() };
+//! let Some(mut entity) = server_entity_by_index(1, engine_token) else {
+//!     return;
+//! };
+//! // nothing else is holding onto this player
+//! let Some(player) = (unsafe { entity.as_player() }) else {
+//!     return;
+//! };
+//!
+//! if let Some(titan) = unsafe { server_entity_from_handle(player.pet_titan(), engine_token) } {
+//!     log::info!("player 1 has a titan at index {}", titan.index());
+//! }
+//! ```
+
+use once_cell::sync::OnceCell;
+use std::{ffi::c_void, ptr::NonNull};
+
+use super::{globalvars::global_vars, player::Player, EngineToken};
+use crate::{
+    bindings::class_types::{
+        cplayer::CPlayer,
+        entity_list::{
+            RawClientEntityList, ENT_ENTRY_MASK, INVALID_EHANDLE_INDEX, NUM_ENT_ENTRIES,
+            NUM_ENT_ENTRY_BITS,
+        },
+    },
+    high::UnsafeHandle,
+    interfaces::external::SourceInterface,
+    mid::server::SERVER_ENTITIES,
+};
+
+/// a handle to an entity which stays invalid if the entity is removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct EHandle(pub u32);
+
+impl EHandle {
+    /// a handle which doesn't point to anything
+    pub const INVALID: Self = Self(INVALID_EHANDLE_INDEX);
+
+    /// creates a handle from an index and a serial number
+    pub const fn new(index: usize, serial: u32) -> Self {
+        Self((serial << NUM_ENT_ENTRY_BITS) | (index as u32 & ENT_ENTRY_MASK))
+    }
+
+    /// the index of the entity
+    ///
+    /// returns [`None`] for [`EHandle::INVALID`]
+    pub const fn index(&self) -> Option<usize> {
+        if self.is_valid() {
+            Some((self.0 & ENT_ENTRY_MASK) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// the serial number which changes when the slot of the entity is reused
+    pub const fn serial(&self) -> u32 {
+        self.0 >> NUM_ENT_ENTRY_BITS
+    }
+
+    /// returns [`false`] for [`EHandle::INVALID`]
+    pub const fn is_valid(&self) -> bool {
+        self.0 != INVALID_EHANDLE_INDEX
+    }
+}
+
+impl Default for EHandle {
+    fn default() -> Self {
+        Self::INVALID
+    }
+}
+
+impl From<i32> for EHandle {
+    fn from(handle: i32) -> Self {
+        Self(handle as u32)
+    }
+}
+
+impl From<EHandle> for i32 {
+    fn from(handle: EHandle) -> Self {
+        handle.0 as i32
+    }
+}
+
+/// the handle an entity has of itself from `IHandleEntity::GetRefEHandle`
+///
+/// # Safety
+///
+/// `entity` has to be a valid entity
+unsafe fn ref_handle(entity: NonNull<c_void>) -> EHandle {
+    // `IHandleEntity` from the source sdk (destructor, `SetRefEHandle`, `GetRefEHandle`); not verified against titanfall 2 yet
+    const GET_REF_EHANDLE: usize = 2;
+
+    unsafe {
+        let vtable = *entity.as_ptr().cast::<*const usize>();
+        let get_ref_ehandle = std::mem::transmute::<
+            usize,
+            unsafe extern "C" fn(*const c_void) -> *const u32,
+        >(*vtable.add(GET_REF_EHANDLE));
+
+        get_ref_ehandle(entity.as_ptr())
+            .as_ref()
+            .map(|handle| EHandle(*handle))
+            .unwrap_or_default()
+    }
+}
+
+/// resolves a handle with a lookup by index and checks that the entity in the slot is still the same
+///
+/// # Safety
+///
+/// `lookup` has to return null or valid entities
+unsafe fn resolve_handle(
+    handle: EHandle,
+    lookup: impl FnOnce(usize) -> *mut c_void,
+) -> Option<(NonNull<c_void>, usize)> {
+    let index = handle.index()?;
+    let entity = NonNull::new(lookup(index))?;
+
+    (unsafe { ref_handle(entity) } == handle).then_some((entity, index))
+}
+
+/// an entity on the server
+///
+/// the entity can be removed by the engine at any point outside of the current engine callback
+#[derive(Clone, Copy)]
+pub struct ServerEntity {
+    entity: NonNull<c_void>,
+    index: usize,
+    token: EngineToken,
+}
+
+impl ServerEntity {
+    /// the raw entity
+    pub const fn get_raw(&self) -> *mut c_void {
+        self.entity.as_ptr()
+    }
+
+    /// the index of the entity
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// the handle of the entity
+    ///
+    /// # Safety
+    ///
+    /// the entity has to still exist and the `GetRefEHandle` vtable slot isn't verified yet
+    pub unsafe fn handle(&self) -> EHandle {
+        unsafe { ref_handle(self.entity) }
+    }
+
+    /// returns [`true`] if the entity is in a player slot
+    pub fn is_player(&self) -> bool {
+        global_vars(self.token)
+            .map(|globals| (1..=globals.max_clients().max(0) as usize).contains(&self.index))
+            .unwrap_or_default()
+    }
+
+    /// the entity as a player if it's in a player slot
+    ///
+    /// # Safety
+    ///
+    /// the entity has to still exist and no other reference to the player can be alive;
+    /// copies of this [`ServerEntity`] and [`crate::high::engine::clients::Client`] point to the same player
+    pub unsafe fn as_player(&mut self) -> Option<Player<'_>> {
+        self.is_player().then(|| {
+            Player::new(
+                unsafe { self.entity.cast::<CPlayer>().as_mut() },
+                self.token,
+            )
+        })
+    }
+}
+
+/// the server entity at an index
+///
+/// returns [`None`] if the slot is empty or server.dll isn't loaded yet
+pub fn server_entity_by_index(index: usize, token: EngineToken) -> Option<ServerEntity> {
+    let entity = NonNull::new(server_lookup(index)?)?;
+
+    Some(ServerEntity {
+        entity,
+        index,
+        token,
+    })
+}
+
+/// the server entity a handle points to
+///
+/// returns [`None`] if the handle is invalid, the entity was removed or server.dll isn't loaded yet
+///
+/// # Safety
+///
+/// the `GetRefEHandle` vtable slot isn't verified yet
+pub unsafe fn server_entity_from_handle(
+    handle: EHandle,
+    token: EngineToken,
+) -> Option<ServerEntity> {
+    let (entity, index) = unsafe {
+        resolve_handle(handle, |index| {
+            server_lookup(index).unwrap_or(std::ptr::null_mut())
+        })?
+    };
+
+    Some(ServerEntity {
+        entity,
+        index,
+        token,
+    })
+}
+
+/// every entity on the server
+pub fn server_entities(token: EngineToken) -> impl Iterator<Item = ServerEntity> {
+    (0..NUM_ENT_ENTRIES).filter_map(move |index| server_entity_by_index(index, token))
+}
+
+fn server_lookup(index: usize) -> Option<*mut c_void> {
+    let index = i32::try_from(index).ok()?;
+    Some(unsafe { (SERVER_ENTITIES.get()?.get_entity_by_index)(index) })
+}
+
+/// an entity on the client
+#[derive(Clone, Copy)]
+pub struct ClientEntity {
+    entity: NonNull<c_void>,
+    index: usize,
+    _token: EngineToken,
+}
+
+impl ClientEntity {
+    /// the raw entity
+    pub const fn get_raw(&self) -> *mut c_void {
+        self.entity.as_ptr()
+    }
+
+    /// the index of the entity
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// the handle of the entity
+    ///
+    /// # Safety
+    ///
+    /// see [`ServerEntity::handle`]
+    pub unsafe fn handle(&self) -> EHandle {
+        unsafe { ref_handle(self.entity) }
+    }
+}
+
+fn client_entity_list() -> Option<&'static RawClientEntityList> {
+    static CLIENT_ENTITY_LIST: OnceCell<UnsafeHandle<&'static RawClientEntityList>> =
+        OnceCell::new();
+
+    CLIENT_ENTITY_LIST
+        .get_or_try_init(|| {
+            unsafe { RawClientEntityList::from_dll_name("client.dll", "VClientEntityList003") }
+                .map(UnsafeHandle::internal_new)
+                .ok_or(())
+        })
+        .ok()
+        .map(|list| *list.get())
+}
+
+/// the client entity at an index
+///
+/// returns [`None`] if the slot is empty or client.dll isn't loaded yet
+///
+/// # Safety
+///
+/// the layout of [`RawClientEntityList`] isn't verified yet
+pub unsafe fn client_entity_by_index(index: usize, token: EngineToken) -> Option<ClientEntity> {
+    let entity = NonNull::new(unsafe {
+        client_entity_list()?.get_client_entity(i32::try_from(index).ok()?)
+    })?;
+
+    Some(ClientEntity {
+        entity,
+        index,
+        _token: token,
+    })
+}
+
+/// the client entity a handle points to
+///
+/// returns [`None`] if the handle is invalid, the entity was removed or client.dll isn't loaded yet
+///
+/// # Safety
+///
+/// the layout of [`RawClientEntityList`] and the `GetRefEHandle` vtable slot aren't verified yet
+pub unsafe fn client_entity_from_handle(
+    handle: EHandle,
+    token: EngineToken,
+) -> Option<ClientEntity> {
+    let list = client_entity_list()?;
+    let (entity, index) = unsafe {
+        resolve_handle(handle, |index| {
+            i32::try_from(index)
+                .map(|index| list.get_client_entity(index))
+                .unwrap_or(std::ptr::null_mut())
+        })?
+    };
+
+    Some(ClientEntity {
+        entity,
+        index,
+        _token: token,
+    })
+}
+
+/// every entity on the client
+///
+/// # Safety
+///
+/// see [`client_entity_by_index`]
+pub unsafe fn client_entities(token: EngineToken) -> impl Iterator<Item = ClientEntity> {
+    let highest = client_entity_list()
+        .map(|list| unsafe { list.get_highest_entity_index() })
+        .unwrap_or(-1);
+
+    (0..=highest).filter_map(move |index| unsafe { client_entity_by_index(index as usize, token) })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[repr(C)]
+    struct FakeEntity {
+        vtable: *const usize,
+        handle: u32,
+    }
+
+    extern "C" fn get_ref_ehandle(entity: *const c_void) -> *const u32 {
+        unsafe { &(*entity.cast::<FakeEntity>()).handle }
+    }
+
+    #[test]
+    fn handles() {
+        let handle = EHandle::new(5, 3);
+        assert_eq!(handle.index(), Some(5));
+        assert_eq!(handle.serial(), 3);
+        assert_eq!(EHandle::from(-1), EHandle::INVALID);
+        assert_eq!(EHandle::INVALID.index(), None);
+        assert_eq!(i32::from(handle), (3 << NUM_ENT_ENTRY_BITS) | 5);
+
+        let vtable = [0, 0, get_ref_ehandle as extern "C" fn(_) -> _ as usize];
+        let mut entity = FakeEntity {
+            vtable: vtable.as_ptr(),
+            handle: handle.0,
+        };
+        let entity_ptr = &mut entity as *mut FakeEntity as *mut c_void;
+
+        let resolved = unsafe {
+            resolve_handle(handle, |index| {
+                assert_eq!(index, 5);
+                entity_ptr
+            })
+        };
+        assert_eq!(resolved.map(|(_, index)| index), Some(5));
+
+        // the slot was reused by another entity
+        let stale = EHandle::new(5, 2);
+        assert!(unsafe { resolve_handle(stale, |_| entity_ptr) }.is_none());
+        assert!(unsafe { resolve_handle(handle, |_| std::ptr::null_mut()) }.is_none());
+        assert!(unsafe { resolve_handle(EHandle::INVALID, |_| entity_ptr) }.is_none());
+    }
+}
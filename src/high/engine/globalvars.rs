@@ -0,0 +1,162 @@
+//! game time, ticks and map information from the engine's [`CGlobalVars`]
+//!
+//! use this instead of wall time for timers since it follows pauses, `host_timescale` and the tick rate of the server
+//!
+//! only the server's globals are available; the client's copy isn't bound yet so client side code can't use these
+//!
+//! ```no_run
+//! use rrplug::prelude::*;
+//! use rrplug::high::engine::globalvars::global_vars;
+//!
+//! # let engine_token = unsafe { EngineToken::new_unchecked() };
+//! if let Some(globals) = global_vars(engine_token) {
+//!     log::info!(
+//!         "tick {} at {}s on {}",
+//!         globals.tick_count(),
+//!         globals.cur_time(),
+//!         globals.map_name().unwrap_or_default()
+//!     );
+//! }
+//! ```
+
+use super::EngineToken;
+use crate::{
+    bindings::class_types::globalvars::CGlobalVars,
+    mid::{engine::globalvars::GLOBAL_VARS, utils::str_from_char_ptr},
+};
+
+/// the server's [`CGlobalVars`] which can only be read on the engine thread
+#[derive(Clone, Copy)]
+pub struct GlobalVars {
+    globals: &'static CGlobalVars,
+    _token: EngineToken,
+}
+
+/// the server's globals
+///
+/// there is no client equivalent yet since the address of the client's globals isn't known
+///
+/// returns [`None`] if engine.dll isn't loaded yet
+pub fn global_vars(token: EngineToken) -> Option<GlobalVars> {
+    // the globals are a static in engine.dll
+    let globals = unsafe { GLOBAL_VARS.get()?.server_globals.as_ref()? };
+
+    Some(unsafe { GlobalVars::new(globals, token) })
+}
+
+impl GlobalVars {
+    /// # Safety
+    ///
+    /// `globals` has to be the engine's globals or something with the same layout
+    pub(crate) const unsafe fn new(globals: &'static CGlobalVars, token: EngineToken) -> Self {
+        Self {
+            globals,
+            _token: token,
+        }
+    }
+
+    /// absolute time updated every frame; keeps increasing while paused
+    pub fn real_time(&self) -> f64 {
+        unsafe { self.globals.real_time.copy_inner() }
+    }
+
+    /// frames since the game started; keeps increasing while paused
+    pub fn frame_count(&self) -> i32 {
+        unsafe { self.globals.frame_count.copy_inner() }
+    }
+
+    /// the duration of the last frame ignoring pauses
+    pub fn absolute_frame_time(&self) -> f32 {
+        unsafe { self.globals.absolute_frame_time.copy_inner() }
+    }
+
+    /// the current game time in seconds
+    pub fn cur_time(&self) -> f32 {
+        unsafe { self.globals.cur_time.copy_inner() }
+    }
+
+    /// the duration of the last frame
+    pub fn frame_time(&self) -> f32 {
+        unsafe { self.globals.frame_time.copy_inner() }
+    }
+
+    /// the max amount of players on the server
+    pub fn max_clients(&self) -> i32 {
+        unsafe { self.globals.max_clients.copy_inner() }
+    }
+
+    /// the current simulation tick; doesn't increase while paused
+    pub fn tick_count(&self) -> u32 {
+        unsafe { self.globals.tick_count.copy_inner() }
+    }
+
+    /// the duration of a tick in seconds
+    pub fn interval_per_tick(&self) -> f32 {
+        unsafe { self.globals.interval_per_tick.copy_inner() }
+    }
+
+    /// the amount of ticks per second
+    pub fn tick_rate(&self) -> f32 {
+        1. / self.interval_per_tick()
+    }
+
+    /// the name of the loaded map like `mp_forwardbase_kodai`
+    pub fn map_name(&self) -> Option<String> {
+        unsafe { str_from_char_ptr(self.globals.map_name.copy_inner()) }
+            .filter(|name| !name.is_empty())
+            .map(ToString::to_string)
+    }
+
+    /// the version of the loaded map
+    pub fn map_version(&self) -> i32 {
+        unsafe { self.globals.map_version.copy_inner() }
+    }
+}
+
+impl std::fmt::Debug for GlobalVars {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GlobalVars")
+            .field("real_time", &self.real_time())
+            .field("cur_time", &self.cur_time())
+            .field("tick_count", &self.tick_count())
+            .field("interval_per_tick", &self.interval_per_tick())
+            .field("max_clients", &self.max_clients())
+            .field("map_name", &self.map_name())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::CString;
+
+    use super::*;
+    use crate::high::engine::test::{fake_struct, write};
+
+    #[test]
+    fn read_globals() {
+        let map_name = CString::new("mp_forwardbase_kodai").unwrap();
+        let mut raw = fake_struct(0x70);
+        write(&mut raw, 0x0, &12.5f64.to_le_bytes());
+        write(&mut raw, 0x10, &10.25f32.to_le_bytes());
+        write(&mut raw, 0x34, &16i32.to_le_bytes());
+        write(&mut raw, 0x3C, &600u32.to_le_bytes());
+        write(&mut raw, 0x40, &(1f32 / 60.).to_le_bytes());
+        write(&mut raw, 0x60, &(map_name.as_ptr() as usize).to_le_bytes());
+
+        let raw: &'static [u64] = raw.leak();
+        let globals = unsafe {
+            GlobalVars::new(
+                &*raw.as_ptr().cast::<CGlobalVars>(),
+                EngineToken::new_unchecked(),
+            )
+        };
+
+        assert_eq!(globals.real_time(), 12.5);
+        assert_eq!(globals.cur_time(), 10.25);
+        assert_eq!(globals.max_clients(), 16);
+        assert_eq!(globals.tick_count(), 600);
+        assert_eq!(globals.tick_rate().round(), 60.);
+        assert_eq!(globals.map_name().as_deref(), Some("mp_forwardbase_kodai"));
+    }
+}
@@ -0,0 +1,364 @@
+//! read only traversal of source's [`KeyValues`] trees
+//!
+//! building new trees isn't supported since the engine's constructor isn't bound
+//!
+//! ```no_run
+//! use rrplug::prelude::*;
+//! use rrplug::high::engine::clients::clients;
+//!
+//! # let engine_token = unsafe { EngineToken::new_unchecked() };
+//! for client in clients(engine_token) {
+//!     // the tree is read within this callback
+//!     let Some(con_vars) = (unsafe { client.con_vars() }) else {
+//!         continue;
+//!     };
+//!
+//!     log::info!(
+//!         "{} has a fov scale of {:?}",
+//!         con_vars.get_string("name").unwrap_or_default(),
+//!         con_vars.get_float("cl_fovScale")
+//!     );
+//!
+//!     for convar in con_vars.children() {
+//!         log::info!("{:?} = {:?}", convar.name(), convar.as_string());
+//!     }
+//! }
+//! ```
+
+use once_cell::sync::OnceCell;
+use std::ffi::{c_char, CStr};
+use windows::{
+    core::PCSTR,
+    Win32::System::LibraryLoader::{GetModuleHandleA, GetProcAddress},
+};
+
+use super::EngineToken;
+use crate::{
+    bindings::class_types::keyvalues::{IKeyValuesSystem, KeyValues, KeyValuesType},
+    high::UnsafeHandle,
+};
+
+/// a node of a [`KeyValues`] tree which can only be read on the engine thread
+#[derive(Clone, Copy)]
+pub struct KeyValuesRef<'a> {
+    kv: &'a KeyValues,
+    system: &'a IKeyValuesSystem,
+    token: EngineToken,
+}
+
+/// iterator over a node and its following siblings
+///
+/// created with [`KeyValuesRef::children`]
+#[derive(Clone)]
+pub struct KeyValuesIter<'a> {
+    next: Option<KeyValuesRef<'a>>,
+}
+
+/// the system from vstdlib.dll which owns the key names
+///
+/// returns [`None`] if vstdlib.dll isn't loaded
+pub fn key_values_system() -> Option<&'static IKeyValuesSystem> {
+    static KEY_VALUES_SYSTEM: OnceCell<UnsafeHandle<&'static IKeyValuesSystem>> = OnceCell::new();
+
+    KEY_VALUES_SYSTEM
+        .get_or_try_init(|| unsafe {
+            let vstdlib =
+                GetModuleHandleA(PCSTR(c"vstdlib.dll".as_ptr().cast())).map_err(|_| ())?;
+            let key_values_system = std::mem::transmute::<
+                unsafe extern "system" fn() -> isize,
+                unsafe extern "C" fn() -> *const IKeyValuesSystem,
+            >(
+                GetProcAddress(vstdlib, PCSTR(c"KeyValuesSystem".as_ptr().cast())).ok_or(())?,
+            );
+
+            key_values_system()
+                .as_ref()
+                .map(UnsafeHandle::internal_new)
+                .ok_or(())
+        })
+        .ok()
+        .map(|system| *system.get())
+}
+
+impl<'a> KeyValuesRef<'a> {
+    /// wraps the root of a tree
+    ///
+    /// returns [`None`] if vstdlib.dll isn't loaded
+    ///
+    /// # Safety
+    ///
+    /// every node reachable from `kv` has to stay valid for `'a`;
+    /// the [`KeyValues`] and [`IKeyValuesSystem`] layouts are from the source sdk and aren't verified against titanfall 2 yet
+    pub unsafe fn new(kv: &'a KeyValues, token: EngineToken) -> Option<Self> {
+        Some(unsafe { Self::with_system(kv, key_values_system()?, token) })
+    }
+
+    /// # Safety
+    ///
+    /// every node reachable from `kv` has to stay valid for `'a`
+    pub(crate) const unsafe fn with_system(
+        kv: &'a KeyValues,
+        system: &'a IKeyValuesSystem,
+        token: EngineToken,
+    ) -> Self {
+        Self { kv, system, token }
+    }
+
+    /// the raw node
+    pub const fn get_raw(&self) -> &'a KeyValues {
+        self.kv
+    }
+
+    /// the name of the key
+    pub fn name(&self) -> Option<&'a str> {
+        unsafe { str_from_ptr(self.system.get_string_for_symbol(self.kv.key_symbol())) }
+    }
+
+    /// the type of the value; [`KeyValuesType::NONE`] for nodes with children
+    ///
+    /// returns [`None`] if the engine wrote an unknown type
+    pub fn data_type(&self) -> Option<KeyValuesType> {
+        self.kv.value_type()
+    }
+
+    /// the first child of this node
+    pub fn first_child(&self) -> Option<Self> {
+        self.wrap(self.kv.sub)
+    }
+
+    /// the node after this one with the same parent
+    pub fn next_sibling(&self) -> Option<Self> {
+        self.wrap(self.kv.peer)
+    }
+
+    /// iterates the children of this node
+    pub fn children(&self) -> KeyValuesIter<'a> {
+        KeyValuesIter {
+            next: self.first_child(),
+        }
+    }
+
+    /// finds a child by its name ignoring case
+    ///
+    /// the name can be a path separated by `/` to find nested children like `"settings/video"`
+    pub fn find(&self, path: &str) -> Option<Self> {
+        path.split('/')
+            .filter(|key| !key.is_empty())
+            .try_fold(*self, |node, key| {
+                node.children().find(|child| {
+                    child
+                        .name()
+                        .is_some_and(|name| name.eq_ignore_ascii_case(key))
+                })
+            })
+    }
+
+    /// the value of this node converted to a string
+    ///
+    /// returns [`None`] for nodes without a value or with a pointer or color
+    pub fn as_string(&self) -> Option<String> {
+        let kv = self.kv;
+        match self.data_type()? {
+            KeyValuesType::STRING => unsafe { str_from_ptr(kv.value_string) }.map(str::to_string),
+            KeyValuesType::WSTRING if !kv.value_wstring.is_null() => {
+                let wide = unsafe {
+                    let len = (0..).take_while(|i| *kv.value_wstring.add(*i) != 0).count();
+                    std::slice::from_raw_parts(kv.value_wstring, len)
+                };
+                Some(String::from_utf16_lossy(wide))
+            }
+            KeyValuesType::INT => Some(unsafe { kv.value.int }.to_string()),
+            KeyValuesType::FLOAT => Some(unsafe { kv.value.float }.to_string()),
+            KeyValuesType::UINT64 => self.as_u64().map(|value| value.to_string()),
+            _ => None,
+        }
+    }
+
+    /// the value of this node converted to an int
+    ///
+    /// strings are parsed and floats are truncated
+    pub fn as_int(&self) -> Option<i32> {
+        match self.data_type()? {
+            KeyValuesType::INT => Some(unsafe { self.kv.value.int }),
+            KeyValuesType::FLOAT => Some(unsafe { self.kv.value.float } as i32),
+            KeyValuesType::UINT64 => self.as_u64().map(|value| value as i32),
+            KeyValuesType::STRING | KeyValuesType::WSTRING => self.as_string()?.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// the value of this node converted to a float
+    ///
+    /// strings are parsed
+    pub fn as_float(&self) -> Option<f32> {
+        match self.data_type()? {
+            KeyValuesType::INT => Some(unsafe { self.kv.value.int } as f32),
+            KeyValuesType::FLOAT => Some(unsafe { self.kv.value.float }),
+            KeyValuesType::UINT64 => self.as_u64().map(|value| value as f32),
+            KeyValuesType::STRING | KeyValuesType::WSTRING => self.as_string()?.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// the value of a child converted to a string; see [`KeyValuesRef::find`] and [`KeyValuesRef::as_string`]
+    pub fn get_string(&self, path: &str) -> Option<String> {
+        self.find(path)?.as_string()
+    }
+
+    /// the value of a child converted to an int; see [`KeyValuesRef::find`] and [`KeyValuesRef::as_int`]
+    pub fn get_int(&self, path: &str) -> Option<i32> {
+        self.find(path)?.as_int()
+    }
+
+    /// the value of a child converted to a float; see [`KeyValuesRef::find`] and [`KeyValuesRef::as_float`]
+    pub fn get_float(&self, path: &str) -> Option<f32> {
+        self.find(path)?.as_float()
+    }
+
+    const fn as_u64(&self) -> Option<u64> {
+        // uint64s are stored behind the string pointer
+        unsafe { self.kv.value_string.cast::<u64>().as_ref() }.copied()
+    }
+
+    fn wrap(&self, kv: *mut KeyValues) -> Option<Self> {
+        Some(unsafe { Self::with_system(kv.as_ref()?, self.system, self.token) })
+    }
+}
+
+impl<'a> Iterator for KeyValuesIter<'a> {
+    type Item = KeyValuesRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.take()?;
+        self.next = current.next_sibling();
+        Some(current)
+    }
+}
+
+impl std::fmt::Debug for KeyValuesRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyValuesRef")
+            .field("name", &self.name())
+            .field("data_type", &self.data_type())
+            .field("value", &self.as_string())
+            .finish()
+    }
+}
+
+unsafe fn str_from_ptr<'a>(ptr: *const c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(ptr) }.to_str().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::c_void;
+
+    use super::*;
+    use crate::bindings::class_types::keyvalues::KeyValuesValue;
+
+    const NAMES: [&CStr; 6] = [
+        c"userinfo",
+        c"name",
+        c"cl_fovScale",
+        c"rate",
+        c"video",
+        c"mode",
+    ];
+
+    extern "C" fn get_string_for_symbol(_: *const c_void, symbol: i32) -> *const c_char {
+        NAMES
+            .get(symbol as usize)
+            .map(|name| name.as_ptr())
+            .unwrap_or(std::ptr::null())
+    }
+
+    fn node(symbol: u32, data_type: KeyValuesType, value: KeyValuesValue) -> Box<KeyValues> {
+        Box::new(KeyValues {
+            // the high bits aren't part of the symbol
+            key_name: symbol | 0xAB00_0000,
+            value_string: std::ptr::null_mut(),
+            value_wstring: std::ptr::null_mut(),
+            value,
+            short_name: [0; 8],
+            data_type: data_type as u8,
+            has_escape_sequences: 0,
+            key_name_case_sensitive: 0,
+            peer: std::ptr::null_mut(),
+            sub: std::ptr::null_mut(),
+            chain: std::ptr::null_mut(),
+        })
+    }
+
+    #[test]
+    fn traverse_tree() {
+        let vtable = [
+            0,
+            0,
+            0,
+            0,
+            get_string_for_symbol as extern "C" fn(_, _) -> _ as usize,
+        ];
+        let vtable = vtable.as_ptr();
+        let system = unsafe { &*(&vtable as *const *const usize).cast::<IKeyValuesSystem>() };
+
+        let empty = KeyValuesValue { int: 0 };
+        let player_name = c"cat_or_not";
+        let mode = c" 2 ";
+
+        let mut mode_node = node(5, KeyValuesType::STRING, empty);
+        mode_node.value_string = mode.as_ptr().cast_mut();
+        let mut video = node(4, KeyValuesType::NONE, empty);
+        video.sub = &mut *mode_node;
+        let mut rate = node(3, KeyValuesType::INT, KeyValuesValue { int: 786432 });
+        rate.peer = &mut *video;
+        let mut fov = node(2, KeyValuesType::FLOAT, KeyValuesValue { float: 1.5 });
+        fov.peer = &mut *rate;
+        let mut name = node(1, KeyValuesType::STRING, empty);
+        name.value_string = player_name.as_ptr().cast_mut();
+        name.peer = &mut *fov;
+        let mut root = node(0, KeyValuesType::NONE, empty);
+        root.sub = &mut *name;
+
+        let root =
+            unsafe { KeyValuesRef::with_system(&root, system, EngineToken::new_unchecked()) };
+
+        assert_eq!(root.name(), Some("userinfo"));
+        assert_eq!(
+            root.children()
+                .map(|child| child.name())
+                .collect::<Vec<_>>(),
+            [
+                Some("name"),
+                Some("cl_fovScale"),
+                Some("rate"),
+                Some("video")
+            ]
+        );
+
+        assert_eq!(root.get_string("NAME").as_deref(), Some("cat_or_not"));
+        assert_eq!(root.get_float("cl_fovscale"), Some(1.5));
+        assert_eq!(root.get_int("cl_fovscale"), Some(1));
+        assert_eq!(root.get_int("rate"), Some(786432));
+        assert_eq!(root.get_string("rate").as_deref(), Some("786432"));
+        assert_eq!(root.get_int("video/mode"), Some(2));
+        assert_eq!(root.get_string("video"), None);
+        assert_eq!(root.get_int("name"), None);
+        assert!(root.find("video/missing").is_none());
+        assert!(root.find("mode").is_none());
+        assert_eq!(
+            root.find("video").map(|video| video.data_type()),
+            Some(Some(KeyValuesType::NONE))
+        );
+
+        // unknown types from the engine aren't read as any value
+        let mut unknown = node(0, KeyValuesType::INT, KeyValuesValue { int: 1 });
+        unknown.data_type = 0xFF;
+        let unknown =
+            unsafe { KeyValuesRef::with_system(&unknown, system, EngineToken::new_unchecked()) };
+        assert_eq!(unknown.data_type(), None);
+        assert_eq!(unknown.as_int(), None);
+    }
+}
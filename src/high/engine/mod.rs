@@ -3,21 +3,31 @@
 use parking_lot::Mutex;
 use std::{cell::UnsafeCell, marker::PhantomData};
 
+pub mod cbuf;
+pub mod clients;
+pub mod concommand_hooks;
 pub mod concommands;
 pub mod convars;
+pub mod cvar_dump;
+pub mod cvar_iter;
+pub mod entities;
+pub mod globalvars;
+pub mod keyvalues;
+pub mod player;
+pub mod statics;
 
 use crate::{
     bindings::cvar::{
-        command::{CCommand, ConCommand},
-        convar::COMMAND_COMPLETION_ITEM_LENGTH,
-        RawCVar,
+        command::CCommand, convar::COMMAND_COMPLETION_ITEM_LENGTH, CVarFlags, RawCVar,
     },
     errors::RegisterError,
     mid::engine::{
         concommands::{RegisterConCommands, REGISTER_CONCOMNMADS},
         convars::{CvarGlobals, CVAR_GLOBALS},
+        tracking,
     },
 };
+use concommands::ConCommandHandle;
 #[cfg(doc)]
 use convars::ConVarStruct;
 
@@ -105,7 +115,7 @@ impl EngineData {
 
     /// registers a command
     ///
-    /// returns a pointer to [`ConCommand`] which is unsafe to access and has a static lifetime
+    /// returns a [`ConCommandHandle`] which can be used to unregister the concommand
     ///
     ///  # Example
     /// ```no_run
@@ -114,7 +124,7 @@ impl EngineData {
     /// # use rrplug::prelude::*;
     /// # let engine = get_engine_data().unwrap();
     /// # let engine_token = unsafe { EngineToken::new_unchecked() };
-    /// engine.register_concommand("cool_command", cool_command, "this is cool_command", 0, engine_token).expect("failed to register cool_command");
+    /// engine.register_concommand("cool_command", cool_command, "this is cool_command", CVarFlags::empty(), engine_token).expect("failed to register cool_command");
     ///
     /// #[rrplug::concommand]
     /// fn cool_command() {
@@ -126,14 +136,15 @@ impl EngineData {
         name: impl AsRef<str>,
         callback: unsafe extern "C" fn(arg1: *const CCommand),
         help_string: impl AsRef<str>,
-        flags: i32,
+        flags: CVarFlags,
         _: EngineToken,
-    ) -> Result<*mut ConCommand, RegisterError> {
+    ) -> Result<ConCommandHandle, RegisterError> {
         let name = name.as_ref();
         log::info!("Registering ConCommand {}", name);
 
         self.concommands
-            .mid_register_concommand(name, callback, help_string.as_ref(), flags)
+            .mid_register_concommand(name, callback, help_string.as_ref(), flags.bits())
+            .map(ConCommandHandle::new)
     }
 
     pub fn register_concommand_with_completion(
@@ -141,23 +152,25 @@ impl EngineData {
         name: impl AsRef<str>,
         callback: unsafe extern "C" fn(arg1: *const CCommand),
         help_string: impl AsRef<str>,
-        flags: i32,
+        flags: CVarFlags,
         completion_callback: unsafe extern "C" fn(
             arg1: *const ::std::os::raw::c_char,
             arg2: *mut [::std::os::raw::c_char; COMMAND_COMPLETION_ITEM_LENGTH as usize],
         ) -> ::std::os::raw::c_int,
         _: EngineToken,
-    ) -> Result<*mut ConCommand, RegisterError> {
+    ) -> Result<ConCommandHandle, RegisterError> {
         let name = name.as_ref();
         log::info!("Registering ConCommand {} with completion", name);
 
-        self.concommands.mid_register_concommand_with_completion(
-            name,
-            callback,
-            help_string.as_ref(),
-            flags,
-            completion_callback,
-        )
+        self.concommands
+            .mid_register_concommand_with_completion(
+                name,
+                callback,
+                help_string.as_ref(),
+                flags.bits(),
+                completion_callback,
+            )
+            .map(ConCommandHandle::new)
     }
 
     /// registers a convar without any complex steps and without giving back the convar pointer
@@ -168,7 +181,7 @@ impl EngineData {
         name: impl Into<String>,
         default_value: impl Into<String>,
         help_string: &'static str,
-        flags: i32,
+        flags: CVarFlags,
         token: EngineToken,
     ) -> Result<(), RegisterError> {
         use self::convars::{ConVarRegister, ConVarStruct};
@@ -181,6 +194,21 @@ impl EngineData {
     }
 }
 
+/// unregisters every convar and concommand registered by this plugin, removes the convar listener dispatch and restores hooked concommands
+///
+/// called by [`crate::entry`] before the plugin is allowed to unload
+///
+/// # Safety
+///
+/// none of the convars and concommands registered by this plugin can be used after this
+#[doc(hidden)]
+pub unsafe fn unregister_all(_: EngineToken) {
+    // hooks write back into the concommands so they have to be restored before the plugin's own concommands are freed
+    concommand_hooks::unhook_all();
+    convars::listeners::uninstall_dispatch();
+    unsafe { tracking::unregister_all() };
+}
+
 // hacky way to test compile failure
 
 #[cfg(doctest)]
@@ -197,3 +225,18 @@ mod doctest {
     /// ```
     const fn test_engine_token() {}
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    /// an 8 byte aligned buffer to fake engine structs in
+    pub(crate) fn fake_struct(size: usize) -> Vec<u64> {
+        vec![0; size.div_ceil(8)]
+    }
+
+    /// writes `bytes` at a byte offset into a buffer from [`fake_struct`]
+    pub(crate) fn write(buf: &mut [u64], offset: usize, bytes: &[u8]) {
+        let buf =
+            unsafe { std::slice::from_raw_parts_mut(buf.as_mut_ptr().cast::<u8>(), buf.len() * 8) };
+        buf[offset..offset + bytes.len()].copy_from_slice(bytes)
+    }
+}
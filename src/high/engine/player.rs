@@ -0,0 +1,245 @@
+//! a safe view over the server's [`CPlayer`]
+//!
+//! [`Player`] has typed getters and setters for the bound fields of [`CPlayer`].
+//! there is no view for the client's `C_Player` yet since its fields and most of its vtable aren't known
+//!
+//! ```no_run
+//! use rrplug::prelude::*;
+//! use rrplug::bindings::class_types::cplayer::CPlayer;
+//! use rrplug::high::engine::player::Player;
+//!
+//! fn heal(raw_player: &mut CPlayer, engine_token: EngineToken) {
+//!     let mut player = Player::new(raw_player, engine_token);
+//!
+//!     if player.health() < player.max_health() {
+//!         player.set_health(player.max_health());
+//!     }
+//!
+//!     log::info!(
+//!         "{} is at {:?} looking at {:?}",
+//!         player.community_name(),
+//!         player.get_origin(),
+//!         player.get_forward_vector()
+//!     );
+//! }
+//! ```
+
+use super::{entities::EHandle, EngineToken};
+use crate::{
+    bindings::class_types::cplayer::CPlayer, high::vector::Vector3, mid::utils::str_from_char_array,
+};
+
+/// a server player which can only be used on the engine thread
+pub struct Player<'a> {
+    player: &'a mut CPlayer,
+    _token: EngineToken,
+}
+
+impl<'a> Player<'a> {
+    /// wraps a player
+    pub const fn new(player: &'a mut CPlayer, token: EngineToken) -> Self {
+        Self {
+            player,
+            _token: token,
+        }
+    }
+
+    /// the raw player
+    pub const fn get_raw(&mut self) -> &mut CPlayer {
+        self.player
+    }
+
+    /// the index of the player which is also its entity index
+    pub fn player_index(&self) -> u32 {
+        unsafe { self.player.player_index.copy_inner() }
+    }
+
+    /// the current health
+    pub fn health(&self) -> i32 {
+        unsafe { self.player.health.copy_inner() }
+    }
+
+    /// sets the current health
+    pub fn set_health(&mut self, health: i32) {
+        unsafe { *self.player.health.get_inner_mut() = health }
+    }
+
+    /// the max health
+    pub fn max_health(&self) -> i32 {
+        unsafe { self.player.max_health.copy_inner() }
+    }
+
+    /// sets the max health
+    pub fn set_max_health(&mut self, max_health: i32) {
+        unsafe { *self.player.max_health.get_inner_mut() = max_health }
+    }
+
+    /// the team number
+    pub fn team(&self) -> i32 {
+        unsafe { self.player.team.copy_inner() }
+    }
+
+    /// sets the team number
+    pub fn set_team(&mut self, team: i32) {
+        unsafe { *self.player.team.get_inner_mut() = team }
+    }
+
+    /// the absolute origin stored on the player; see [`Player::get_origin`] for the engine's version
+    pub fn origin(&self) -> Vector3 {
+        unsafe { self.player.vec_abs_origin.copy_inner() }
+    }
+
+    /// the view angles
+    pub fn angles(&self) -> Vector3 {
+        unsafe { self.player.angles.copy_inner() }
+    }
+
+    /// sets the view angles
+    pub fn set_angles(&mut self, angles: Vector3) {
+        unsafe { *self.player.angles.get_inner_mut() = angles }
+    }
+
+    /// returns [`true`] if the player is sprinting
+    pub fn is_sprinting(&self) -> bool {
+        unsafe { self.player.is_sprinting.copy_inner() }
+    }
+
+    /// returns [`true`] if the player is hanging on a wall
+    pub fn is_wall_hanging(&self) -> bool {
+        unsafe { self.player.wall_hanging.copy_inner() }
+    }
+
+    /// the kind of traversal like mantling
+    pub fn traversal_type(&self) -> i32 {
+        unsafe { self.player.traversal_type.copy_inner() }
+    }
+
+    /// the state of the current traversal
+    pub fn traversal_state(&self) -> i32 {
+        unsafe { self.player.traversal_state.copy_inner() }
+    }
+
+    /// returns [`true`] if the grapple is attached
+    pub fn is_grapple_active(&self) -> bool {
+        unsafe { self.player.grapple_active.copy_inner() }
+    }
+
+    /// the titan of the player
+    pub fn pet_titan(&self) -> EHandle {
+        unsafe { self.player.pet_titan.copy_inner() }.into()
+    }
+
+    /// the soul of the titan the player is in
+    pub fn titan_soul(&self) -> EHandle {
+        unsafe { self.player.titan_soul.copy_inner() }.into()
+    }
+
+    /// the entity the player is standing on
+    pub fn ground_entity(&self) -> EHandle {
+        unsafe { self.player.ground_entity.copy_inner() }.into()
+    }
+
+    /// the entity the player is spectating
+    pub fn observer_target(&self) -> EHandle {
+        unsafe { self.player.observer_target.copy_inner() }.into()
+    }
+
+    /// the name of the player
+    pub fn community_name(&self) -> &str {
+        str_from_char_array(unsafe { self.player.community_name.get_inner() })
+    }
+
+    /// the clan tag of the player
+    pub fn community_clan_tag(&self) -> &str {
+        str_from_char_array(unsafe { self.player.community_clan_tag.get_inner() })
+    }
+
+    /// the origin from the engine
+    pub fn get_origin(&self) -> Vector3 {
+        let mut origin = Vector3::ZERO;
+        unsafe { self.player.get_origin(&mut origin) };
+        origin
+    }
+
+    /// the angles of the eyes
+    pub fn eye_angles(&self) -> Vector3 {
+        let mut angles = Vector3::ZERO;
+        unsafe { self.player.eye_angles(&mut angles) };
+        angles
+    }
+
+    /// the position of the eyes
+    pub fn get_eye_position(&self) -> Vector3 {
+        let mut position = Vector3::ZERO;
+        unsafe { self.player.get_eye_position(&mut position) };
+        position
+    }
+
+    /// the center of the player
+    pub fn get_center_position(&self) -> Vector3 {
+        let mut position = Vector3::ZERO;
+        unsafe { self.player.get_center_position(&mut position) };
+        position
+    }
+
+    /// the direction the player is looking at
+    pub fn get_forward_vector(&self) -> Vector3 {
+        let mut forward = Vector3::ZERO;
+        unsafe {
+            self.player
+                .get_forward_vector(&mut forward, std::ptr::null(), std::ptr::null())
+        };
+        forward
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::c_void;
+
+    use super::*;
+    use crate::high::engine::test::{fake_struct, write};
+
+    extern "C" fn get_origin(_: *const c_void, out: *mut Vector3) -> *mut Vector3 {
+        unsafe { *out = Vector3::new(1., 2., 3.) };
+        out
+    }
+
+    extern "C" fn get_forward_vector(
+        _: *const c_void,
+        out: *mut Vector3,
+        _: *const c_void,
+        _: *const c_void,
+    ) {
+        unsafe { *out = Vector3::new(0., 1., 0.) };
+    }
+
+    #[test]
+    fn player_fields_and_vmethods() {
+        let mut vtable = vec![0usize; 141];
+        vtable[139] = get_origin as extern "C" fn(_, _) -> _ as usize;
+        vtable[140] = get_forward_vector as extern "C" fn(_, _, _, _) as usize;
+
+        let mut raw = fake_struct(std::mem::size_of::<CPlayer>());
+        write(&mut raw, 0, &(vtable.as_ptr() as usize).to_le_bytes());
+        write(&mut raw, 0x4D4, &50i32.to_le_bytes());
+        write(&mut raw, 0x4D0, &100i32.to_le_bytes());
+        write(&mut raw, 0x5E4, &2i32.to_le_bytes());
+        write(&mut raw, 0x27C4, &[1]);
+        write(&mut raw, 0x1C91, b"cat_or_not\0");
+
+        let raw_player = unsafe { &mut *raw.as_mut_ptr().cast::<CPlayer>() };
+        let mut player = Player::new(raw_player, unsafe { EngineToken::new_unchecked() });
+
+        assert_eq!(player.health(), 50);
+        player.set_health(player.max_health());
+        assert_eq!(player.health(), 100);
+        assert_eq!(player.team(), 2);
+        assert!(player.is_sprinting());
+        assert!(!player.is_wall_hanging());
+        assert_eq!(player.community_name(), "cat_or_not");
+
+        assert_eq!(player.get_origin(), Vector3::new(1., 2., 3.));
+        assert_eq!(player.get_forward_vector(), Vector3::new(0., 1., 0.));
+    }
+}
@@ -0,0 +1,311 @@
+//! module scope convars and concommands
+//!
+//! convars declared with [`crate::static_convar`] and concommands declared with [`crate::static_concommand`]
+//! are collected at link time and registered by [`crate::entry`] when `engine.dll` is loaded, before [`crate::plugin::Plugin::on_dll_load`] is called.
+//!
+//! afterwards they can be accessed from anywhere on the engine thread through their static
+//!
+//! ```no_run
+//! use rrplug::prelude::*;
+//!
+//! rrplug::static_convar! {
+//!     /// how cool the plugin is
+//!     pub static COOLNESS: i32 = {
+//!         name: "cool_coolness",
+//!         default: 4,
+//!         flags: CVarFlags::GAMEDLL,
+//!         help: "how cool the plugin is",
+//!         min: 0,
+//!         max: 10,
+//!     };
+//! }
+//!
+//! rrplug::static_concommand! {
+//!     pub static PRINT_COOLNESS = {
+//!         name: "cool_print_coolness",
+//!         help: "prints the coolness",
+//!         callback: print_coolness,
+//!     };
+//! }
+//!
+//! #[rrplug::concommand]
+//! fn print_coolness() {
+//!     let token = unsafe { EngineToken::new_unchecked() };
+//!     log::info!("coolness is {}", COOLNESS.value(token));
+//! }
+//! ```
+
+use once_cell::sync::OnceCell;
+
+use super::{
+    concommands::ConCommandHandle,
+    convars::typed::{ConVarValue, TypedConVar, TypedConVarRegister},
+    EngineToken,
+};
+use crate::{
+    bindings::cvar::{command::CCommand, convar::COMMAND_COMPLETION_ITEM_LENGTH, CVarFlags},
+    errors::RegisterError,
+    high::UnsafeHandle,
+    mid::engine::get_engine_data,
+};
+
+/// the signature of callbacks created with [`crate::concommand`]
+pub type ConCommandCallback = unsafe extern "C" fn(arg1: *const CCommand);
+
+/// the signature of callbacks created with [`crate::completion`]
+pub type CompletionCallback = unsafe extern "C" fn(
+    arg1: *const ::std::os::raw::c_char,
+    arg2: *mut [::std::os::raw::c_char; COMMAND_COMPLETION_ITEM_LENGTH as usize],
+) -> ::std::os::raw::c_int;
+
+/// implemented by everything that can be collected by [`crate::entry`] and registered when `engine.dll` loads
+#[doc(hidden)]
+pub trait RegisterStatic: Sync {
+    /// the name of the convar or concommand
+    fn static_name(&self) -> &'static str;
+
+    /// registers the item if it isn't registered yet
+    fn register_static(&self, token: EngineToken) -> Result<(), RegisterError>;
+}
+
+/// an item submitted by [`crate::static_convar`] or [`crate::static_concommand`]
+#[doc(hidden)]
+pub struct StaticRegistration(pub &'static dyn RegisterStatic);
+
+inventory::collect!(StaticRegistration);
+
+/// registers every convar and concommand declared with [`crate::static_convar`] or [`crate::static_concommand`]
+///
+/// this is called by [`crate::entry`] when `engine.dll` is loaded so it shouldn't be called manually
+#[doc(hidden)]
+pub fn register_statics(token: EngineToken) {
+    for registration in inventory::iter::<StaticRegistration> {
+        if let Err(err) = registration.0.register_static(token) {
+            log::error!("failed to register {}", registration.0.static_name());
+            err.log();
+        }
+    }
+}
+
+/// a convar declared at module scope with [`crate::static_convar`]
+pub struct StaticConVar<T: ConVarValue> {
+    name: &'static str,
+    register_info: fn() -> TypedConVarRegister<T>,
+    convar: OnceCell<TypedConVar<T>>,
+}
+
+impl<T: ConVarValue> StaticConVar<T> {
+    #[doc(hidden)]
+    pub const fn new(name: &'static str, register_info: fn() -> TypedConVarRegister<T>) -> Self {
+        Self {
+            name,
+            register_info,
+            convar: OnceCell::new(),
+        }
+    }
+
+    /// the name of the convar
+    pub const fn get_name(&self) -> &'static str {
+        self.name
+    }
+
+    /// returns [`true`] if the convar was registered
+    pub fn is_registered(&self) -> bool {
+        self.convar.get().is_some()
+    }
+
+    /// registers the convar if it isn't registered yet
+    ///
+    /// this is done automatically when `engine.dll` loads
+    pub fn register(&self, token: EngineToken) -> Result<&TypedConVar<T>, RegisterError> {
+        self.convar
+            .get_or_try_init(|| TypedConVar::try_new(&(self.register_info)(), token))
+    }
+
+    /// returns the convar or [`None`] if it wasn't registered yet
+    pub fn try_get(&self, _: EngineToken) -> Option<&TypedConVar<T>> {
+        self.convar.get()
+    }
+
+    /// returns the convar
+    ///
+    /// # Panics
+    ///
+    /// panics if the convar wasn't registered yet
+    pub fn get(&self, token: EngineToken) -> &TypedConVar<T> {
+        self.try_get(token)
+            .unwrap_or_else(|| panic!("convar {} wasn't registered yet", self.name))
+    }
+
+    /// get the value of the convar
+    ///
+    /// falls back to the default value if the convar wasn't registered yet
+    pub fn value(&self, token: EngineToken) -> T {
+        match self.try_get(token) {
+            Some(convar) => convar.get(),
+            None => (self.register_info)().get_default_value().clone(),
+        }
+    }
+
+    /// set the value of the convar
+    ///
+    /// does nothing if the convar wasn't registered yet
+    pub fn set(&self, value: T, token: EngineToken) {
+        if let Some(convar) = self.try_get(token) {
+            convar.set(value, token)
+        }
+    }
+}
+
+impl<T: ConVarValue> RegisterStatic for StaticConVar<T>
+where
+    Self: Sync,
+{
+    fn static_name(&self) -> &'static str {
+        self.name
+    }
+
+    fn register_static(&self, token: EngineToken) -> Result<(), RegisterError> {
+        self.register(token).map(|_| ())
+    }
+}
+
+/// a concommand declared at module scope with [`crate::static_concommand`]
+pub struct StaticConCommand {
+    name: &'static str,
+    help_string: &'static str,
+    flags: CVarFlags,
+    callback: ConCommandCallback,
+    completion: Option<CompletionCallback>,
+    command: OnceCell<UnsafeHandle<ConCommandHandle>>,
+}
+
+impl StaticConCommand {
+    #[doc(hidden)]
+    pub const fn new(
+        name: &'static str,
+        help_string: &'static str,
+        flags: CVarFlags,
+        callback: ConCommandCallback,
+        completion: Option<CompletionCallback>,
+    ) -> Self {
+        Self {
+            name,
+            help_string,
+            flags,
+            callback,
+            completion,
+            command: OnceCell::new(),
+        }
+    }
+
+    /// the name of the concommand
+    pub const fn get_name(&self) -> &'static str {
+        self.name
+    }
+
+    /// returns [`true`] if the concommand was registered and wasn't unregistered
+    pub fn is_registered(&self) -> bool {
+        self.command
+            .get()
+            .map(|command| command.get().is_registered())
+            .unwrap_or_default()
+    }
+
+    /// registers the concommand if it isn't registered yet
+    ///
+    /// this is done automatically when `engine.dll` loads
+    pub fn register(&self, token: EngineToken) -> Result<ConCommandHandle, RegisterError> {
+        self.command
+            .get_or_try_init(|| {
+                let engine = get_engine_data().ok_or(RegisterError::NoneFunction)?;
+
+                match self.completion {
+                    Some(completion) => engine.register_concommand_with_completion(
+                        self.name,
+                        self.callback,
+                        self.help_string,
+                        self.flags,
+                        completion,
+                        token,
+                    ),
+                    None => engine.register_concommand(
+                        self.name,
+                        self.callback,
+                        self.help_string,
+                        self.flags,
+                        token,
+                    ),
+                }
+                .map(UnsafeHandle::internal_new)
+            })
+            .map(|command| command.copy())
+    }
+
+    /// returns the handle of the registered concommand or [`None`] if it wasn't registered yet
+    pub fn try_get(&self, _: EngineToken) -> Option<ConCommandHandle> {
+        self.command.get().map(|command| command.copy())
+    }
+}
+
+impl RegisterStatic for StaticConCommand {
+    fn static_name(&self) -> &'static str {
+        self.name
+    }
+
+    fn register_static(&self, token: EngineToken) -> Result<(), RegisterError> {
+        self.register(token).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate as rrplug;
+
+    crate::static_convar! {
+        static TEST_STATIC_CONVAR: i32 = {
+            name: "test_static_convar",
+            default: 4,
+            help: "test",
+            min: 0,
+            max: 8,
+        };
+    }
+
+    crate::static_concommand! {
+        static TEST_STATIC_CONCOMMAND = {
+            name: "test_static_concommand",
+            flags: CVarFlags::empty(),
+            help: "test",
+            callback: test_static_concommand,
+        };
+    }
+
+    #[rrplug::concommand]
+    fn test_static_concommand() {}
+
+    #[test]
+    fn statics_are_collected() {
+        let names = inventory::iter::<StaticRegistration>
+            .into_iter()
+            .map(|registration| registration.0.static_name())
+            .collect::<Vec<_>>();
+
+        assert!(names.contains(&"test_static_convar"));
+        assert!(names.contains(&"test_static_concommand"));
+    }
+
+    #[test]
+    fn unregistered_statics() {
+        let token = unsafe { EngineToken::new_unchecked() };
+
+        assert!(!TEST_STATIC_CONVAR.is_registered());
+        assert!(TEST_STATIC_CONVAR.try_get(token).is_none());
+        assert_eq!(TEST_STATIC_CONVAR.value(token), 4);
+        assert_eq!(TEST_STATIC_CONVAR.get_name(), "test_static_convar");
+
+        assert!(!TEST_STATIC_CONCOMMAND.is_registered());
+        assert!(TEST_STATIC_CONCOMMAND.try_get(token).is_none());
+    }
+}
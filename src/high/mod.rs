@@ -2,6 +2,7 @@
 
 pub mod engine;
 pub mod northstar;
+pub mod pdef;
 pub mod squirrel;
 pub mod squirrel_traits;
 pub mod vector;
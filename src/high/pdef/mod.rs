@@ -0,0 +1,664 @@
+//! schemas for player persistence parsed from the game's `.pdef` files
+//!
+//! a pdef lists enums, structs and the members of the persistence in the order they are stored in; [`persistence::PlayerPersistence`] uses a [`Pdef`] to read and write the bytes of a persistence buffer
+//!
+//! ```
+//! use rrplug::high::pdef::{Pdef, PdefType};
+//!
+//! let pdef = Pdef::parse(
+//!     r#"
+//! $ENUM_START gameModes
+//!     tdm
+//!     ctf
+//! $ENUM_END
+//!
+//! int xp
+//! int[gameModes] wins
+//! string{16} lastPlaylist
+//! "#,
+//! )
+//! .unwrap();
+//!
+//! assert_eq!(pdef.size(), 4 + 4 * 2 + 16);
+//! assert_eq!(pdef.member("lastPlaylist").map(|member| member.ty), Some(PdefType::String(16)));
+//! ```
+
+use std::str::FromStr;
+
+use crate::errors::{PdefError, PersistenceError};
+
+pub mod persistence;
+
+/// the type of a member
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PdefType {
+    /// 4 bytes
+    Int,
+    /// 4 bytes
+    Float,
+    /// 1 byte
+    Bool,
+    /// a null terminated string in a buffer of this size
+    String(usize),
+    /// the index of a variant in [`Pdef::enums`] stored in 1 byte
+    Enum(usize),
+    /// an index in [`Pdef::structs`]
+    Struct(usize),
+}
+
+/// the size of an array member
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PdefArray {
+    /// the amount of elements
+    pub len: usize,
+    /// the index of the enum in [`Pdef::enums`] if the array has an element for every variant
+    pub indexed_by: Option<usize>,
+}
+
+/// a member of a struct or of the persistence itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdefMember {
+    pub name: String,
+    pub ty: PdefType,
+    pub array: Option<PdefArray>,
+    /// the offset from the start of the parent
+    pub offset: usize,
+}
+
+/// an enum
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdefEnum {
+    pub name: String,
+    pub variants: Vec<String>,
+}
+
+/// a struct
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdefStruct {
+    pub name: String,
+    pub members: Vec<PdefMember>,
+    pub size: usize,
+}
+
+/// a parsed pdef schema
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pdef {
+    version: Option<u32>,
+    enums: Vec<PdefEnum>,
+    structs: Vec<PdefStruct>,
+    members: Vec<PdefMember>,
+    size: usize,
+}
+
+enum Block {
+    Enum(PdefEnum),
+    Struct(PdefStruct),
+}
+
+impl Pdef {
+    /// parses the text of a `.pdef` file
+    ///
+    /// types have to be defined before they are used
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the schema is invalid
+    pub fn parse(source: &str) -> Result<Self, PdefError> {
+        let mut pdef = Self::default();
+        let mut block = None;
+
+        for (index, line) in source.lines().enumerate() {
+            let line_num = index + 1;
+            let line = strip_brackets(line.split("//").next().unwrap_or_default());
+            let words = line.split_whitespace().collect::<Vec<&str>>();
+            let syntax = |expected| PdefError::Syntax {
+                line: line_num,
+                expected,
+            };
+
+            match (&mut block, words.as_slice()) {
+                (_, []) => {}
+                (None, ["version", version]) => {
+                    pdef.version = Some(version.parse().map_err(|_| syntax("a version number"))?)
+                }
+                (None, ["$ENUM_START", name]) => {
+                    pdef.check_type_name(name, line_num)?;
+                    block = Some(Block::Enum(PdefEnum {
+                        name: name.to_string(),
+                        variants: Vec::new(),
+                    }))
+                }
+                (None, ["$STRUCT_START", name]) => {
+                    pdef.check_type_name(name, line_num)?;
+                    block = Some(Block::Struct(PdefStruct {
+                        name: name.to_string(),
+                        members: Vec::new(),
+                        size: 0,
+                    }))
+                }
+                (Some(Block::Enum(_)), ["$ENUM_END"]) => {
+                    if let Some(Block::Enum(pdef_enum)) = block.take() {
+                        pdef.enums.push(pdef_enum)
+                    }
+                }
+                (Some(Block::Struct(_)), ["$STRUCT_END"]) => {
+                    if let Some(Block::Struct(pdef_struct)) = block.take() {
+                        pdef.structs.push(pdef_struct)
+                    }
+                }
+                (Some(Block::Enum(pdef_enum)), [variant]) if is_identifier(variant) => {
+                    if pdef_enum.variants.iter().any(|other| other == variant) {
+                        return Err(PdefError::Duplicate(line_num, variant.to_string()));
+                    }
+                    // stored in a byte
+                    if pdef_enum.variants.len() > u8::MAX as usize {
+                        return Err(syntax("at most 256 variants"));
+                    }
+                    pdef_enum.variants.push(variant.to_string())
+                }
+                (Some(Block::Enum(_)), _) => return Err(syntax("a variant or $ENUM_END")),
+                (Some(Block::Struct(pdef_struct)), [ty, name]) => {
+                    let member = pdef.parse_member(ty, name, pdef_struct.size, line_num)?;
+                    pdef_struct.size = pdef
+                        .checked_member_size(&member)
+                        .and_then(|size| pdef_struct.size.checked_add(size))
+                        .ok_or(PdefError::TooLarge(line_num))?;
+                    push_member(&mut pdef_struct.members, member, line_num)?
+                }
+                (None, [ty, name]) => {
+                    let member = pdef.parse_member(ty, name, pdef.size, line_num)?;
+                    pdef.size = pdef
+                        .checked_member_size(&member)
+                        .and_then(|size| pdef.size.checked_add(size))
+                        .ok_or(PdefError::TooLarge(line_num))?;
+                    push_member(&mut pdef.members, member, line_num)?
+                }
+                _ => return Err(syntax("a type followed by a name")),
+            }
+        }
+
+        match block {
+            Some(Block::Enum(PdefEnum { name, .. }) | Block::Struct(PdefStruct { name, .. })) => {
+                Err(PdefError::Unterminated(name))
+            }
+            None => Ok(pdef),
+        }
+    }
+
+    /// the version from the `version` line
+    pub const fn version(&self) -> Option<u32> {
+        self.version
+    }
+
+    /// the size of the whole persistence
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    /// the members of the persistence in the order they are stored in
+    pub fn members(&self) -> &[PdefMember] {
+        &self.members
+    }
+
+    /// every enum
+    pub fn enums(&self) -> &[PdefEnum] {
+        &self.enums
+    }
+
+    /// every struct
+    pub fn structs(&self) -> &[PdefStruct] {
+        &self.structs
+    }
+
+    /// a member of the persistence itself by name
+    pub fn member(&self, name: &str) -> Option<&PdefMember> {
+        self.members.iter().find(|member| member.name == name)
+    }
+
+    /// the size of a single value of a type
+    pub fn type_size(&self, ty: PdefType) -> usize {
+        match ty {
+            PdefType::Int | PdefType::Float => 4,
+            PdefType::Bool | PdefType::Enum(_) => 1,
+            PdefType::String(size) => size,
+            PdefType::Struct(index) => self.structs[index].size,
+        }
+    }
+
+    /// the size of a member including every element of an array
+    pub fn member_size(&self, member: &PdefMember) -> usize {
+        self.checked_member_size(member).unwrap_or(usize::MAX)
+    }
+
+    fn checked_member_size(&self, member: &PdefMember) -> Option<usize> {
+        self.type_size(member.ty)
+            .checked_mul(member.array.map(|array| array.len).unwrap_or(1))
+    }
+
+    /// finds the offset and type of a single value from a path like `loadouts[0].primary`
+    ///
+    /// arrays sized with an enum can also be indexed with a variant like `wins[ctf]`
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the path doesn't lead to a single value
+    pub fn resolve(&self, path: &str) -> Result<(usize, PdefType), PersistenceError> {
+        let mut members = self.members.as_slice();
+        let mut offset = 0;
+        let mut segments = path.split('.').peekable();
+        let mut walked = 0;
+
+        while let Some(segment) = segments.next() {
+            walked += segment.len() + 1;
+            let current = &path[..(walked - 1).min(path.len())];
+
+            let (name, index) = split_index(segment)
+                .filter(|(name, _)| is_identifier(name))
+                .ok_or_else(|| PersistenceError::InvalidPath(path.to_string()))?;
+            let member = members
+                .iter()
+                .find(|member| member.name == name)
+                .ok_or_else(|| PersistenceError::UnknownMember(current.to_string()))?;
+
+            offset += member.offset;
+            match (member.array, index) {
+                (Some(array), Some(index)) => {
+                    let element = index
+                        .parse::<usize>()
+                        .ok()
+                        .or_else(|| {
+                            self.enums[array.indexed_by?]
+                                .variants
+                                .iter()
+                                .position(|variant| variant == index)
+                        })
+                        .filter(|element| *element < array.len)
+                        .ok_or_else(|| PersistenceError::OutOfBounds {
+                            path: current[..current.len() - index.len() - 2].to_string(),
+                            index: index.to_string(),
+                        })?;
+                    offset += element * self.type_size(member.ty);
+                }
+                (Some(_), None) => return Err(PersistenceError::NotAValue(current.to_string())),
+                (None, Some(_)) => return Err(PersistenceError::InvalidPath(path.to_string())),
+                (None, None) => {}
+            }
+
+            match (member.ty, segments.peek()) {
+                (PdefType::Struct(index), Some(_)) => members = &self.structs[index].members,
+                (PdefType::Struct(_), None) => {
+                    return Err(PersistenceError::NotAValue(current.to_string()))
+                }
+                (_, Some(next)) => {
+                    return Err(PersistenceError::UnknownMember(format!("{current}.{next}")))
+                }
+                (ty, None) => return Ok((offset, ty)),
+            }
+        }
+
+        Err(PersistenceError::InvalidPath(path.to_string()))
+    }
+
+    fn check_type_name(&self, name: &str, line: usize) -> Result<(), PdefError> {
+        if !is_identifier(name) {
+            return Err(PdefError::Syntax {
+                line,
+                expected: "a type name",
+            });
+        }
+
+        if self.enums.iter().any(|other| other.name == name)
+            || self.structs.iter().any(|other| other.name == name)
+            || ["int", "float", "bool", "string"].contains(&name)
+        {
+            return Err(PdefError::Duplicate(line, name.to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn parse_member(
+        &self,
+        ty: &str,
+        name: &str,
+        offset: usize,
+        line: usize,
+    ) -> Result<PdefMember, PdefError> {
+        // the array size can be after the type or after the name
+        let (ty, type_array) = split_index(ty).ok_or(PdefError::Syntax {
+            line,
+            expected: "a type",
+        })?;
+        let (name, name_array) = split_index(name).ok_or(PdefError::Syntax {
+            line,
+            expected: "a name",
+        })?;
+
+        if !is_identifier(name) {
+            return Err(PdefError::Syntax {
+                line,
+                expected: "a name",
+            });
+        }
+
+        let array = match (type_array, name_array) {
+            (Some(_), Some(_)) => {
+                return Err(PdefError::Syntax {
+                    line,
+                    expected: "a single array size",
+                })
+            }
+            (Some(size), None) | (None, Some(size)) => Some(self.parse_array(size, line)?),
+            (None, None) => None,
+        };
+
+        let ty = match ty {
+            "int" => PdefType::Int,
+            "float" => PdefType::Float,
+            "bool" => PdefType::Bool,
+            _ => match ty
+                .strip_prefix("string{")
+                .and_then(|ty| ty.strip_suffix('}'))
+            {
+                Some(size) => PdefType::String(size.parse().ok().filter(|size| *size != 0).ok_or(
+                    PdefError::Syntax {
+                        line,
+                        expected: "a string size",
+                    },
+                )?),
+                None => self
+                    .enums
+                    .iter()
+                    .position(|pdef_enum| pdef_enum.name == ty)
+                    .map(PdefType::Enum)
+                    .or_else(|| {
+                        self.structs
+                            .iter()
+                            .position(|pdef_struct| pdef_struct.name == ty)
+                            .map(PdefType::Struct)
+                    })
+                    .ok_or_else(|| PdefError::UnknownType(line, ty.to_string()))?,
+            },
+        };
+
+        Ok(PdefMember {
+            name: name.to_string(),
+            ty,
+            array,
+            offset,
+        })
+    }
+
+    fn parse_array(&self, size: &str, line: usize) -> Result<PdefArray, PdefError> {
+        if let Ok(len) = size.parse::<usize>() {
+            return Ok(PdefArray {
+                len,
+                indexed_by: None,
+            });
+        }
+
+        self.enums
+            .iter()
+            .position(|pdef_enum| pdef_enum.name == size)
+            .map(|index| PdefArray {
+                len: self.enums[index].variants.len(),
+                indexed_by: Some(index),
+            })
+            .ok_or_else(|| PdefError::InvalidArraySize(line, size.to_string()))
+    }
+}
+
+impl FromStr for Pdef {
+    type Err = PdefError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// a single value of persistence
+#[derive(Debug, Clone, PartialEq)]
+pub enum PdefValue {
+    Int(i32),
+    Float(f32),
+    Bool(bool),
+    String(String),
+    /// the name of the variant
+    Enum(String),
+}
+
+impl From<i32> for PdefValue {
+    fn from(value: i32) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<f32> for PdefValue {
+    fn from(value: f32) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<bool> for PdefValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<String> for PdefValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for PdefValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+fn push_member(
+    members: &mut Vec<PdefMember>,
+    member: PdefMember,
+    line: usize,
+) -> Result<(), PdefError> {
+    if members.iter().any(|other| other.name == member.name) {
+        return Err(PdefError::Duplicate(line, member.name));
+    }
+    members.push(member);
+    Ok(())
+}
+
+fn is_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// splits `name[index]` into its parts
+fn split_index(segment: &str) -> Option<(&str, Option<&str>)> {
+    match segment.split_once('[') {
+        Some((name, index)) => Some((name, Some(index.strip_suffix(']')?))),
+        None => Some((segment, None)),
+    }
+}
+
+/// removes whitespace inside of `[]` and `{}` so `int[ 10 ]` is a single word
+fn strip_brackets(line: &str) -> String {
+    let mut depth = 0usize;
+    line.chars()
+        .filter(|c| {
+            match c {
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            depth == 0 || !c.is_whitespace()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    pub(crate) const SAMPLE: &str = r#"
+// a made up schema shaped like the game's
+version 3
+
+$ENUM_START gameModes
+    tdm
+    ctf // capture the flag
+    lts
+$ENUM_END
+
+$STRUCT_START loadout
+    string{16} name
+    string{32} primary
+    int primaryMod
+    bool[2] unlocked
+$STRUCT_END
+
+int xp
+float kd
+bool isCheater
+gameModes lastMode
+string{8} tag
+int[ gameModes ] wins
+loadout loadouts[3]
+"#;
+
+    #[test]
+    fn parse_sample() {
+        let pdef = Pdef::parse(SAMPLE).unwrap();
+
+        assert_eq!(pdef.version(), Some(3));
+        assert_eq!(pdef.enums()[0].variants, ["tdm", "ctf", "lts"]);
+        assert_eq!(pdef.structs()[0].size, 16 + 32 + 4 + 2);
+        assert_eq!(pdef.size(), 4 + 4 + 1 + 1 + 8 + 12 + 54 * 3);
+        assert_eq!(
+            pdef.member("wins"),
+            Some(&PdefMember {
+                name: "wins".to_string(),
+                ty: PdefType::Int,
+                array: Some(PdefArray {
+                    len: 3,
+                    indexed_by: Some(0)
+                }),
+                offset: 18,
+            })
+        );
+
+        assert_eq!(pdef.resolve("xp"), Ok((0, PdefType::Int)));
+        assert_eq!(pdef.resolve("lastMode"), Ok((9, PdefType::Enum(0))));
+        assert_eq!(pdef.resolve("wins[ctf]"), Ok((22, PdefType::Int)));
+        assert_eq!(pdef.resolve("wins[2]"), Ok((26, PdefType::Int)));
+        assert_eq!(
+            pdef.resolve("loadouts[1].primary"),
+            Ok((30 + 54 + 16, PdefType::String(32)))
+        );
+        assert_eq!(
+            pdef.resolve("loadouts[2].unlocked[1]"),
+            Ok((30 + 54 * 2 + 52 + 1, PdefType::Bool))
+        );
+    }
+
+    #[test]
+    fn resolve_errors() {
+        let pdef = Pdef::parse(SAMPLE).unwrap();
+
+        assert_eq!(
+            pdef.resolve("loadouts[3].primary"),
+            Err(PersistenceError::OutOfBounds {
+                path: "loadouts".to_string(),
+                index: "3".to_string()
+            })
+        );
+        assert_eq!(
+            pdef.resolve("wins[ffa]"),
+            Err(PersistenceError::OutOfBounds {
+                path: "wins".to_string(),
+                index: "ffa".to_string()
+            })
+        );
+        assert_eq!(
+            pdef.resolve("loadouts[0].secondary"),
+            Err(PersistenceError::UnknownMember(
+                "loadouts[0].secondary".to_string()
+            ))
+        );
+        assert_eq!(
+            pdef.resolve("loadouts"),
+            Err(PersistenceError::NotAValue("loadouts".to_string()))
+        );
+        assert_eq!(
+            pdef.resolve("loadouts[0]"),
+            Err(PersistenceError::NotAValue("loadouts[0]".to_string()))
+        );
+        assert_eq!(
+            pdef.resolve("xp.level"),
+            Err(PersistenceError::UnknownMember("xp.level".to_string()))
+        );
+        assert_eq!(
+            pdef.resolve("xp[0]"),
+            Err(PersistenceError::InvalidPath("xp[0]".to_string()))
+        );
+        assert_eq!(
+            pdef.resolve("loadouts[0"),
+            Err(PersistenceError::InvalidPath("loadouts[0".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            Pdef::parse("int xp\nweapon primary"),
+            Err(PdefError::UnknownType(2, "weapon".to_string()))
+        );
+        assert_eq!(
+            Pdef::parse("int xp\nfloat xp"),
+            Err(PdefError::Duplicate(2, "xp".to_string()))
+        );
+        assert_eq!(
+            Pdef::parse("$ENUM_START modes\ntdm\ntdm\n$ENUM_END"),
+            Err(PdefError::Duplicate(3, "tdm".to_string()))
+        );
+        assert_eq!(
+            Pdef::parse("int[modes] wins"),
+            Err(PdefError::InvalidArraySize(1, "modes".to_string()))
+        );
+        assert_eq!(
+            Pdef::parse("$STRUCT_START loadout\nint xp"),
+            Err(PdefError::Unterminated("loadout".to_string()))
+        );
+        assert_eq!(
+            Pdef::parse("string{0} tag"),
+            Err(PdefError::Syntax {
+                line: 1,
+                expected: "a string size"
+            })
+        );
+        assert_eq!(
+            Pdef::parse("int xp level"),
+            Err(PdefError::Syntax {
+                line: 1,
+                expected: "a type followed by a name"
+            })
+        );
+        // a struct can't contain itself
+        assert_eq!(
+            Pdef::parse("$STRUCT_START node\nnode next\n$STRUCT_END"),
+            Err(PdefError::UnknownType(2, "node".to_string()))
+        );
+        assert_eq!(
+            Pdef::parse(&format!("int[{}] xp", usize::MAX)),
+            Err(PdefError::TooLarge(1))
+        );
+        assert_eq!(
+            Pdef::parse(&format!(
+                "string{{{}}} a\nstring{{{}}} b",
+                usize::MAX,
+                usize::MAX
+            )),
+            Err(PdefError::TooLarge(2))
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! reading and writing a persistence buffer with a [`Pdef`]
+//!
+//! ```
+//! use rrplug::high::pdef::{persistence::PlayerPersistence, Pdef, PdefValue};
+//!
+//! let pdef = Pdef::parse(
+//!     r#"
+//! $STRUCT_START loadout
+//!     string{32} primary
+//! $STRUCT_END
+//!
+//! int xp
+//! loadout[2] loadouts
+//! "#,
+//! )
+//! .unwrap();
+//!
+//! let mut buffer = vec![0; pdef.size()];
+//! let mut persistence = PlayerPersistence::new(&pdef, buffer.as_mut_slice()).unwrap();
+//!
+//! persistence.set("xp", 1200).unwrap();
+//! persistence.set("loadouts[0].primary", "mp_weapon_car").unwrap();
+//!
+//! assert_eq!(persistence.get("xp"), Ok(PdefValue::Int(1200)));
+//! assert_eq!(
+//!     persistence.get("loadouts[0].primary"),
+//!     Ok(PdefValue::String("mp_weapon_car".to_string()))
+//! );
+//! assert!(persistence.set("loadouts[2].primary", "mp_weapon_car").is_err());
+//! ```
+
+use super::{Pdef, PdefType, PdefValue};
+use crate::errors::PersistenceError;
+
+/// a persistence buffer interpreted with a [`Pdef`]
+///
+/// the schema starts at the first byte of the buffer; writing needs a mutable buffer
+#[derive(Debug)]
+pub struct PlayerPersistence<'a, B> {
+    pdef: &'a Pdef,
+    buffer: B,
+}
+
+impl<'a, B: AsRef<[u8]>> PlayerPersistence<'a, B> {
+    /// wraps a buffer
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the buffer is smaller than the schema
+    pub fn new(pdef: &'a Pdef, buffer: B) -> Result<Self, PersistenceError> {
+        let len = buffer.as_ref().len();
+        if len < pdef.size() {
+            return Err(PersistenceError::BufferTooSmall {
+                needed: pdef.size(),
+                len,
+            });
+        }
+
+        Ok(Self { pdef, buffer })
+    }
+
+    /// the schema
+    pub const fn pdef(&self) -> &'a Pdef {
+        self.pdef
+    }
+
+    /// the buffer
+    pub fn into_inner(self) -> B {
+        self.buffer
+    }
+
+    /// reads a value with a path like `loadouts[0].primary`; see [`Pdef::resolve`]
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the path doesn't lead to a single value or if an enum holds an invalid variant
+    pub fn get(&self, path: &str) -> Result<PdefValue, PersistenceError> {
+        let (offset, ty) = self.pdef.resolve(path)?;
+        let bytes = &self.buffer.as_ref()[offset..offset + self.pdef.type_size(ty)];
+
+        Ok(match ty {
+            PdefType::Int => PdefValue::Int(i32::from_le_bytes(to_array(bytes))),
+            PdefType::Float => PdefValue::Float(f32::from_le_bytes(to_array(bytes))),
+            PdefType::Bool => PdefValue::Bool(bytes[0] != 0),
+            PdefType::String(_) => {
+                let len = bytes.iter().position(|c| *c == 0).unwrap_or(bytes.len());
+                PdefValue::String(String::from_utf8_lossy(&bytes[..len]).to_string())
+            }
+            PdefType::Enum(index) => PdefValue::Enum(
+                self.pdef.enums[index]
+                    .variants
+                    .get(bytes[0] as usize)
+                    .cloned()
+                    .ok_or_else(|| PersistenceError::InvalidEnumValue {
+                        path: path.to_string(),
+                        value: bytes[0],
+                    })?,
+            ),
+            PdefType::Struct(_) => return Err(PersistenceError::NotAValue(path.to_string())),
+        })
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> PlayerPersistence<'_, B> {
+    /// writes a value with a path like `loadouts[0].primary`; see [`Pdef::resolve`]
+    ///
+    /// enums can be set with the name of a variant as a string
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the path doesn't lead to a single value, if the value has the wrong type or if it doesn't fit
+    pub fn set(&mut self, path: &str, value: impl Into<PdefValue>) -> Result<(), PersistenceError> {
+        let (offset, ty) = self.pdef.resolve(path)?;
+        let size = self.pdef.type_size(ty);
+
+        let value = value.into();
+        let bytes = &mut self.buffer.as_mut()[offset..offset + size];
+
+        match (ty, value) {
+            (PdefType::Int, PdefValue::Int(value)) => bytes.copy_from_slice(&value.to_le_bytes()),
+            (PdefType::Float, PdefValue::Float(value)) => {
+                bytes.copy_from_slice(&value.to_le_bytes())
+            }
+            (PdefType::Bool, PdefValue::Bool(value)) => bytes[0] = value as u8,
+            // keep a byte for the null terminator
+            (PdefType::String(size), PdefValue::String(value)) if value.len() >= size => {
+                return Err(PersistenceError::StringTooLong {
+                    path: path.to_string(),
+                    max: size - 1,
+                })
+            }
+            (PdefType::String(_), PdefValue::String(value)) => {
+                bytes.fill(0);
+                bytes[..value.len()].copy_from_slice(value.as_bytes())
+            }
+            (PdefType::Enum(index), PdefValue::Enum(variant) | PdefValue::String(variant)) => {
+                match self.pdef.enums[index]
+                    .variants
+                    .iter()
+                    .position(|other| *other == variant)
+                {
+                    Some(value) => bytes[0] = value as u8,
+                    None => {
+                        return Err(PersistenceError::TypeMismatch {
+                            path: path.to_string(),
+                            value: PdefValue::Enum(variant),
+                        })
+                    }
+                }
+            }
+            (_, value) => {
+                return Err(PersistenceError::TypeMismatch {
+                    path: path.to_string(),
+                    value,
+                })
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn to_array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    let mut array = [0; N];
+    array.copy_from_slice(&bytes[..N]);
+    array
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::high::pdef::test::SAMPLE;
+
+    #[test]
+    fn read_and_write() {
+        let pdef = Pdef::parse(SAMPLE).unwrap();
+        let mut buffer = vec![0u8; pdef.size() + 8];
+        buffer[0..4].copy_from_slice(&1200i32.to_le_bytes());
+        buffer[9] = 2;
+        buffer[10..14].copy_from_slice(b"RRP\0");
+
+        let mut persistence = PlayerPersistence::new(&pdef, buffer.as_mut_slice()).unwrap();
+        assert_eq!(persistence.get("xp"), Ok(PdefValue::Int(1200)));
+        assert_eq!(
+            persistence.get("lastMode"),
+            Ok(PdefValue::Enum("lts".into()))
+        );
+        assert_eq!(persistence.get("tag"), Ok(PdefValue::String("RRP".into())));
+        assert_eq!(persistence.get("isCheater"), Ok(PdefValue::Bool(false)));
+
+        persistence.set("kd", 1.5).unwrap();
+        persistence.set("wins[ctf]", 7).unwrap();
+        persistence.set("lastMode", "tdm").unwrap();
+        persistence.set("tag", "1234567").unwrap();
+        persistence
+            .set("loadouts[2].primary", "mp_weapon_car")
+            .unwrap();
+        persistence.set("loadouts[2].unlocked[1]", true).unwrap();
+
+        assert_eq!(persistence.get("kd"), Ok(PdefValue::Float(1.5)));
+        assert_eq!(persistence.get("wins[1]"), Ok(PdefValue::Int(7)));
+        assert_eq!(
+            persistence.get("lastMode"),
+            Ok(PdefValue::Enum("tdm".into()))
+        );
+        assert_eq!(
+            persistence.get("tag"),
+            Ok(PdefValue::String("1234567".into()))
+        );
+        assert_eq!(
+            persistence.get("loadouts[2].primary"),
+            Ok(PdefValue::String("mp_weapon_car".into()))
+        );
+        assert_eq!(
+            persistence.get("loadouts[2].unlocked[1]"),
+            Ok(PdefValue::Bool(true))
+        );
+
+        assert_eq!(
+            persistence.set("tag", "12345678"),
+            Err(PersistenceError::StringTooLong {
+                path: "tag".into(),
+                max: 7
+            })
+        );
+        assert_eq!(
+            persistence.set("xp", 1.5),
+            Err(PersistenceError::TypeMismatch {
+                path: "xp".into(),
+                value: PdefValue::Float(1.5)
+            })
+        );
+        assert_eq!(
+            persistence.set("lastMode", "ffa"),
+            Err(PersistenceError::TypeMismatch {
+                path: "lastMode".into(),
+                value: PdefValue::Enum("ffa".into())
+            })
+        );
+        assert!(persistence.set("loadouts[3].primary", "").is_err());
+
+        // shorter strings clear what was there before
+        persistence.set("tag", "RRP").unwrap();
+        assert_eq!(persistence.get("tag"), Ok(PdefValue::String("RRP".into())));
+
+        // the trailing bytes are outside of the schema
+        assert!(buffer[pdef.size()..].iter().all(|b| *b == 0));
+        assert_eq!(buffer[18 + 4..18 + 8], 7i32.to_le_bytes());
+
+        buffer[9] = 9;
+        let persistence = PlayerPersistence::new(&pdef, buffer.as_slice()).unwrap();
+        assert_eq!(
+            persistence.get("lastMode"),
+            Err(PersistenceError::InvalidEnumValue {
+                path: "lastMode".into(),
+                value: 9
+            })
+        );
+        assert_eq!(
+            PlayerPersistence::new(&pdef, &buffer[..10]).err(),
+            Some(PersistenceError::BufferTooSmall {
+                needed: pdef.size(),
+                len: 10
+            })
+        );
+    }
+}
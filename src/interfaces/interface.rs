@@ -1,4 +1,10 @@
 use std::{ffi::c_void, marker::PhantomData, ptr::NonNull};
+use windows::Win32::Foundation::HMODULE;
+
+use crate::{
+    bindings::plugin_abi::{InterfaceStatus, LogLevel, PluginField, PluginString},
+    high::vector::Vector3,
+};
 
 // #[allow(unused)] // because vtable will never be read by the plugin iteself
 #[repr(C)]
@@ -16,8 +22,204 @@ impl<T: Sync + Send> Interface<T> {
             marker: PhantomData,
         }
     }
+
+    /// the data behind the vtable
+    pub const fn data(&self) -> &T {
+        &self.data
+    }
 }
 
 pub trait AsInterface: Sized + Sync + Send {
     fn to_interface(self) -> Interface<Self>;
 }
+
+/// checks that a file generated by `#[as_interface]` is up to date; called by the tests the macro generates
+///
+/// the file is written if it doesn't exist or if the `RRPLUG_UPDATE_BINDINGS` env var is set
+///
+/// # Panics
+///
+/// panics if the file is different from `contents` or if it couldn't be written
+pub fn check_generated_file(path: &str, contents: &str) {
+    let path = std::path::Path::new(path);
+
+    match std::fs::read_to_string(path) {
+        Ok(old) if old == contents => return,
+        Ok(_) if std::env::var_os("RRPLUG_UPDATE_BINDINGS").is_none() => panic!(
+            "{} is out of date; run the tests with RRPLUG_UPDATE_BINDINGS=1 to regenerate it",
+            path.display()
+        ),
+        _ => {}
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).expect("couldn't create the directory for the file");
+    }
+    std::fs::write(path, contents)
+        .unwrap_or_else(|err| panic!("couldn't write {}: {err}", path.display()));
+}
+
+/// types which can be passed through the vtable of an interface
+///
+/// implement it for your own `#[repr(C)]` types to use them in interfaces
+///
+/// `#[as_interface]` rejects everything else
+///
+/// ```compile_fail
+/// struct Names;
+///
+/// #[rrplug::as_interface]
+/// impl Names {
+///     fn new() -> Self {
+///         Self
+///     }
+///
+///     // `&str` is a fat pointer
+///     pub fn get_name(&self) -> &'static str {
+///         "name"
+///     }
+/// }
+/// ```
+///
+/// ```compile_fail
+/// struct Stored;
+///
+/// #[rrplug::as_interface]
+/// impl Stored {
+///     fn new() -> Self {
+///         Self
+///     }
+///
+///     // a vtable can't have an entry for every `T`
+///     pub fn store<T: rrplug::interfaces::interface::FfiSafe>(&self, value: T) {}
+/// }
+/// ```
+///
+/// ```
+/// use rrplug::interfaces::interface::FfiSafe;
+///
+/// #[repr(C)]
+/// #[derive(Clone, Copy)]
+/// struct Score {
+///     kills: i32,
+///     deaths: i32,
+/// }
+///
+/// unsafe impl FfiSafe for Score {}
+///
+/// struct Scores;
+///
+/// #[rrplug::as_interface]
+/// impl Scores {
+///     fn new() -> Self {
+///         Self
+///     }
+///
+///     pub fn get_score(&self, player_index: i32) -> Score {
+///         Score { kills: player_index, deaths: 0 }
+///     }
+/// }
+/// ```
+///
+/// # Safety
+///
+/// the type has to have a layout c and c++ understand
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't be passed through an interface since it isn't ffi safe",
+    label = "not ffi safe",
+    note = "use primitives, raw pointers, references to sized types, `extern \"C\"` function pointers or implement `FfiSafe` for a `#[repr(C)]` type"
+)]
+pub unsafe trait FfiSafe {}
+
+/// used by `#[as_interface]` to check every argument and return type
+#[doc(hidden)]
+#[inline(always)]
+pub const fn assert_ffi_safe<T: FfiSafe>() {}
+
+macro_rules! impl_ffi_safe {
+    ($($ty:ty),* $(,)?) => {
+        $(unsafe impl FfiSafe for $ty {})*
+    };
+}
+
+macro_rules! impl_ffi_safe_fn {
+    ($($arg:ident),*) => {
+        unsafe impl<R, $($arg),*> FfiSafe for extern "C" fn($($arg),*) -> R {}
+        unsafe impl<R, $($arg),*> FfiSafe for unsafe extern "C" fn($($arg),*) -> R {}
+        unsafe impl<R, $($arg),*> FfiSafe for Option<extern "C" fn($($arg),*) -> R> {}
+        unsafe impl<R, $($arg),*> FfiSafe for Option<unsafe extern "C" fn($($arg),*) -> R> {}
+    };
+}
+
+impl_ffi_safe!(
+    (),
+    bool,
+    i8,
+    i16,
+    i32,
+    i64,
+    isize,
+    u8,
+    u16,
+    u32,
+    u64,
+    usize,
+    f32,
+    f64,
+    HMODULE,
+    Vector3,
+    PluginString,
+    PluginField,
+    InterfaceStatus,
+    LogLevel,
+);
+
+unsafe impl<T> FfiSafe for *const T {}
+unsafe impl<T> FfiSafe for *mut T {}
+unsafe impl<T> FfiSafe for &T {}
+unsafe impl<T> FfiSafe for &mut T {}
+unsafe impl<T> FfiSafe for NonNull<T> {}
+unsafe impl<T> FfiSafe for Option<&T> {}
+unsafe impl<T> FfiSafe for Option<&mut T> {}
+unsafe impl<T> FfiSafe for Option<NonNull<T>> {}
+
+impl_ffi_safe_fn!();
+impl_ffi_safe_fn!(A);
+impl_ffi_safe_fn!(A, B);
+impl_ffi_safe_fn!(A, B, C);
+impl_ffi_safe_fn!(A, B, C, D);
+impl_ffi_safe_fn!(A, B, C, D, E);
+impl_ffi_safe_fn!(A, B, C, D, E, F);
+
+/// the version of an interface
+///
+/// a version is compatible with a required version if the major versions are the same and the minor version is at least the required one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InterfaceVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl InterfaceVersion {
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+
+    /// returns [`true`] if this version can be used by something that needs `required`
+    pub const fn is_compatible_with(&self, required: Self) -> bool {
+        self.major == required.major && self.minor >= required.minor
+    }
+}
+
+impl std::fmt::Display for InterfaceVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// the name and version of a registered interface
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InterfaceInfo {
+    pub name: String,
+    pub version: InterfaceVersion,
+}
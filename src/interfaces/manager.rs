@@ -1,18 +1,108 @@
+//! registration of this plugin's interfaces and lookup of interfaces from this plugin or other modules
+//!
+//! interfaces are the way plugins share services with each other without passing raw pointers through squirrel
+//!
+//! interfaces can be registered with a version so a shared api can change without breaking older consumers; the exported `CreateInterface` understands `name` for the highest version, `name@major` and `name@major.minor` for the highest compatible version
+//!
+//! ```no_run
+//! use rrplug::prelude::*;
+//! use rrplug::{
+//!     create_external_interface,
+//!     interfaces::{
+//!         interface::InterfaceVersion,
+//!         manager::{get_compatible_from_module, get_from_module, get_local, module_interfaces},
+//!     },
+//! };
+//!
+//! // the interface exported by another plugin
+//! create_external_interface! {
+//!     pub OtherPluginStats + OtherPluginStatsMod => {
+//!         pub fn get_kills(player_index: i32) -> i32;
+//!     }
+//! }
+//!
+//! # struct MyInterface;
+//! # impl MyInterface { fn get_line(&self) -> &'static str { "" } }
+//! # impl rrplug::interfaces::interface::AsInterface for MyInterface {
+//! #     fn to_interface(self) -> rrplug::interfaces::interface::Interface<Self> { todo!() }
+//! # }
+//! match unsafe { get_from_module::<OtherPluginStats>("other_plugin.dll", "OtherPluginStats001") } {
+//!     Ok(stats) => log::info!("player 1 has {} kills", unsafe { stats.get_kills(1) }),
+//!     Err(err) => err.log(),
+//! }
+//!
+//! // an interface registered by this plugin
+//! if let Ok(interface) = get_local::<MyInterface>("MyInterface001") {
+//!     log::info!("{}", interface.get_line());
+//! }
+//!
+//! // any 1.x version of the interface which has at least what 1.2 added
+//! match unsafe {
+//!     get_compatible_from_module::<OtherPluginStats>(
+//!         "other_plugin.dll",
+//!         "OtherPluginStats",
+//!         InterfaceVersion::new(1, 2),
+//!     )
+//! } {
+//!     Ok(stats) => log::info!("player 1 has {} kills", unsafe { stats.get_kills(1) }),
+//!     Err(err) => err.log(),
+//! }
+//!
+//! for info in module_interfaces("other_plugin.dll").unwrap_or_default() {
+//!     log::info!("other_plugin.dll has {} {}", info.name, info.version);
+//! }
+//! ```
+
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
 use std::{
+    any::TypeId,
     collections::HashMap,
     ffi::{c_char, c_void, CStr},
 };
+use windows::{
+    core::PCSTR,
+    Win32::{
+        Foundation::HMODULE,
+        System::LibraryLoader::{GetModuleHandleA, GetProcAddress},
+    },
+};
+
+use crate::{
+    bindings::plugin_abi::{CreateInterface, GetInterfaceInfo, InterfaceStatus, RawInterfaceInfo},
+    errors::InterfaceError,
+    high::UnsafeHandle,
+    mid::utils::try_cstring,
+};
+
+use super::{
+    external::SourceInterface,
+    interface::{AsInterface, Interface, InterfaceInfo, InterfaceVersion},
+};
 
-use crate::high::UnsafeHandle;
+struct RegisteredInterface {
+    interface: *const c_void,
+    type_id: TypeId,
+    version: InterfaceVersion,
+    /// leaked like the interface since other modules can hold on to it through `GetInterfaceInfo`
+    c_name: &'static CStr,
+}
 
-use super::interface::{AsInterface, Interface};
+/// every version of every interface keyed by name
+type InterfaceRegistry = HashMap<&'static str, Vec<UnsafeHandle<RegisteredInterface>>>;
 
 /// SAFETY: keys cannot be removed or else a memory leak will accure, this whole thing lives for the entire duration of a plugin
-static REGISTERED_INTERFACES: Lazy<Mutex<HashMap<&'static str, UnsafeHandle<*const c_void>>>> =
+static REGISTERED_INTERFACES: Lazy<Mutex<InterfaceRegistry>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
+/// the sorted list `GetInterfaceInfo` indexes into; cleared when an interface is registered
+///
+/// always locked after [`REGISTERED_INTERFACES`]
+static INTERFACE_INFO_CACHE: Mutex<Option<Vec<UnsafeHandle<RawInterfaceInfo>>>> = Mutex::new(None);
+
+/// how many interfaces are read from another module before it's assumed to be broken
+const MAX_MODULE_INTERFACES: usize = 4096;
+
 #[no_mangle]
 #[export_name = "CreateInterface"]
 unsafe extern "C" fn create_interface(
@@ -24,25 +114,482 @@ unsafe extern "C" fn create_interface(
     unsafe { CStr::from_ptr(interface_name) }
         .to_str()
         .ok()
-        .and_then(|name| interfaces.get(name))
-        .map(|interface| interface.copy())
+        .and_then(parse_query)
+        .and_then(|(name, required)| find_compatible(interfaces.get(name)?, required))
+        .map(|interface| interface.interface)
         .unwrap_or_else(|| {
             unsafe { *error = 1 };
             std::ptr::null()
         })
 }
 
+#[export_name = "GetInterfaceInfo"]
+unsafe extern "C" fn get_interface_info(index: usize, info: *mut RawInterfaceInfo) -> bool {
+    let interfaces = REGISTERED_INTERFACES.lock();
+    let mut cache = INTERFACE_INFO_CACHE.lock();
+    let sorted = cache.get_or_insert_with(|| {
+        let mut sorted = interfaces
+            .iter()
+            .flat_map(|(name, versions)| versions.iter().map(move |version| (name, version.get())))
+            .collect::<Vec<_>>();
+        sorted.sort_by_key(|(name, interface)| (**name, interface.version));
+
+        sorted
+            .into_iter()
+            .map(|(_, interface)| {
+                UnsafeHandle::internal_new(RawInterfaceInfo {
+                    name: interface.c_name.as_ptr(),
+                    major: interface.version.major,
+                    minor: interface.version.minor,
+                })
+            })
+            .collect()
+    });
+
+    match sorted.get(index) {
+        Some(raw) => {
+            unsafe { *info = raw.copy() };
+            true
+        }
+        None => false,
+    }
+}
+
+/// registers an interface without a version; same as version `0.0`
+///
+/// # Errors
+///
+/// this function will return an error if the name contains a null char
 pub unsafe fn register_interface<T: Send + Sync + 'static + AsInterface>(
     name: &'static str,
     interface: Interface<T>,
-) {
-    REGISTERED_INTERFACES.lock().insert(
+) -> Result<(), InterfaceError> {
+    unsafe { register_versioned_interface(name, InterfaceVersion::default(), interface) }
+}
+
+/// registers a version of an interface which replaces an earlier registration of the same version
+///
+/// other versions stay registered so older consumers can keep using them
+///
+/// # Errors
+///
+/// this function will return an error if the name contains a null char
+pub unsafe fn register_versioned_interface<T: Send + Sync + 'static + AsInterface>(
+    name: &'static str,
+    version: InterfaceVersion,
+    interface: Interface<T>,
+) -> Result<(), InterfaceError> {
+    let c_name = Box::leak(try_cstring(name)?.into_boxed_c_str());
+
+    let registered = UnsafeHandle::internal_new(RegisteredInterface {
+        interface: Box::leak(Box::new(interface)) as *const _ as *const c_void,
+        type_id: TypeId::of::<T>(),
+        version,
+        c_name,
+    });
+
+    let mut interfaces = REGISTERED_INTERFACES.lock();
+    *INTERFACE_INFO_CACHE.lock() = None;
+    let versions = interfaces.entry(name).or_default();
+    match versions
+        .iter_mut()
+        .find(|other| other.get().version == version)
+    {
+        Some(other) => *other = registered,
+        None => versions.push(registered),
+    }
+
+    Ok(())
+}
+
+/// the highest version of an interface registered by this plugin with [`register_interface`] or [`register_versioned_interface`]
+///
+/// # Errors
+///
+/// this function will return an error if the interface isn't registered or was registered with another type
+pub fn get_local<T: Send + Sync + 'static + AsInterface>(
+    name: &str,
+) -> Result<&'static T, InterfaceError> {
+    get_local_inner(name, None)
+}
+
+/// the highest version of an interface registered by this plugin which is compatible with `required`
+///
+/// # Errors
+///
+/// this function will return an error if the interface isn't registered, if no version is compatible or if the compatible version was registered with another type
+pub fn get_local_compatible<T: Send + Sync + 'static + AsInterface>(
+    name: &str,
+    required: InterfaceVersion,
+) -> Result<&'static T, InterfaceError> {
+    get_local_inner(name, Some(required))
+}
+
+fn get_local_inner<T: Send + Sync + 'static + AsInterface>(
+    name: &str,
+    required: Option<InterfaceVersion>,
+) -> Result<&'static T, InterfaceError> {
+    let interfaces = REGISTERED_INTERFACES.lock();
+    let versions = interfaces
+        .get(name)
+        .ok_or_else(|| InterfaceError::NotRegistered(name.to_string()))?;
+    let interface =
+        find_compatible(versions, required).ok_or_else(|| InterfaceError::IncompatibleVersion {
+            interface: name.to_string(),
+            required: required.unwrap_or_default(),
+        })?;
+
+    if interface.type_id != TypeId::of::<T>() {
+        return Err(InterfaceError::TypeMismatch(name.to_string()));
+    }
+
+    // registered interfaces are leaked so they live for the rest of the plugin
+    Ok(unsafe { &*interface.interface.cast::<Interface<T>>() }.data())
+}
+
+/// every interface registered by this plugin sorted by name and version
+pub fn local_interfaces() -> Vec<InterfaceInfo> {
+    let mut infos = REGISTERED_INTERFACES
+        .lock()
+        .iter()
+        .flat_map(|(name, versions)| {
+            versions.iter().map(|interface| InterfaceInfo {
+                name: name.to_string(),
+                version: interface.get().version,
+            })
+        })
+        .collect::<Vec<InterfaceInfo>>();
+    infos.sort_by(|a, b| (&a.name, a.version).cmp(&(&b.name, b.version)));
+    infos
+}
+
+/// an interface from the `CreateInterface` of a loaded module like another plugin
+///
+/// # Safety
+///
+/// `T` has to match the vtable of the interface
+///
+/// # Errors
+///
+/// this function will return an error if the module isn't loaded, doesn't export `CreateInterface` or doesn't have the interface
+pub unsafe fn get_from_module<T: SourceInterface>(
+    module: &str,
+    name: &str,
+) -> Result<&'static T, InterfaceError> {
+    let module_name = try_cstring(module)?;
+    let handle = unsafe { GetModuleHandleA(PCSTR(module_name.as_ptr().cast())) }
+        .map_err(|_| InterfaceError::ModuleNotFound(module.to_string()))?;
+
+    unsafe { get_from_handle(handle, module, name) }
+}
+
+/// an interface from the `CreateInterface` of a module handle
+///
+/// `module` is only used for errors
+///
+/// # Safety
+///
+/// `handle` has to be a loaded module and `T` has to match the vtable of the interface
+///
+/// # Errors
+///
+/// this function will return an error if the module doesn't export `CreateInterface` or doesn't have the interface
+pub unsafe fn get_from_handle<T: SourceInterface>(
+    handle: HMODULE,
+    module: &str,
+    name: &str,
+) -> Result<&'static T, InterfaceError> {
+    let create_interface = unsafe {
+        GetProcAddress(handle, PCSTR(c"CreateInterface".as_ptr().cast())).map(|func| {
+            std::mem::transmute::<unsafe extern "system" fn() -> isize, CreateInterface>(func)
+        })
+    }
+    .ok_or_else(|| InterfaceError::NoCreateInterface(module.to_string()))?;
+
+    unsafe { call_create_interface(create_interface, module, name) }
+}
+
+/// the highest version of an interface from another rrplug plugin which is compatible with `required`
+///
+/// # Safety
+///
+/// `T` has to match the vtable of every compatible version
+///
+/// # Errors
+///
+/// this function will return an error if the module isn't loaded, doesn't export `CreateInterface` or doesn't have a compatible version
+pub unsafe fn get_compatible_from_module<T: SourceInterface>(
+    module: &str,
+    name: &str,
+    required: InterfaceVersion,
+) -> Result<&'static T, InterfaceError> {
+    match unsafe { get_from_module(module, &versioned_name(name, required)) } {
+        Err(InterfaceError::NotFound { .. }) => Err(InterfaceError::IncompatibleVersion {
+            interface: name.to_string(),
+            required,
+        }),
+        result => result,
+    }
+}
+
+/// every interface a loaded rrplug plugin exports sorted by name and version
+///
+/// # Errors
+///
+/// this function will return an error if the module isn't loaded or isn't a rrplug plugin
+pub fn module_interfaces(module: &str) -> Result<Vec<InterfaceInfo>, InterfaceError> {
+    let module_name = try_cstring(module)?;
+    let get_info = unsafe {
+        let handle = GetModuleHandleA(PCSTR(module_name.as_ptr().cast()))
+            .map_err(|_| InterfaceError::ModuleNotFound(module.to_string()))?;
+
+        GetProcAddress(handle, PCSTR(c"GetInterfaceInfo".as_ptr().cast())).map(|func| {
+            std::mem::transmute::<unsafe extern "system" fn() -> isize, GetInterfaceInfo>(func)
+        })
+    }
+    .ok_or_else(|| InterfaceError::NotEnumerable(module.to_string()))?;
+
+    Ok(unsafe { collect_interface_info(get_info) })
+}
+
+unsafe fn collect_interface_info(get_info: GetInterfaceInfo) -> Vec<InterfaceInfo> {
+    let mut raw = RawInterfaceInfo {
+        name: std::ptr::null(),
+        major: 0,
+        minor: 0,
+    };
+
+    let mut infos: Vec<InterfaceInfo> = Vec::new();
+    for index in 0..MAX_MODULE_INTERFACES {
+        if !unsafe { get_info(index, &mut raw) } {
+            break;
+        }
+        if raw.name.is_null() {
+            continue;
+        }
+
+        let info = InterfaceInfo {
+            name: unsafe { CStr::from_ptr(raw.name) }
+                .to_string_lossy()
+                .to_string(),
+            version: InterfaceVersion::new(raw.major, raw.minor),
+        };
+
+        // the list is sorted so a repeat means the module keeps returning the same entry
+        if infos.last() == Some(&info) {
+            break;
+        }
+        infos.push(info);
+    }
+
+    infos
+}
+
+/// the name [`create_interface`] resolves to the highest version compatible with `required`
+fn versioned_name(name: &str, required: InterfaceVersion) -> String {
+    format!("{name}@{}.{}", required.major, required.minor)
+}
+
+/// splits `name`, `name@major` or `name@major.minor` into the name and the required version
+fn parse_query(query: &str) -> Option<(&str, Option<InterfaceVersion>)> {
+    let Some((name, version)) = query.split_once('@') else {
+        return Some((query, None));
+    };
+
+    let (major, minor) = version.split_once('.').unwrap_or((version, "0"));
+    Some((
         name,
-        UnsafeHandle::internal_new(Box::leak(Box::new(interface)) as *const _ as *const c_void),
-    );
+        Some(InterfaceVersion::new(
+            major.parse().ok()?,
+            minor.parse().ok()?,
+        )),
+    ))
 }
 
-// TODO: implement this
-// pub unsafe fn get_local(
+/// the highest version which is compatible with `required` or the highest version if nothing is required
+fn find_compatible(
+    versions: &[UnsafeHandle<RegisteredInterface>],
+    required: Option<InterfaceVersion>,
+) -> Option<&RegisteredInterface> {
+    versions
+        .iter()
+        .map(UnsafeHandle::get)
+        .filter(|interface| {
+            required.is_none_or(|required| interface.version.is_compatible_with(required))
+        })
+        .max_by_key(|interface| interface.version)
+}
+
+unsafe fn call_create_interface<T>(
+    create_interface: CreateInterface,
+    module: &str,
+    name: &str,
+) -> Result<&'static T, InterfaceError> {
+    let interface_name = try_cstring(name)?;
+    let mut status = InterfaceStatus::IfaceOk;
+
+    unsafe {
+        create_interface(interface_name.as_ptr(), &mut status)
+            .cast::<T>()
+            .as_ref()
+    }
+    .filter(|_| matches!(status, InterfaceStatus::IfaceOk))
+    .ok_or_else(|| InterfaceError::NotFound {
+        module: module.to_string(),
+        interface: name.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::CString;
+
+    use super::*;
+    use crate::interfaces::test::TestInterface;
+
+    #[test]
+    fn local_lookup() {
+        unsafe { register_interface("TestInterface001", TestInterface::new()) }.unwrap();
+        assert!(matches!(
+            unsafe { register_interface("Test\0Interface", TestInterface::new()) },
+            Err(InterfaceError::InvalidCString(_))
+        ));
 
-// )
+        assert_eq!(
+            get_local::<TestInterface>("TestInterface001").map(|interface| interface.line()),
+            Ok("line")
+        );
+        assert_eq!(
+            get_local::<TestInterface>("TestInterface002").err(),
+            Some(InterfaceError::NotRegistered(
+                "TestInterface002".to_string()
+            ))
+        );
+
+        struct Other;
+        impl AsInterface for Other {
+            fn to_interface(self) -> Interface<Self> {
+                unreachable!()
+            }
+        }
+        assert_eq!(
+            get_local::<Other>("TestInterface001").err(),
+            Some(InterfaceError::TypeMismatch("TestInterface001".to_string()))
+        );
+
+        // the exported CreateInterface sees the same registry
+        unsafe extern "C" fn exported(
+            name: *const c_char,
+            status: *mut InterfaceStatus,
+        ) -> *const c_void {
+            unsafe { create_interface(name, status.cast()) }
+        }
+        let create_interface = exported as CreateInterface;
+        let found = unsafe {
+            call_create_interface::<Interface<TestInterface>>(
+                create_interface,
+                "rrplug",
+                "TestInterface001",
+            )
+        };
+        assert_eq!(found.map(|interface| interface.data().line()), Ok("line"));
+        assert!(matches!(
+            unsafe { call_create_interface::<c_void>(create_interface, "rrplug", "Missing001") },
+            Err(InterfaceError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn versioned_lookup() {
+        for (major, minor) in [(1, 0), (1, 3), (2, 0)] {
+            let line = Box::leak(
+                CString::new(format!("{major}.{minor}"))
+                    .unwrap()
+                    .into_boxed_c_str(),
+            );
+            unsafe {
+                register_versioned_interface(
+                    "TestVersioned",
+                    InterfaceVersion::new(major, minor),
+                    TestInterface { the_line: line }.to_interface(),
+                )
+            }
+            .unwrap();
+        }
+
+        let line = |required| {
+            get_local_compatible::<TestInterface>("TestVersioned", required)
+                .map(|interface| interface.line())
+        };
+        assert_eq!(line(InterfaceVersion::new(1, 0)), Ok("1.3"));
+        assert_eq!(line(InterfaceVersion::new(1, 3)), Ok("1.3"));
+        assert_eq!(line(InterfaceVersion::new(2, 0)), Ok("2.0"));
+        assert_eq!(
+            line(InterfaceVersion::new(1, 4)),
+            Err(InterfaceError::IncompatibleVersion {
+                interface: "TestVersioned".to_string(),
+                required: InterfaceVersion::new(1, 4)
+            })
+        );
+        assert_eq!(
+            get_local::<TestInterface>("TestVersioned").map(|interface| interface.line()),
+            Ok("2.0")
+        );
+
+        unsafe extern "C" fn exported(
+            name: *const c_char,
+            status: *mut InterfaceStatus,
+        ) -> *const c_void {
+            unsafe { create_interface(name, status.cast()) }
+        }
+        let exported_line = |name: &str| unsafe {
+            call_create_interface::<Interface<TestInterface>>(exported, "rrplug", name)
+                .map(|interface| interface.data().line())
+        };
+        assert_eq!(exported_line("TestVersioned"), Ok("2.0"));
+        assert_eq!(exported_line("TestVersioned@1"), Ok("1.3"));
+        assert_eq!(
+            exported_line(&versioned_name(
+                "TestVersioned",
+                InterfaceVersion::new(1, 1)
+            )),
+            Ok("1.3")
+        );
+        assert!(exported_line("TestVersioned@3").is_err());
+        assert!(exported_line("TestVersioned@one").is_err());
+
+        let versions = |infos: Vec<InterfaceInfo>| {
+            infos
+                .into_iter()
+                .filter(|info| info.name == "TestVersioned")
+                .map(|info| info.version.to_string())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(versions(local_interfaces()), ["1.0", "1.3", "2.0"]);
+        assert_eq!(
+            versions(unsafe { collect_interface_info(get_interface_info) }),
+            ["1.0", "1.3", "2.0"]
+        );
+    }
+
+    #[test]
+    fn broken_enumeration_stops() {
+        unsafe extern "C" fn repeating(_: usize, info: *mut RawInterfaceInfo) -> bool {
+            unsafe {
+                *info = RawInterfaceInfo {
+                    name: c"Broken001".as_ptr(),
+                    major: 1,
+                    minor: 0,
+                }
+            };
+            true
+        }
+        unsafe extern "C" fn unnamed(_: usize, info: *mut RawInterfaceInfo) -> bool {
+            unsafe { (*info).name = std::ptr::null() };
+            true
+        }
+
+        assert_eq!(unsafe { collect_interface_info(repeating) }.len(), 1);
+        assert!(unsafe { collect_interface_info(unnamed) }.is_empty());
+    }
+}
@@ -3,46 +3,150 @@ pub mod interface;
 pub mod manager;
 
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
+    use std::ffi::{c_char, CStr};
+
     use crate::rrplug;
     use rrplug_proc::as_interface;
 
+    use super::interface::AsInterface;
+
     #[repr(C)]
-    struct TestInterface {
-        the_line: &'static str,
+    pub(crate) struct TestInterface {
+        pub(crate) the_line: &'static CStr,
     }
 
     #[as_interface]
-    #[allow(improper_ctypes_definitions)]
     impl TestInterface {
+        pub(crate) fn new() -> Self {
+            Self { the_line: c"line" }
+        }
+
+        pub const fn get_line(&self) -> *const c_char {
+            self.the_line.as_ptr()
+        }
+    }
+
+    impl TestInterface {
+        pub(crate) fn line(&self) -> &'static str {
+            self.the_line.to_str().unwrap()
+        }
+    }
+
+    struct BindingsTestInterface;
+
+    #[as_interface(
+        Bindings = "target/interface_bindings/bindings_test.rs",
+        Header = "target/interface_bindings/bindings_test.h"
+    )]
+    impl BindingsTestInterface {
+        fn new() -> Self {
+            Self
+        }
+
+        pub fn get_name(&self, player_index: i32, buffer: *mut c_char, len: usize) -> bool {
+            _ = (player_index, buffer, len);
+            false
+        }
+
+        pub fn get_origin(&self, player_index: i32) -> rrplug::high::vector::Vector3 {
+            _ = player_index;
+            rrplug::high::vector::Vector3::ZERO
+        }
+
+        pub fn reset(&self, entity: Option<&std::ffi::c_void>) {
+            _ = entity;
+        }
+    }
+
+    #[repr(C)]
+    struct GenericTestInterface<T> {
+        smth: T,
+    }
+
+    #[as_interface]
+    impl<T: Default + Clone + Sync + Send + super::interface::FfiSafe> GenericTestInterface<T> {
         fn new() -> Self {
-            Self { the_line: "line" }
+            Self { smth: T::default() }
         }
 
-        pub const fn get_line(&self) -> &'static str {
-            self.the_line
+        pub fn clone(&self) -> T {
+            self.smth.clone()
+        }
+
+        pub fn store(&self, smth: T) {
+            _ = smth;
         }
     }
 
-    // NOTE: this is not really super important
-    // #[repr(C)]
-    // struct GenericTestInterface<T> {
-    //     smth: T,
-    // }
+    #[test]
+    fn generic_vtables() {
+        // every type gets its own vtable
+        let int_interface = GenericTestInterface { smth: 7i32 }.to_interface();
+        let float_interface = GenericTestInterface { smth: 0.5f32 }.to_interface();
+        _ = GenericTestInterface::<u8>::new();
 
-    // #[as_interface]
-    // #[allow(improper_ctypes_definitions)]
-    // impl<T: Default + Clone + Sync + Send> GenericTestInterface<T> {
-    //     fn new() -> Self {
-    //         Self { smth: T::default() }
-    //     }
+        let call = |interface: *const std::ffi::c_void, index: usize| unsafe {
+            let vtable = *interface.cast::<*const usize>();
+            *vtable.add(index)
+        };
+        let int_ptr = &int_interface as *const _ as *const std::ffi::c_void;
+        let float_ptr = &float_interface as *const _ as *const std::ffi::c_void;
+        assert_ne!(call(int_ptr, 0), call(float_ptr, 0));
 
-    //     pub fn clone<T: Default + Clone + Sync + Send>(&self) -> T {
-    //         self.smth.clone()
-    //     }
+        let clone_int = unsafe {
+            std::mem::transmute::<usize, unsafe extern "C" fn(*const std::ffi::c_void) -> i32>(
+                call(int_ptr, 0),
+            )
+        };
+        let clone_float = unsafe {
+            std::mem::transmute::<usize, unsafe extern "C" fn(*const std::ffi::c_void) -> f32>(
+                call(float_ptr, 0),
+            )
+        };
+        assert_eq!(unsafe { clone_int(int_ptr) }, 7);
+        assert_eq!(unsafe { clone_float(float_ptr) }, 0.5);
+    }
 
-    //     pub fn store(&self, smth: T) {
-    //         _ = smth;
-    //     }
-    // }
+    #[test]
+    fn generated_bindings() {
+        _ = BindingsTestInterface::new();
+
+        assert_eq!(
+            BindingsTestInterface::INTERFACE_BINDINGS,
+            r#"// generated by #[as_interface] from BindingsTestInterface; don't edit
+
+#[allow(unused_imports)]
+use std::ffi::*;
+
+rrplug::create_external_interface! {
+    pub BindingsTestInterface + BindingsTestInterfaceMod => {
+        pub fn get_name(player_index: i32, buffer: *mut c_char, len: usize) -> bool;
+        pub fn get_origin(player_index: i32) -> rrplug::high::vector::Vector3;
+        pub fn reset(entity: Option<&std::ffi::c_void>) -> ();
+    }
+}
+"#
+        );
+
+        let header = BindingsTestInterface::INTERFACE_HEADER;
+        for line in [
+            "// these types have to be defined before this header is included: Vector3",
+            "    bool get_name(int32_t player_index, char* buffer, size_t len) { return vtable->get_name(this, player_index, buffer, len); }",
+            "    Vector3 get_origin(int32_t player_index) { return vtable->get_origin(this, player_index); }",
+            "    void reset(void const* entity) { return vtable->reset(this, entity); }",
+            "    bool (*get_name)(BindingsTestInterface* self, int32_t player_index, char* buffer, size_t len);",
+            "    Vector3 (*get_origin)(BindingsTestInterface* self, int32_t player_index);",
+            "    void (*reset)(BindingsTestInterface* self, void const* entity);",
+            "    BindingsTestInterfaceVTable const* vtable;",
+        ] {
+            assert!(header.lines().any(|other| other == line), "{line}");
+        }
+
+        // the vtable order is the order of the functions
+        let get_name = header.find("(*get_name)").unwrap();
+        let get_origin = header.find("(*get_origin)").unwrap();
+        let reset = header.find("(*reset)").unwrap();
+        assert!(get_name < get_origin && get_origin < reset);
+    }
 }
@@ -41,6 +41,7 @@ pub use rrplug_proc::{as_interface, completion, concommand, convar, sqfunction};
 #[doc(hidden)]
 /// used by some macros
 pub mod exports {
+    pub use inventory;
     pub use log;
     pub use once_cell::sync::OnceCell;
     pub use windows;
@@ -116,7 +116,18 @@ macro_rules! entry {
                     PLUGIN.wait().plugins_loaded()
                 }
                 fn Unload(&self) -> bool {
-                    PLUGIN.wait().on_reload_request().should_reload()
+                    let should_reload = PLUGIN.wait().on_reload_request().should_reload();
+
+                    if should_reload {
+                        // hooks can point into the convars and concommands so they go first
+                        unsafe { mid::hooks::unhook_all() };
+                        unsafe { mid::engine::patch::restore_all() };
+                        unsafe {
+                            high::engine::unregister_all(high::engine::EngineToken::new_unchecked())
+                        };
+                    }
+
+                    should_reload
                 }
                 fn OnSqvmCreated(&self, sqvm: *mut squirreldatatypes::CSquirrelVM) {
                     _ = mid::squirrel::SQFUNCTIONS.try_init();
@@ -226,15 +237,59 @@ macro_rules! entry {
                         module.0 as *const std::ffi::c_void,
                     );
 
-                    unsafe {
-                        mid::engine::convars::CvarGlobals::try_init(
-                            &dll_ptr,
-                            &mid::engine::convars::CVAR_GLOBALS,
-                        );
-                        mid::engine::concommands::RegisterConCommands::try_init(
-                            &dll_ptr,
-                            &mid::engine::concommands::REGISTER_CONCOMNMADS,
-                        );
+                    // the tables log which check failed so this only says what stops working
+                    for (feature, result) in unsafe {
+                        [
+                            (
+                                "convars",
+                                mid::engine::convars::CvarGlobals::try_init(
+                                    &dll_ptr,
+                                    &mid::engine::convars::CVAR_GLOBALS,
+                                ),
+                            ),
+                            (
+                                "concommands",
+                                mid::engine::concommands::RegisterConCommands::try_init(
+                                    &dll_ptr,
+                                    &mid::engine::concommands::REGISTER_CONCOMNMADS,
+                                ),
+                            ),
+                            (
+                                "the command buffer",
+                                mid::engine::concommands::CbufFunctions::try_init(
+                                    &dll_ptr,
+                                    &mid::engine::concommands::CBUF_FUNCTIONS,
+                                ),
+                            ),
+                            (
+                                "global vars",
+                                mid::engine::globalvars::GlobalVars::try_init(
+                                    &dll_ptr,
+                                    &mid::engine::globalvars::GLOBAL_VARS,
+                                ),
+                            ),
+                            (
+                                "the client array",
+                                mid::server::ClientArray::try_init(
+                                    &dll_ptr,
+                                    &mid::server::CLIENT_ARRAY,
+                                ),
+                            ),
+                            (
+                                "server entities",
+                                mid::server::ServerEntities::try_init(
+                                    &dll_ptr,
+                                    &mid::server::SERVER_ENTITIES,
+                                ),
+                            ),
+                        ]
+                    } {
+                        if let Err(err) = result {
+                            $crate::exports::log::warn!(
+                                "{feature} won't be available since {} doesn't match: {err}",
+                                dll_string
+                            );
+                        }
                     }
                     mid::squirrel::SQFUNCTIONS.fetch_functions(&dll_ptr);
 
@@ -248,6 +303,9 @@ macro_rules! entry {
                                 .expect("no CVar?????"),
                             ));
                         }
+                        high::engine::statics::register_statics(unsafe {
+                            high::engine::EngineToken::new_unchecked()
+                        });
                         Some(mid::engine::ENGINE_DATA.wait())
                     } else {
                         None
@@ -277,12 +335,13 @@ macro_rules! entry {
                 if reason
                     == $crate::exports::windows::Win32::System::SystemServices::DLL_PROCESS_ATTACH
                 {
+                    // the names are valid cstrings so these can't fail
                     unsafe {
-                        $crate::interfaces::manager::register_interface(
+                        _ = $crate::interfaces::manager::register_interface(
                             "PluginId001",
                             PluginId::new(),
                         );
-                        $crate::interfaces::manager::register_interface(
+                        _ = $crate::interfaces::manager::register_interface(
                             "PluginCallbacks001",
                             PluginCallbacks::new(),
                         );
@@ -4,4 +4,5 @@ pub mod entry;
 #[deprecated]
 pub mod sq_return;
 pub mod sq_utils;
+pub mod statics;
 pub mod utils;
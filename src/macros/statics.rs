@@ -0,0 +1,153 @@
+//! macros to declare convars and concommands at module scope
+
+/// declares convars at module scope which are registered when `engine.dll` loads
+///
+/// each convar is a [`crate::high::engine::statics::StaticConVar`] bound to a type implementing [`crate::high::engine::convars::typed::ConVarValue`]
+///
+/// `flags`, `min`, `max` and `callback` are optional; `callback` should be created with [`crate::convar`]
+///
+/// requires [`crate::entry`] to be used by the plugin
+///
+/// ```no_run
+/// use rrplug::prelude::*;
+///
+/// rrplug::static_convar! {
+///     /// how fast something is
+///     pub static COOL_SPEED: f32 = {
+///         name: "cool_speed",
+///         default: 1.,
+///         flags: CVarFlags::empty(),
+///         help: "how fast something is",
+///         min: 0.,
+///         max: 10.,
+///         callback: cool_speed_changed,
+///     };
+///
+///     static COOL_NAME: String = {
+///         name: "cool_name",
+///         default: "cool".to_string(),
+///         help: "the cool name",
+///     };
+/// }
+///
+/// #[rrplug::convar]
+/// fn cool_speed_changed() {
+///     let token = unsafe { EngineToken::new_unchecked() };
+///     log::info!("new speed {}", COOL_SPEED.value(token));
+/// }
+/// ```
+#[macro_export]
+macro_rules! static_convar {
+    ( $(
+        $(#[$attr:meta])*
+        $vis:vis static $ident:ident : $ty:ty = {
+            name: $name:literal,
+            default: $default:expr,
+            $(flags: $flags:expr,)?
+            help: $help:expr
+            $(, min: $min:expr)?
+            $(, max: $max:expr)?
+            $(, callback: $callback:expr)?
+            $(,)?
+        };
+    )* ) => {
+        $(
+            $(#[$attr])*
+            $vis static $ident: $crate::high::engine::statics::StaticConVar<$ty> =
+                $crate::high::engine::statics::StaticConVar::new($name, || {
+                    #[allow(unused_variables)]
+                    let flags = $crate::bindings::cvar::CVarFlags::empty();
+                    $(let flags = $flags;)?
+
+                    let register = $crate::high::engine::convars::typed::TypedConVarRegister::<$ty>::new(
+                        $name,
+                        $default,
+                        flags,
+                        $help,
+                    );
+                    $(let register = register.with_min($min);)?
+                    $(let register = register.with_max($max);)?
+                    $(let register = register.with_callback(Some($callback));)?
+                    register
+                });
+
+            $crate::exports::inventory::submit! {
+                $crate::high::engine::statics::StaticRegistration(&$ident)
+            }
+        )*
+    };
+}
+
+/// declares concommands at module scope which are registered when `engine.dll` loads
+///
+/// each concommand is a [`crate::high::engine::statics::StaticConCommand`]
+///
+/// `callback` should be created with [`crate::concommand`] and the optional `completion` with [`crate::completion`];
+/// `flags` is optional too
+///
+/// requires [`crate::entry`] to be used by the plugin
+///
+/// ```no_run
+/// use rrplug::prelude::*;
+/// use rrplug::high::engine::concommands::{CommandCompletion, CurrentCommand};
+///
+/// rrplug::static_concommand! {
+///     pub static COOL_COMMAND = {
+///         name: "cool_command",
+///         flags: CVarFlags::empty(),
+///         help: "this is cool_command",
+///         callback: cool_command,
+///         completion: cool_completion,
+///     };
+/// }
+///
+/// #[rrplug::concommand]
+/// fn cool_command() {
+///     log::info!("cool_command");
+/// }
+///
+/// #[rrplug::completion]
+/// fn cool_completion(current: CurrentCommand, suggestions: CommandCompletion) {
+///     _ = suggestions.push(&format!("{} cool", current.cmd));
+/// }
+/// ```
+#[macro_export]
+macro_rules! static_concommand {
+    ( $(
+        $(#[$attr:meta])*
+        $vis:vis static $ident:ident = {
+            name: $name:literal,
+            $(flags: $flags:expr,)?
+            help: $help:expr,
+            callback: $callback:expr
+            $(, completion: $completion:expr)?
+            $(,)?
+        };
+    )* ) => {
+        $(
+            $(#[$attr])*
+            $vis static $ident: $crate::high::engine::statics::StaticConCommand =
+                $crate::high::engine::statics::StaticConCommand::new(
+                    $name,
+                    $help,
+                    {
+                        #[allow(unused_variables)]
+                        let flags = $crate::bindings::cvar::CVarFlags::empty();
+                        $(let flags = $flags;)?
+                        flags
+                    },
+                    $callback,
+                    {
+                        #[allow(unused_variables)]
+                        let completion: Option<$crate::high::engine::statics::CompletionCallback> = None;
+                        $(let completion: Option<$crate::high::engine::statics::CompletionCallback> = Some($completion);)?
+                        completion
+                    },
+                );
+
+            $crate::exports::inventory::submit! {
+                $crate::high::engine::statics::StaticRegistration(&$ident)
+            }
+        )*
+    };
+}
@@ -120,10 +120,23 @@ macro_rules! impl_vmethods {
     };
 }
 
-/// utility macro to get functions and globals from dlls with offsets
+/// utility macro to get functions and globals from dlls with offsets or patterns
 ///
 /// the generated struct has to be init in `on_dll_load`
 ///
+/// fields can be found with
+/// - `offset(0x...)` a fixed offset from the base of the dll
+/// - `pattern("48 8B ?? ?? E8")` the first match of a ida style pattern in the code sections of the dll
+/// - `pattern("48 8B 05 ?? ?? ?? ??", rip(3, 7))` the target of the rip relative operand of the matched instruction; see [`crate::mid::pattern::resolve_rip_relative`]
+///
+/// an optional `verify` block can check that the loaded dll is the expected version before anything is read from it with
+/// - `timestamp = 0x...;` the `TimeDateStamp` of the pe file header
+/// - `size_of_image = 0x...;` the `SizeOfImage` of the pe optional header
+/// - `signature(0x...) = "48 8B ?? ??";` a ida style pattern which has to match the bytes at an offset
+/// - `code(0x...);` the offset has to be inside an executable section which catches function offsets that ended up in data after an update
+///
+/// `try_init` logs and returns an error and doesn't initialize the struct if a check fails or if a pattern isn't found
+///
 /// # Example
 ///
 /// ```
@@ -133,17 +146,35 @@ macro_rules! impl_vmethods {
 /// offset_functions! {
 ///     ENGINE_FUNCTIONS + EngineFunctions for WhichDll::Engine => {
 ///         client_array = *const rrplug::bindings::class_types::client::CClient where offset(0x12A53F90);
+///         cbuf_execute = unsafe extern "C" fn() where pattern("48 89 5C 24 ?? 57 48 83 EC 20 48 8B 1D");
+///         globals = *const std::ffi::c_void where pattern("48 8B 05 ?? ?? ?? ?? F3 0F 10 40", rip(3, 7));
+///     }
+/// }
+///
+/// offset_functions! {
+///     VERIFIED_FUNCTIONS + VerifiedFunctions for WhichDll::Engine => verify {
+///         // placeholders; use the values of the dll build the offsets are from
+///         timestamp = 0x12345678;
+///         size_of_image = 0x1000000;
+///         signature(0x1203B0) = "48 89 5C 24 ?? 48 89 74 24";
+///         code(0x1203B0);
+///     } {
+///         cbuf_add_text = unsafe extern "C" fn(i32, *const std::ffi::c_char, i32) where offset(0x1203B0);
 ///     }
 /// }
 ///
 /// // init
 /// fn on_dll_load(engine_data: Option<&EngineData>, dll_ptr: &DLLPointer) {
-///     unsafe { EngineFunctions::try_init(dll_ptr, &ENGINE_FUNCTIONS) };
+///     _ = unsafe { EngineFunctions::try_init(dll_ptr, &ENGINE_FUNCTIONS) };
+///
+///     if let Err(err) = unsafe { VerifiedFunctions::try_init(dll_ptr, &VERIFIED_FUNCTIONS) } {
+///         log::warn!("unsupported engine.dll: {err}");
+///     }
 /// }
 /// ```
 #[macro_export]
 macro_rules! offset_functions {
-    ( $static_name:ident + $struct_name:ident for $dll:expr => { $($name:ident = $t:ty where offset($addr:literal);)* } ) => {
+    ( $static_name:ident + $struct_name:ident for $dll:expr => $(verify { $($check:ident $(($check_offset:literal))? $(= $expected:literal)?;)* })? { $($name:ident = $t:ty where $kind:ident($($args:tt)*);)* } ) => {
         pub static $static_name: $crate::exports::OnceCell<$struct_name> = $crate::exports::OnceCell::new();
 
         #[doc(hidden)]
@@ -153,27 +184,72 @@ macro_rules! offset_functions {
 
         #[allow(clippy::missing_safety_doc,clippy::useless_transmute)]
         impl $struct_name {
-            pub unsafe fn try_init(dll: &$crate::mid::engine::DLLPointer, static_var: &$crate::exports::OnceCell<Self>) {
+            pub unsafe fn try_init(dll: &$crate::mid::engine::DLLPointer, static_var: &$crate::exports::OnceCell<Self>) -> Result<(), $crate::errors::DllValidationError> {
                 use $crate::mid::engine::WhichDll;
 
                 if &$dll != dll.which_dll() {
-                    return
+                    return Ok(())
+                }
+
+                let checks: &[$crate::mid::engine::DllCheck] = &[$($($crate::offset_functions!(@check $check $(($check_offset))? $(= $expected)?),)*)?];
+                if let Err(err) = unsafe { dll.verify(checks) } {
+                    $crate::exports::log::error!("{} doesn't match the loaded dll: {}", stringify!($struct_name), err);
+                    return Err(err)
                 }
 
                 _ = static_var.set( unsafe {
                     Self {
                         $(
-                            $name: std::mem::transmute(dll.offset( $addr )), // transmute is used since it's easier but a lot more unsafe so yeah
+                            $name: std::mem::transmute($crate::offset_functions!(@address dll, $struct_name, $name, $kind($($args)*))), // transmute is used since it's easier but a lot more unsafe so yeah
                         )*
                     }
                 });
 
+                Ok(())
             }
         }
 
         unsafe impl Sync for $struct_name {}
         unsafe impl Send for $struct_name {}
-    }
+    };
+
+    (@address $dll:ident, $struct_name:ident, $name:ident, offset($addr:literal)) => {
+        $dll.offset( $addr )
+    };
+
+    (@address $dll:ident, $struct_name:ident, $name:ident, pattern($pattern:literal)) => {
+        $crate::offset_functions!(@pattern $dll, $struct_name, $name, $pattern, None)
+    };
+
+    (@address $dll:ident, $struct_name:ident, $name:ident, pattern($pattern:literal, rip($displacement:literal, $instruction_len:literal))) => {
+        $crate::offset_functions!(@pattern $dll, $struct_name, $name, $pattern, Some(($displacement, $instruction_len)))
+    };
+
+    (@pattern $dll:ident, $struct_name:ident, $name:ident, $pattern:literal, $rip:expr) => {
+        match $dll.pattern_address($pattern, $rip) {
+            Ok(address) => address,
+            Err(err) => {
+                $crate::exports::log::error!("failed to find {} of {}: {}", stringify!($name), stringify!($struct_name), err);
+                return Err(err.into())
+            }
+        }
+    };
+
+    (@check timestamp = $expected:literal) => {
+        $crate::mid::engine::DllCheck::Timestamp($expected)
+    };
+
+    (@check size_of_image = $expected:literal) => {
+        $crate::mid::engine::DllCheck::SizeOfImage($expected)
+    };
+
+    (@check signature($offset:literal) = $expected:literal) => {
+        $crate::mid::engine::DllCheck::Signature($offset, $expected)
+    };
+
+    (@check code($offset:literal)) => {
+        $crate::mid::engine::DllCheck::Code($offset)
+    };
 }
 
 /// macro to implement [`SQVMName`]
@@ -201,6 +277,8 @@ macro_rules! impl_sqvm_name {
 mod test {
     #![allow(dead_code)]
 
+    use crate::errors::{DllValidationError, PatternError};
+
     struct Test;
 
     impl_sqvm_name!(Test => "Test");
@@ -216,4 +294,116 @@ mod test {
             client_array = *const crate::bindings::class_types::client::CClient where offset(0xdeadbeef);
         }
     }
+
+    offset_functions! {
+        PATTERN_FUNCTIONS + PatternFunctions for WhichDll::Other("pattern.dll") => {
+            some_function = unsafe extern "C" fn() where pattern("48 8B ?? ?? 00");
+            some_global = *const i32 where pattern("48 8B 05 ?? ?? ?? ?? C3", rip(3, 7));
+            client_array = *const crate::bindings::class_types::client::CClient where offset(0x10);
+        }
+    }
+
+    offset_functions! {
+        VERIFIED_FUNCTIONS + VerifiedFunctions for WhichDll::Other("verified.dll") => verify {
+            timestamp = 0x5E8B1C2D;
+            size_of_image = 0x420;
+            signature(0x400) = "48 8B 05 ?? ?? ?? ?? C3";
+            code(0x400);
+        } {
+            some_function = unsafe extern "C" fn() where offset(0x400);
+        }
+    }
+
+    #[test]
+    fn verified_offset_functions() {
+        use crate::mid::engine::{
+            pe::test::fake_headers, pe::IMAGE_SCN_CNT_CODE, DLLPointer, DllCheck,
+        };
+
+        let mut image = fake_headers(&[(".text", 0x400, 0x20, IMAGE_SCN_CNT_CODE)]);
+        image.resize(0x420, 0xCC);
+        image[0x88..0x8C].copy_from_slice(&0x5E8B1C2Du32.to_le_bytes());
+        image[0x80 + 24 + 56..0x80 + 24 + 60].copy_from_slice(&0x420u32.to_le_bytes());
+        image[0x400..0x408].copy_from_slice(&[0x48, 0x8B, 0x05, 0x10, 0x00, 0x00, 0x00, 0xC3]);
+
+        let dll = DLLPointer::new("verified.dll", image.as_ptr().cast());
+        unsafe { VerifiedFunctions::try_init(&dll, &VERIFIED_FUNCTIONS) }.unwrap();
+        assert_eq!(
+            VERIFIED_FUNCTIONS.get().unwrap().some_function as usize,
+            image.as_ptr() as usize + 0x400
+        );
+
+        // other dlls are skipped
+        let other = DLLPointer::new("other.dll", std::ptr::null());
+        let skipped = crate::exports::OnceCell::new();
+        unsafe { VerifiedFunctions::try_init(&other, &skipped) }.unwrap();
+        assert!(skipped.get().is_none());
+
+        let mut wrong_signature = image.clone();
+        wrong_signature[0x407] = 0x90;
+        let dll = DLLPointer::new("verified.dll", wrong_signature.as_ptr().cast());
+        let mismatched = crate::exports::OnceCell::new();
+        assert_eq!(
+            unsafe { VerifiedFunctions::try_init(&dll, &mismatched) },
+            Err(DllValidationError::SignatureMismatch(0x400))
+        );
+        assert!(mismatched.get().is_none());
+
+        // the headers aren't code
+        let dll = DLLPointer::new("verified.dll", image.as_ptr().cast());
+        assert_eq!(
+            unsafe { dll.verify(&[DllCheck::Code(0x10)]) },
+            Err(DllValidationError::NotCode(0x10))
+        );
+
+        let mut wrong_timestamp = image.clone();
+        wrong_timestamp[0x88] = 0;
+        let dll = DLLPointer::new("verified.dll", wrong_timestamp.as_ptr().cast());
+        assert_eq!(
+            unsafe { VerifiedFunctions::try_init(&dll, &mismatched) },
+            Err(DllValidationError::TimestampMismatch {
+                expected: 0x5E8B1C2D,
+                found: 0x5E8B1C00
+            })
+        );
+    }
+
+    #[test]
+    fn pattern_offset_functions() {
+        use crate::mid::engine::{pe::test::fake_headers, pe::IMAGE_SCN_CNT_CODE, DLLPointer};
+
+        // headers followed by a code section
+        let mut image = fake_headers(&[(".text", 0x400, 0x20, IMAGE_SCN_CNT_CODE)]);
+        image.resize(0x420, 0xCC);
+        image[0x400..0x409]
+            .copy_from_slice(&[0x48, 0x8B, 0x05, 0x10, 0x00, 0x00, 0x00, 0xC3, 0x00]);
+
+        let dll = DLLPointer::new("pattern.dll", image.as_ptr().cast());
+        unsafe { PatternFunctions::try_init(&dll, &PATTERN_FUNCTIONS) }.unwrap();
+
+        let functions = PATTERN_FUNCTIONS.get().unwrap();
+        assert_eq!(
+            functions.some_function as usize,
+            image.as_ptr() as usize + 0x400
+        );
+        assert_eq!(
+            functions.some_global as usize,
+            image.as_ptr() as usize + 0x417
+        );
+        assert_eq!(
+            functions.client_array as usize,
+            image.as_ptr() as usize + 0x10
+        );
+
+        // missing patterns leave it uninitialized
+        let mut image = fake_headers(&[(".text", 0x400, 0, IMAGE_SCN_CNT_CODE)]);
+        image.resize(0x400, 0);
+        let dll = DLLPointer::new("pattern.dll", image.as_ptr().cast());
+        let missing = crate::exports::OnceCell::new();
+        assert!(matches!(
+            unsafe { PatternFunctions::try_init(&dll, &missing) },
+            Err(DllValidationError::Pattern(PatternError::NotFound(_)))
+        ));
+        assert!(missing.get().is_none());
+    }
 }
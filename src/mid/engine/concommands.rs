@@ -7,24 +7,38 @@ use std::{
 use crate::{
     bindings::cvar::{
         command::{
-            CCommand, ConCommand, ConCommandBase, ConCommandConstructorType,
-            COMMAND_COMPLETION_ITEM_LENGTH,
+            CCommand, CCommand_COMMAND_MAX_ARGC, CCommand_COMMAND_MAX_LENGTH, ConCommand,
+            ConCommandBase, ConCommandConstructorType, COMMAND_COMPLETION_ITEM_LENGTH,
         },
         RawCVar,
     },
-    errors::{CVarQueryError, RegisterError},
+    errors::{CVarQueryError, CommandError, RegisterError},
     mid::{source_alloc::SOURCE_ALLOC, utils::try_cstring},
     offset_functions,
 };
 
-use super::get_engine_data;
+use super::{get_engine_data, tracking};
 
 offset_functions! {
-    REGISTER_CONCOMNMADS + RegisterConCommands for WhichDll::Engine => {
+    REGISTER_CONCOMNMADS + RegisterConCommands for WhichDll::Engine => verify {
+        code(0x415F60);
+    } {
         reg_func = ConCommandConstructorType where offset(0x415F60);
     }
 }
 
+offset_functions! {
+    CBUF_FUNCTIONS + CbufFunctions for WhichDll::Engine => verify {
+        code(0x120630);
+        code(0x1203B0);
+        code(0x1204B0);
+    } {
+        cbuf_get_current_player = unsafe extern "C" fn() -> i32 where offset(0x120630);
+        cbuf_add_text = unsafe extern "C" fn(target: i32, text: *const c_char, source: i32) where offset(0x1203B0);
+        cbuf_execute = unsafe extern "C" fn() where offset(0x1204B0);
+    }
+}
+
 impl RegisterConCommands {
     pub(crate) fn mid_register_concommand(
         &self,
@@ -33,8 +47,12 @@ impl RegisterConCommands {
         help_string: &str,
         flags: i32,
     ) -> Result<*mut ConCommand, RegisterError> {
-        // TODO: use IMemAlloc here
+        // checked first so nothing is allocated if it can't be registered
+        let reg_func = self.reg_func.ok_or(RegisterError::NoneFunction)?;
+
         let name = try_cstring(name)?.into_bytes_with_nul();
+        let help_string = try_cstring(help_string)?.into_bytes_with_nul();
+
         let name_ptr =
             unsafe {
                 SOURCE_ALLOC.alloc(Layout::array::<c_char>(name.len()).expect(
@@ -43,7 +61,6 @@ impl RegisterConCommands {
             };
         unsafe { name_ptr.copy_from_nonoverlapping(name.as_ptr(), name.len()) };
 
-        let help_string = try_cstring(help_string)?.into_bytes_with_nul();
         let help_string_ptr =
             unsafe {
                 SOURCE_ALLOC.alloc(Layout::array::<c_char>(help_string.len()).expect(
@@ -54,11 +71,12 @@ impl RegisterConCommands {
             help_string_ptr.copy_from_nonoverlapping(help_string.as_ptr(), help_string.len())
         };
 
+        // freed by tracking::unregister
         let command = unsafe { SOURCE_ALLOC.alloc(std::alloc::Layout::new::<ConCommand>()) }
-            as *mut ConCommand; // TODO: this is not good since if the source allocator decides to drop this concommand bad things will happen
+            as *mut ConCommand;
 
         unsafe {
-            self.reg_func.ok_or(RegisterError::NoneFunction)?(
+            reg_func(
                 command,
                 name_ptr as *const i8,
                 Some(callback),
@@ -67,6 +85,8 @@ impl RegisterConCommands {
                 std::ptr::null_mut(),
             )
         };
+        tracking::track_concommand(command);
+
         Ok(command)
     }
 
@@ -103,6 +123,65 @@ pub unsafe fn add_completion_callback(
     command.m_nCallbackFlags |= 0x3;
 }
 
+/// builds a [`CCommand`] from a command name followed by its args, like the engine does when tokenizing a command
+///
+/// args with whitespace are quoted in the arg string; the [`CCommand`] is boxed since it points into itself
+///
+/// # Errors
+///
+/// this function will return an error if there are more than 64 args, if the command is longer than 512 characters or if any arg contains a null char or a quote
+pub fn build_ccommand(args: &[&str]) -> Result<Box<CCommand>, CommandError> {
+    if args.len() > CCommand_COMMAND_MAX_ARGC as usize {
+        return Err(CommandError::TooManyArgs);
+    }
+    // a quote would end the quoted arg early and split the rest into more args
+    if args.iter().any(|arg| arg.contains('"')) {
+        return Err(CommandError::QuoteInArg);
+    }
+
+    let quoted = args
+        .iter()
+        .map(|arg| {
+            if arg.is_empty() || arg.contains(char::is_whitespace) {
+                format!("\"{arg}\"")
+            } else {
+                arg.to_string()
+            }
+        })
+        .collect::<Vec<String>>();
+    let arg_string = quoted.join(" ");
+    let arg_string = try_cstring(&arg_string)?.into_bytes_with_nul();
+    if arg_string.len() > CCommand_COMMAND_MAX_LENGTH as usize {
+        return Err(CommandError::TooLong);
+    }
+
+    let mut ccommand = Box::new(CCommand {
+        m_nArgc: args.len() as i64,
+        // `ArgS` starts after argv0 as it was written into the arg string so this includes its quotes
+        m_nArgv0Size: quoted.first().map(|arg| arg.len()).unwrap_or_default() as i64,
+        m_pArgSBuffer: [0; CCommand_COMMAND_MAX_LENGTH as usize],
+        m_pArgvBuffer: [0; CCommand_COMMAND_MAX_LENGTH as usize],
+        m_ppArgv: [std::ptr::null(); CCommand_COMMAND_MAX_ARGC as usize],
+    });
+
+    for (c, byte) in ccommand.m_pArgSBuffer.iter_mut().zip(arg_string) {
+        *c = byte as c_char;
+    }
+
+    // each arg is null terminated in the argv buffer; it's never longer than the arg string
+    let mut offset = 0;
+    for (index, arg) in args.iter().enumerate() {
+        for (c, byte) in ccommand.m_pArgvBuffer[offset..].iter_mut().zip(arg.bytes()) {
+            *c = byte as c_char;
+        }
+
+        ccommand.m_ppArgv[index] = ccommand.m_pArgvBuffer[offset..].as_ptr();
+        offset += arg.len() + 1;
+    }
+
+    Ok(ccommand)
+}
+
 /// finds a concommand by name
 ///
 /// # Example
@@ -186,3 +265,65 @@ pub fn find_concommand_base(name: &str) -> Result<&'static mut ConCommandBase, C
             .cvar,
     )
 }
+
+#[cfg(test)]
+mod test {
+    use std::ffi::CStr;
+
+    use super::*;
+    use crate::high::engine::concommands::CCommandResult;
+
+    #[test]
+    fn ccommand_from_args() {
+        let ccommand = build_ccommand(&["kick", "cat_or_not", "the reason"]).unwrap();
+
+        assert_eq!(ccommand.m_nArgc, 3);
+        assert_eq!(ccommand.m_nArgv0Size, 4);
+        assert_eq!(
+            unsafe { CStr::from_ptr(ccommand.m_pArgSBuffer.as_ptr()) }.to_str(),
+            Ok("kick cat_or_not \"the reason\"")
+        );
+        assert_eq!(
+            unsafe { CStr::from_ptr(ccommand.m_ppArgv[2]) }.to_str(),
+            Ok("the reason")
+        );
+
+        let result = unsafe { CCommandResult::new(&*ccommand) };
+        assert_eq!(result.get_command(), "kick");
+        assert_eq!(result.get_arg(0), Some("cat_or_not"));
+
+        assert!(matches!(
+            build_ccommand(&["x"; 65]),
+            Err(CommandError::TooManyArgs)
+        ));
+        assert!(matches!(
+            build_ccommand(&[&"x".repeat(512)]),
+            Err(CommandError::TooLong)
+        ));
+
+        // argv0 is quoted so the rest of the args start after its quotes
+        let ccommand = build_ccommand(&["my command", "the arg"]).unwrap();
+        assert_eq!(ccommand.m_nArgv0Size, 12);
+        assert_eq!(
+            unsafe {
+                CStr::from_ptr(
+                    ccommand
+                        .m_pArgSBuffer
+                        .as_ptr()
+                        .add(ccommand.m_nArgv0Size as usize),
+                )
+            }
+            .to_str(),
+            Ok(" \"the arg\"")
+        );
+        assert_eq!(
+            unsafe { CStr::from_ptr(ccommand.m_ppArgv[0]) }.to_str(),
+            Ok("my command")
+        );
+
+        assert!(matches!(
+            build_ccommand(&["kick", "x\" \"y"]),
+            Err(CommandError::QuoteInArg)
+        ));
+    }
+}
@@ -15,7 +15,10 @@ use crate::{
 };
 
 offset_functions! {
-    CVAR_GLOBALS + CvarGlobals for WhichDll::Engine => {
+    CVAR_GLOBALS + CvarGlobals for WhichDll::Engine => verify {
+        code(0x417230);
+        code(0x415C20);
+    } {
         convar_vtable = *mut c_void where offset(0x67FD28);
         convar_register = ConVarRegisterType where offset(0x417230);
         iconvar_vtable = *mut ConCommandBase where offset(0x67FD28);
@@ -0,0 +1,13 @@
+//! the engine's [`CGlobalVars`]
+//!
+//! only the server's globals are bound; the client's copy still needs an offset into engine.dll or client.dll which hasn't been found yet
+//!
+//! use [`crate::high::engine::globalvars`] for a safe api
+
+use crate::{bindings::class_types::globalvars::CGlobalVars, offset_functions};
+
+offset_functions! {
+    GLOBAL_VARS + GlobalVars for WhichDll::Engine => {
+        server_globals = *mut CGlobalVars where offset(0x7C6F70);
+    }
+}
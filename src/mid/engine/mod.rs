@@ -3,10 +3,21 @@ use once_cell::sync::OnceCell;
 use std::ffi::c_void;
 
 use self::{concommands::RegisterConCommands, convars::CvarGlobals};
-use crate::{bindings::cvar::RawCVar, high::engine::EngineData};
+use crate::{
+    bindings::cvar::RawCVar,
+    errors::{DllValidationError, PatchError, PatternError},
+    high::engine::EngineData,
+    mid::pattern::{resolve_rip_relative, Pattern},
+};
+use patch::MemoryPatch;
+use pe::PeImage;
 
 pub mod concommands;
 pub mod convars;
+pub mod globalvars;
+pub mod patch;
+pub mod pe;
+pub mod tracking;
 
 /// used to create to ConVars and ConComands
 ///
@@ -35,6 +46,8 @@ impl EngineData {
     /// [`RawCVar`] has many many unsafe functions
     /// but the `iterator` function should not be invoked
     /// since the returned `iterator` cannot be dropped by rust's default `allocator` which may produce ub.
+    ///
+    /// use [`crate::high::engine::cvar_iter::cvar_iter`] instead
     pub const fn get_cvar(&self) -> &RawCVar {
         self.cvar
     }
@@ -62,6 +75,19 @@ pub enum WhichDll<'a> {
     Other(&'a str),
 }
 
+/// a check that the loaded dll is the expected version; see [`DLLPointer::verify`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DllCheck {
+    /// the `TimeDateStamp` of the pe file header
+    Timestamp(u32),
+    /// the `SizeOfImage` of the pe optional header
+    SizeOfImage(u32),
+    /// a ida style pattern which has to match the bytes at an offset
+    Signature(usize, &'static str),
+    /// an offset which has to be inside an executable section like a function
+    Code(usize),
+}
+
 /// only holding the current dll's pointer
 pub struct DLLPointer<'a> {
     dll: WhichDll<'a>,
@@ -104,4 +130,216 @@ impl<'a> DLLPointer<'a> {
     pub const unsafe fn offset(&self, offset: isize) -> *const c_void {
         unsafe { self.ptr.offset(offset) }
     }
+
+    /// finds the first match of `pattern` in the code sections of the dll
+    ///
+    /// # Safety
+    ///
+    /// the dll has to be loaded
+    pub unsafe fn find_pattern(&self, pattern: &Pattern) -> Option<*const c_void> {
+        unsafe { self.code_sections() }
+            .into_iter()
+            .find_map(|(offset, code)| {
+                Some(unsafe { self.offset((offset + pattern.find(code)?) as isize) })
+            })
+    }
+
+    /// finds the first match of `pattern` in the code sections of the dll and resolves the rip relative operand of the matched instruction
+    ///
+    /// see [`resolve_rip_relative`] for `displacement` and `instruction_len`
+    ///
+    /// # Safety
+    ///
+    /// the dll has to be loaded
+    pub unsafe fn find_pattern_rip(
+        &self,
+        pattern: &Pattern,
+        displacement: usize,
+        instruction_len: usize,
+    ) -> Option<*const c_void> {
+        unsafe { self.code_sections() }
+            .into_iter()
+            .find_map(|(offset, code)| {
+                let target =
+                    resolve_rip_relative(code, pattern.find(code)?, displacement, instruction_len)?;
+                Some(unsafe { self.offset(offset as isize + target) })
+            })
+    }
+
+    /// parses and finds a pattern; used by [`crate::offset_functions`]
+    ///
+    /// # Safety
+    ///
+    /// the dll has to be loaded
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the pattern is invalid or if it wasn't found
+    #[doc(hidden)]
+    pub unsafe fn pattern_address(
+        &self,
+        pattern: &str,
+        rip: Option<(usize, usize)>,
+    ) -> Result<*const c_void, PatternError> {
+        let parsed = Pattern::new(pattern)?;
+
+        match rip {
+            Some((displacement, instruction_len)) => unsafe {
+                self.find_pattern_rip(&parsed, displacement, instruction_len)
+            },
+            None => unsafe { self.find_pattern(&parsed) },
+        }
+        .ok_or_else(|| PatternError::NotFound(pattern.to_string()))
+    }
+
+    /// checks that the loaded dll passes every check
+    ///
+    /// # Safety
+    ///
+    /// the dll has to be loaded
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error for the first check which failed
+    pub unsafe fn verify(&self, checks: &[DllCheck]) -> Result<(), DllValidationError> {
+        if checks.is_empty() {
+            return Ok(());
+        }
+
+        let pe = unsafe { PeImage::from_base(self.ptr.cast()) }
+            .ok_or(DllValidationError::InvalidHeaders)?;
+
+        for check in checks {
+            match *check {
+                DllCheck::Timestamp(expected) => {
+                    let found = pe.timestamp();
+                    if found != expected {
+                        return Err(DllValidationError::TimestampMismatch { expected, found });
+                    }
+                }
+                DllCheck::SizeOfImage(expected) => {
+                    let found = pe.size_of_image();
+                    if found != expected {
+                        return Err(DllValidationError::SizeMismatch { expected, found });
+                    }
+                }
+                DllCheck::Signature(offset, signature) => {
+                    let pattern = Pattern::new(signature)?;
+                    let bytes = offset
+                        .checked_add(pattern.len())
+                        .and_then(|end| pe.bytes().get(offset..end));
+
+                    if !bytes.is_some_and(|bytes| pattern.matches(bytes)) {
+                        return Err(DllValidationError::SignatureMismatch(offset));
+                    }
+                }
+                DllCheck::Code(offset) => {
+                    let is_code = u32::try_from(offset)
+                        .ok()
+                        .and_then(|rva| pe.section_of(rva))
+                        .is_some_and(|section| section.is_code());
+
+                    if !is_code {
+                        return Err(DllValidationError::NotCode(offset));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// the parsed pe headers of the dll
+    ///
+    /// returns [`None`] if the dll doesn't have valid pe headers
+    ///
+    /// # Safety
+    ///
+    /// the dll has to be loaded
+    pub unsafe fn pe(&self) -> Option<PeImage<'a>> {
+        unsafe { PeImage::from_base(self.ptr.cast()) }
+    }
+
+    /// finds an exported function by name from the export table of the dll without `GetProcAddress`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use rrplug::prelude::*;
+    /// # fn sub(dll_ptr: &DLLPointer) -> Option<()> {
+    /// let create_interface = unsafe { dll_ptr.export("CreateInterface")? };
+    /// # Some(())
+    /// # }
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// the dll has to be loaded
+    pub unsafe fn export(&self, name: &str) -> Option<*const c_void> {
+        Some(unsafe { self.offset(self.pe()?.export(name)? as isize) })
+    }
+
+    /// writes `bytes` at `offset` from the base of the dll if the bytes there match the ida style pattern `expected`
+    ///
+    /// the original bytes are restored when the [`MemoryPatch`] is dropped or when the plugin unloads
+    ///
+    /// # Safety
+    ///
+    /// the dll has to be loaded and nothing can be executing the patched bytes while they are written
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the offset is outside of the dll or if the patch couldn't be applied; see [`MemoryPatch::new`]
+    pub unsafe fn patch(
+        &self,
+        offset: usize,
+        expected: &str,
+        bytes: &[u8],
+    ) -> Result<MemoryPatch, PatchError> {
+        let expected = Pattern::new(expected)?;
+
+        let size_of_image = unsafe { self.pe() }
+            .map(|pe| pe.size_of_image() as usize)
+            .unwrap_or_default();
+        if offset
+            .checked_add(expected.len())
+            .is_none_or(|end| end > size_of_image)
+        {
+            return Err(PatchError::OutOfBounds(offset));
+        }
+
+        unsafe { MemoryPatch::new(self.offset(offset as isize).cast_mut(), &expected, bytes) }
+    }
+
+    /// replaces the bytes matching `expected` at `offset` with `nop`s
+    ///
+    /// # Safety
+    ///
+    /// see [`DLLPointer::patch`]
+    ///
+    /// # Errors
+    ///
+    /// see [`DLLPointer::patch`]
+    pub unsafe fn nop(&self, offset: usize, expected: &str) -> Result<MemoryPatch, PatchError> {
+        let len = Pattern::new(expected)?.len();
+        unsafe { self.patch(offset, expected, &vec![0x90; len]) }
+    }
+
+    /// the code sections of the dll with their offset from the base
+    unsafe fn code_sections(&self) -> Vec<(usize, &'a [u8])> {
+        let Some(pe) = (unsafe { self.pe() }) else {
+            return Vec::new();
+        };
+
+        pe.sections()
+            .into_iter()
+            .filter(pe::Section::is_code)
+            .filter_map(|section| {
+                Some((
+                    section.virtual_address as usize,
+                    pe.section_bytes(&section)?,
+                ))
+            })
+            .collect()
+    }
 }
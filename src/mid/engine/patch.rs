@@ -0,0 +1,241 @@
+//! byte patches which are restored when dropped or when the plugin unloads
+//!
+//! a patch only applies if the bytes it replaces match an expected ida style pattern so a game update can't make it write over the wrong code
+//!
+//! ```no_run
+//! use rrplug::prelude::*;
+//! use rrplug::{exports::OnceCell, mid::engine::patch::MemoryPatch};
+//!
+//! static NOPPED_CALL: OnceCell<MemoryPatch> = OnceCell::new();
+//!
+//! fn on_dll_load(engine_data: Option<&EngineData>, dll_ptr: &DLLPointer) {
+//!     if !matches!(dll_ptr.which_dll(), WhichDll::Engine) {
+//!         return;
+//!     }
+//!
+//!     // nop a call
+//!     match unsafe { dll_ptr.nop(0x1203B0, "E8 ?? ?? ?? ??") } {
+//!         Ok(patch) => _ = NOPPED_CALL.set(patch),
+//!         Err(err) => err.log(),
+//!     }
+//!
+//!     // flip a jz to a jmp only until the end of the scope
+//!     let _patch = unsafe { dll_ptr.patch(0x1204B0, "74 ??", &[0xEB]) };
+//! }
+//! ```
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    ffi::c_void,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{
+    errors::PatchError,
+    high::UnsafeHandle,
+    mid::{memory::write_bytes, pattern::Pattern},
+};
+
+struct AppliedPatch {
+    address: *mut u8,
+    original: Vec<u8>,
+}
+
+/// every applied patch keyed by its id
+static PATCHES: Lazy<Mutex<HashMap<u64, UnsafeHandle<AppliedPatch>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static NEXT_PATCH_ID: AtomicU64 = AtomicU64::new(0);
+
+/// bytes written over some memory
+///
+/// the original bytes are restored when this is dropped; keep it in a static to keep the patch until the plugin unloads
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct MemoryPatch {
+    id: u64,
+    address: usize,
+    len: usize,
+}
+
+impl MemoryPatch {
+    /// writes `bytes` to `address` if the bytes there match `expected`
+    ///
+    /// `bytes` can be shorter than `expected` to check more of the surrounding code than what is replaced
+    ///
+    /// # Safety
+    ///
+    /// `address` has to be valid for the length of `expected` and nothing can be executing the patched bytes while they are written
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the bytes don't match, if `bytes` is longer than `expected`, if the bytes are already patched or if the memory couldn't be written
+    pub unsafe fn new(
+        address: *mut c_void,
+        expected: &Pattern,
+        bytes: &[u8],
+    ) -> Result<Self, PatchError> {
+        let address = address.cast::<u8>();
+        if address.is_null() {
+            return Err(PatchError::NullPointer);
+        }
+
+        if bytes.len() > expected.len() {
+            return Err(PatchError::TooLong {
+                expected: expected.len(),
+                patch: bytes.len(),
+            });
+        }
+
+        let mut patches = PATCHES.lock();
+
+        let start = address as usize;
+        let end = start + bytes.len();
+        if patches.values().any(|patch| {
+            let patch = patch.get();
+            let patch_start = patch.address as usize;
+            start < patch_start + patch.original.len() && patch_start < end
+        }) {
+            return Err(PatchError::AlreadyPatched(start));
+        }
+
+        let current = unsafe { std::slice::from_raw_parts(address, expected.len()) };
+        if !expected.matches(current) {
+            return Err(PatchError::Mismatch(start));
+        }
+
+        let original = current[..bytes.len()].to_vec();
+        unsafe { write_bytes(address, bytes)? };
+
+        let id = NEXT_PATCH_ID.fetch_add(1, Ordering::Relaxed);
+        patches.insert(
+            id,
+            UnsafeHandle::internal_new(AppliedPatch { address, original }),
+        );
+
+        Ok(Self {
+            id,
+            address: start,
+            len: bytes.len(),
+        })
+    }
+
+    /// the patched address
+    pub const fn address(&self) -> *const c_void {
+        self.address as *const c_void
+    }
+
+    /// the amount of patched bytes
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// returns [`true`] if the patch doesn't change any bytes
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// returns [`false`] if the patch was restored by an unload
+    pub fn is_applied(&self) -> bool {
+        PATCHES.lock().contains_key(&self.id)
+    }
+
+    /// restores the original bytes now
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the memory couldn't be written; the patch is forgotten either way
+    pub fn restore(self) -> Result<(), PatchError> {
+        // dropping it afterwards does nothing since it's not tracked anymore
+        self.take_and_restore()
+    }
+
+    fn take_and_restore(&self) -> Result<(), PatchError> {
+        match PATCHES.lock().remove(&self.id) {
+            Some(patch) => unsafe { patch.take().restore() },
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for MemoryPatch {
+    fn drop(&mut self) {
+        if let Err(err) = self.take_and_restore() {
+            err.log()
+        }
+    }
+}
+
+impl AppliedPatch {
+    unsafe fn restore(self) -> Result<(), PatchError> {
+        unsafe { write_bytes(self.address, &self.original) }.map_err(PatchError::from)
+    }
+}
+
+/// restores every patch
+///
+/// called by [`crate::entry`] before the plugin is allowed to unload
+///
+/// # Safety
+///
+/// nothing can be executing the patched bytes
+#[doc(hidden)]
+pub unsafe fn restore_all() {
+    for (_, patch) in PATCHES.lock().drain() {
+        if let Err(err) = unsafe { patch.take().restore() } {
+            err.log()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mid::engine::{pe::test::fake_headers, DLLPointer};
+
+    #[test]
+    fn patch_and_restore() {
+        let mut image = fake_headers(&[(".text", 0x200, 0x100, 0x6000_0020)]);
+        image.resize(0x300, 0xCC);
+        image[0x210..0x215].copy_from_slice(&[0xE8, 0x10, 0x20, 0x30, 0x40]);
+        image[0x220..0x222].copy_from_slice(&[0x74, 0x05]);
+        let original = image.clone();
+
+        // patches are written through this pointer so it has to come from a mutable borrow
+        let dll = DLLPointer::new("engine.dll", image.as_mut_ptr().cast());
+
+        let nop = unsafe { dll.nop(0x210, "E8 ?? ?? ?? ??") }.unwrap();
+        assert_eq!(image[0x210..0x215], [0x90; 5]);
+        assert_eq!(nop.len(), 5);
+        assert!(nop.is_applied());
+
+        let jmp = unsafe { dll.patch(0x220, "74 ?? CC", &[0xEB]) }.unwrap();
+        assert_eq!(image[0x220..0x222], [0xEB, 0x05]);
+
+        assert_eq!(
+            unsafe { dll.patch(0x212, "90 90", &[0, 0]) },
+            Err(PatchError::AlreadyPatched(image.as_ptr() as usize + 0x212))
+        );
+        assert_eq!(
+            unsafe { dll.patch(0x230, "74", &[0xEB]) },
+            Err(PatchError::Mismatch(image.as_ptr() as usize + 0x230))
+        );
+        assert_eq!(
+            unsafe { dll.patch(0x230, "CC", &[0x90, 0x90]) },
+            Err(PatchError::TooLong {
+                expected: 1,
+                patch: 2
+            })
+        );
+        assert_eq!(
+            unsafe { dll.patch(0x2FF, "CC CC", &[0x90]) },
+            Err(PatchError::OutOfBounds(0x2FF))
+        );
+
+        drop(jmp);
+        assert_eq!(image[0x220..0x222], [0x74, 0x05]);
+
+        nop.restore().unwrap();
+        assert_eq!(image, original);
+    }
+}
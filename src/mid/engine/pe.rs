@@ -0,0 +1,706 @@
+//! parsing of pe images (dlls)
+//!
+//! [`PeImage`] works on loaded dlls (see [`crate::mid::engine::DLLPointer::pe`]) and on pe files read from disk
+//!
+//! ```no_run
+//! use rrplug::mid::engine::pe::PeImage;
+//!
+//! let file = std::fs::read("bin/x64_retail/engine.dll").unwrap();
+//! let pe = PeImage::from_file(&file).unwrap();
+//!
+//! log::info!("linked at {:#X}", pe.timestamp());
+//! for export in pe.exports() {
+//!     log::info!("{:?} at {:#X}", export.name, export.rva);
+//! }
+//! ```
+
+use std::ffi::CStr;
+
+/// `IMAGE_SCN_CNT_CODE`
+pub const IMAGE_SCN_CNT_CODE: u32 = 0x20;
+/// `IMAGE_SCN_MEM_EXECUTE`
+pub const IMAGE_SCN_MEM_EXECUTE: u32 = 0x20000000;
+
+const DOS_MAGIC: u16 = 0x5A4D; // MZ
+const NT_SIGNATURE: u32 = 0x4550; // PE\0\0
+const PE32_MAGIC: u16 = 0x10B;
+const PE32_PLUS_MAGIC: u16 = 0x20B;
+const FILE_HEADER_SIZE: usize = 20;
+const SECTION_HEADER_SIZE: usize = 40;
+const EXPORT_DIRECTORY: usize = 0;
+const IMPORT_DIRECTORY: usize = 1;
+
+/// how the bytes of a [`PeImage`] are laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PeLayout {
+    /// mapped by the loader; rvas are offsets into the bytes
+    Image,
+    /// read from disk; rvas have to be mapped to file offsets with the section table
+    File,
+}
+
+/// a section from the section table of a dll
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Section {
+    /// the name like `.text`
+    pub name: String,
+    /// the offset of the section from the base of the dll
+    pub virtual_address: u32,
+    /// the size of the section in memory
+    pub virtual_size: u32,
+    /// the offset of the section in the file
+    pub pointer_to_raw_data: u32,
+    /// the size of the section in the file
+    pub size_of_raw_data: u32,
+    /// the `IMAGE_SCN_*` flags of the section
+    pub characteristics: u32,
+}
+
+impl Section {
+    /// returns [`true`] if the section contains code
+    pub const fn is_code(&self) -> bool {
+        self.characteristics & (IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE) != 0
+    }
+
+    /// returns [`true`] if the rva is inside of the section
+    pub const fn contains(&self, rva: u32) -> bool {
+        let size = if self.virtual_size > self.size_of_raw_data {
+            self.virtual_size
+        } else {
+            self.size_of_raw_data
+        };
+
+        rva >= self.virtual_address && rva - self.virtual_address < size
+    }
+}
+
+/// a function exported by a dll
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Export {
+    /// the name; exports can be ordinal only
+    pub name: Option<String>,
+    /// the ordinal
+    pub ordinal: u32,
+    /// the rva of the function
+    pub rva: u32,
+    /// the `dll.function` this export is forwarded to; the rva points to this string instead of code
+    pub forwarder: Option<String>,
+}
+
+/// a function imported from another dll
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ImportedFunction {
+    /// imported by name with a hint into the export name table
+    Name {
+        /// the name of the function
+        name: String,
+        /// the hint
+        hint: u16,
+    },
+    /// imported by ordinal
+    Ordinal(u16),
+}
+
+/// an import of a function
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Import {
+    /// the dll the function is imported from
+    pub dll: String,
+    /// the function
+    pub function: ImportedFunction,
+    /// the rva of the slot in the import address table the loader writes the address of the function to
+    pub iat_rva: u32,
+}
+
+/// a parsed pe image
+#[derive(Debug, Clone, Copy)]
+pub struct PeImage<'a> {
+    bytes: &'a [u8],
+    layout: PeLayout,
+    nt: usize,
+    is_pe32_plus: bool,
+}
+
+impl<'a> PeImage<'a> {
+    /// parses a dll mapped by the loader
+    ///
+    /// returns [`None`] if it doesn't have valid pe headers
+    pub fn from_image(bytes: &'a [u8]) -> Option<Self> {
+        Self::parse(bytes, PeLayout::Image)
+    }
+
+    /// parses a pe file read from disk
+    ///
+    /// returns [`None`] if it doesn't have valid pe headers
+    pub fn from_file(bytes: &'a [u8]) -> Option<Self> {
+        Self::parse(bytes, PeLayout::File)
+    }
+
+    /// parses a loaded dll from its base address
+    ///
+    /// # Safety
+    ///
+    /// `base` has to point to a loaded dll which stays loaded for `'a`
+    pub unsafe fn from_base(base: *const u8) -> Option<Self> {
+        if base.is_null() {
+            return None;
+        }
+
+        // the headers have to be read first to know how big the image is
+        let dos = unsafe { std::slice::from_raw_parts(base, 0x40) };
+        let nt = read_u32(dos, 0x3C)? as usize;
+        let headers = unsafe { std::slice::from_raw_parts(base, nt + 4 + FILE_HEADER_SIZE + 64) };
+        let size_of_image = Self::parse(headers, PeLayout::Image)?.size_of_image() as usize;
+
+        Self::from_image(unsafe { std::slice::from_raw_parts(base, size_of_image) })
+    }
+
+    fn parse(bytes: &'a [u8], layout: PeLayout) -> Option<Self> {
+        if read_u16(bytes, 0)? != DOS_MAGIC {
+            return None;
+        }
+
+        let nt = read_u32(bytes, 0x3C)? as usize;
+        if read_u32(bytes, nt)? != NT_SIGNATURE {
+            return None;
+        }
+
+        let is_pe32_plus = match read_u16(bytes, nt + 4 + FILE_HEADER_SIZE)? {
+            PE32_PLUS_MAGIC => true,
+            PE32_MAGIC => false,
+            _ => return None,
+        };
+
+        // SizeOfHeaders has to be readable
+        read_u32(bytes, nt + 4 + FILE_HEADER_SIZE + 60)?;
+
+        Some(Self {
+            bytes,
+            layout,
+            nt,
+            is_pe32_plus,
+        })
+    }
+
+    /// the raw bytes
+    pub const fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// the layout of the bytes
+    pub const fn layout(&self) -> PeLayout {
+        self.layout
+    }
+
+    /// returns [`true`] for 64 bit images
+    pub const fn is_pe32_plus(&self) -> bool {
+        self.is_pe32_plus
+    }
+
+    const fn file_header(&self) -> usize {
+        self.nt + 4
+    }
+
+    const fn optional_header(&self) -> usize {
+        self.nt + 4 + FILE_HEADER_SIZE
+    }
+
+    /// the `TimeDateStamp` from the file header; usually the time the dll was linked
+    pub fn timestamp(&self) -> u32 {
+        read_u32(self.bytes, self.file_header() + 4).unwrap_or_default()
+    }
+
+    /// the size of the image once loaded
+    pub fn size_of_image(&self) -> u32 {
+        // same offset for pe32 and pe32+
+        read_u32(self.bytes, self.optional_header() + 56).unwrap_or_default()
+    }
+
+    /// the size of every header of the image
+    pub fn size_of_headers(&self) -> u32 {
+        read_u32(self.bytes, self.optional_header() + 60).unwrap_or_default()
+    }
+
+    /// the sections from the section table
+    pub fn sections(&self) -> Vec<Section> {
+        let file_header = self.file_header();
+        let number_of_sections = read_u16(self.bytes, file_header + 2).unwrap_or_default();
+        let size_of_optional_header =
+            read_u16(self.bytes, file_header + 16).unwrap_or_default() as usize;
+        let section_table = file_header + FILE_HEADER_SIZE + size_of_optional_header;
+
+        (0..number_of_sections as usize)
+            .map_while(|index| {
+                let section = section_table + index * SECTION_HEADER_SIZE;
+                let name = self.bytes.get(section..section + 8)?;
+
+                Some(Section {
+                    name: String::from_utf8_lossy(name)
+                        .trim_end_matches('\0')
+                        .to_string(),
+                    virtual_size: read_u32(self.bytes, section + 8)?,
+                    virtual_address: read_u32(self.bytes, section + 12)?,
+                    size_of_raw_data: read_u32(self.bytes, section + 16)?,
+                    pointer_to_raw_data: read_u32(self.bytes, section + 20)?,
+                    characteristics: read_u32(self.bytes, section + 36)?,
+                })
+            })
+            .collect()
+    }
+
+    /// the section which contains the rva
+    pub fn section_of(&self, rva: u32) -> Option<Section> {
+        self.sections()
+            .into_iter()
+            .find(|section| section.contains(rva))
+    }
+
+    /// the bytes of a section
+    pub fn section_bytes(&self, section: &Section) -> Option<&'a [u8]> {
+        let (start, size) = match self.layout {
+            PeLayout::Image => (section.virtual_address, section.virtual_size),
+            PeLayout::File => (section.pointer_to_raw_data, section.size_of_raw_data),
+        };
+
+        self.bytes
+            .get(start as usize..start.checked_add(size)? as usize)
+    }
+
+    /// maps a rva to an offset into the bytes
+    pub fn rva_to_offset(&self, rva: u32) -> Option<usize> {
+        let offset = match self.layout {
+            PeLayout::Image => rva as usize,
+            PeLayout::File if rva < self.size_of_headers() => rva as usize,
+            PeLayout::File => {
+                let section = self.section_of(rva)?;
+                (rva - section.virtual_address).checked_add(section.pointer_to_raw_data)? as usize
+            }
+        };
+
+        (offset < self.bytes.len()).then_some(offset)
+    }
+
+    /// the rva and size of a data directory
+    pub fn data_directory(&self, index: usize) -> Option<(u32, u32)> {
+        let (count, directories) = if self.is_pe32_plus {
+            (self.optional_header() + 108, self.optional_header() + 112)
+        } else {
+            (self.optional_header() + 92, self.optional_header() + 96)
+        };
+
+        if index >= read_u32(self.bytes, count)? as usize {
+            return None;
+        }
+
+        let directory = directories + index * 8;
+        let rva = read_u32(self.bytes, directory)?;
+        let size = read_u32(self.bytes, directory + 4)?;
+
+        (rva != 0 && size != 0).then_some((rva, size))
+    }
+
+    /// every exported function
+    pub fn exports(&self) -> Vec<Export> {
+        self.parse_exports().unwrap_or_default()
+    }
+
+    /// the rva of an exported function by name
+    ///
+    /// forwarded exports are skipped since they aren't in this dll
+    pub fn export(&self, name: &str) -> Option<u32> {
+        self.exports()
+            .into_iter()
+            .find(|export| export.forwarder.is_none() && export.name.as_deref() == Some(name))
+            .map(|export| export.rva)
+    }
+
+    fn parse_exports(&self) -> Option<Vec<Export>> {
+        let (directory_rva, directory_size) = self.data_directory(EXPORT_DIRECTORY)?;
+        let directory = self.rva_to_offset(directory_rva)?;
+
+        let base = read_u32(self.bytes, directory + 16)?;
+        let number_of_functions = read_u32(self.bytes, directory + 20)?;
+        let number_of_names = read_u32(self.bytes, directory + 24)?;
+        let functions = self.rva_to_offset(read_u32(self.bytes, directory + 28)?)?;
+        let names = self.rva_to_offset(read_u32(self.bytes, directory + 32)?);
+        let ordinals = self.rva_to_offset(read_u32(self.bytes, directory + 36)?);
+
+        // the tables live inside the export directory so its size bounds the number of entries
+        let fits = |table: usize, count: u32, entry_size: usize| {
+            count as usize <= directory_size as usize / 4
+                && table
+                    .checked_add(count as usize * entry_size)
+                    .is_some_and(|end| end <= self.bytes.len())
+        };
+        if !fits(functions, number_of_functions, 4)
+            || names.is_some_and(|names| !fits(names, number_of_names, 4))
+            || ordinals.is_some_and(|ordinals| !fits(ordinals, number_of_names, 2))
+        {
+            return None;
+        }
+
+        // names point into the function table through the ordinal table
+        let mut function_names = vec![None; number_of_functions as usize];
+        if let (Some(names), Some(ordinals)) = (names, ordinals) {
+            for index in 0..number_of_names as usize {
+                let name =
+                    read_u32(self.bytes, names + index * 4).and_then(|rva| self.read_cstr(rva));
+                let ordinal = read_u16(self.bytes, ordinals + index * 2);
+
+                if let (Some(name), Some(ordinal)) = (name, ordinal) {
+                    if let Some(function_name) = function_names.get_mut(ordinal as usize) {
+                        *function_name = Some(name);
+                    }
+                }
+            }
+        }
+
+        Some(
+            function_names
+                .into_iter()
+                .enumerate()
+                .filter_map(|(index, name)| {
+                    let rva = read_u32(self.bytes, functions + index * 4)?;
+                    if rva == 0 {
+                        return None;
+                    }
+
+                    let is_forwarded = rva >= directory_rva && rva - directory_rva < directory_size;
+
+                    Some(Export {
+                        name,
+                        ordinal: base + index as u32,
+                        rva,
+                        forwarder: is_forwarded.then(|| self.read_cstr(rva)).flatten(),
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// every imported function
+    pub fn imports(&self) -> Vec<Import> {
+        self.parse_imports().unwrap_or_default()
+    }
+
+    fn parse_imports(&self) -> Option<Vec<Import>> {
+        const DESCRIPTOR_SIZE: usize = 20;
+
+        let (directory_rva, _) = self.data_directory(IMPORT_DIRECTORY)?;
+        let directory = self.rva_to_offset(directory_rva)?;
+        let thunk_size = if self.is_pe32_plus { 8 } else { 4 };
+        let ordinal_flag = if self.is_pe32_plus { 1 << 63 } else { 1 << 31 };
+
+        let mut imports = Vec::new();
+        for descriptor in (directory..).step_by(DESCRIPTOR_SIZE) {
+            // a truncated table ends the imports but keeps the ones already read
+            let (Some(original_first_thunk), Some(name), Some(first_thunk)) = (
+                read_u32(self.bytes, descriptor),
+                read_u32(self.bytes, descriptor + 12),
+                read_u32(self.bytes, descriptor + 16),
+            ) else {
+                break;
+            };
+
+            if name == 0 && first_thunk == 0 {
+                break;
+            }
+
+            let Some(dll) = self.read_cstr(name) else {
+                continue;
+            };
+
+            // the loader overwrites the first thunks with addresses so the original ones are used when they exist
+            let lookup = if original_first_thunk != 0 {
+                original_first_thunk
+            } else {
+                first_thunk
+            };
+            let Some(lookup) = self.rva_to_offset(lookup) else {
+                continue;
+            };
+
+            for index in 0.. {
+                let thunk = match self.read_thunk(lookup + index * thunk_size) {
+                    Some(0) | None => break,
+                    Some(thunk) => thunk,
+                };
+
+                let function = if thunk & ordinal_flag != 0 {
+                    ImportedFunction::Ordinal(thunk as u16)
+                } else {
+                    let Some(hint_name) = self.rva_to_offset(thunk as u32) else {
+                        continue;
+                    };
+
+                    ImportedFunction::Name {
+                        hint: read_u16(self.bytes, hint_name).unwrap_or_default(),
+                        name: (thunk as u32)
+                            .checked_add(2)
+                            .and_then(|rva| self.read_cstr(rva))
+                            .unwrap_or_default(),
+                    }
+                };
+
+                imports.push(Import {
+                    dll: dll.clone(),
+                    function,
+                    iat_rva: first_thunk.wrapping_add((index * thunk_size) as u32),
+                });
+            }
+        }
+
+        Some(imports)
+    }
+
+    fn read_thunk(&self, offset: usize) -> Option<u64> {
+        if self.is_pe32_plus {
+            Some(u64::from_le_bytes(
+                self.bytes.get(offset..offset + 8)?.try_into().ok()?,
+            ))
+        } else {
+            read_u32(self.bytes, offset).map(u64::from)
+        }
+    }
+
+    fn read_cstr(&self, rva: u32) -> Option<String> {
+        let bytes = self.bytes.get(self.rva_to_offset(rva)?..)?;
+        Some(
+            CStr::from_bytes_until_nul(bytes)
+                .ok()?
+                .to_string_lossy()
+                .to_string(),
+        )
+    }
+}
+
+pub(crate) fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(offset..offset.checked_add(2)?)?.try_into().ok()?,
+    ))
+}
+
+pub(crate) fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset.checked_add(4)?)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    const NT: usize = 0x80;
+    const OPTIONAL_HEADER: usize = NT + 4 + FILE_HEADER_SIZE;
+    const OPTIONAL_HEADER_SIZE: usize = 0xF0;
+
+    fn write_u16(bytes: &mut [u8], offset: usize, value: u16) {
+        bytes[offset..offset + 2].copy_from_slice(&value.to_le_bytes())
+    }
+
+    fn write_u32(bytes: &mut [u8], offset: usize, value: u32) {
+        bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes())
+    }
+
+    /// builds pe32+ headers with the given sections as `(name, virtual_address, virtual_size, characteristics)`
+    ///
+    /// the raw data of each section starts at its virtual address
+    pub(crate) fn fake_headers(sections: &[(&str, u32, u32, u32)]) -> Vec<u8> {
+        let section_table = OPTIONAL_HEADER + OPTIONAL_HEADER_SIZE;
+        let size = section_table + sections.len() * SECTION_HEADER_SIZE;
+        let size_of_image = sections
+            .iter()
+            .map(|(_, virtual_address, virtual_size, _)| virtual_address + virtual_size)
+            .max()
+            .unwrap_or_default()
+            .max(size as u32);
+        let mut headers = vec![0; size];
+
+        write_u16(&mut headers, 0, DOS_MAGIC);
+        write_u32(&mut headers, 0x3C, NT as u32);
+        write_u32(&mut headers, NT, NT_SIGNATURE);
+        write_u16(&mut headers, NT + 6, sections.len() as u16);
+        write_u16(&mut headers, NT + 20, OPTIONAL_HEADER_SIZE as u16);
+        write_u16(&mut headers, OPTIONAL_HEADER, PE32_PLUS_MAGIC);
+        write_u32(&mut headers, OPTIONAL_HEADER + 56, size_of_image);
+        write_u32(&mut headers, OPTIONAL_HEADER + 60, size as u32);
+        write_u32(&mut headers, OPTIONAL_HEADER + 108, 16);
+
+        for (index, (name, virtual_address, virtual_size, characteristics)) in
+            sections.iter().enumerate()
+        {
+            let section = section_table + index * SECTION_HEADER_SIZE;
+            headers[section..section + name.len()].copy_from_slice(name.as_bytes());
+            write_u32(&mut headers, section + 8, *virtual_size);
+            write_u32(&mut headers, section + 12, *virtual_address);
+            write_u32(&mut headers, section + 16, *virtual_size);
+            write_u32(&mut headers, section + 20, *virtual_address);
+            write_u32(&mut headers, section + 36, *characteristics);
+        }
+
+        headers
+    }
+
+    /// a pe file where `.rdata` is at rva 0x2000 but at 0x400 in the file
+    ///
+    /// exports `CreateInterface` (0x1010), ordinal 2 without a name (0x1020) and `Forwarded` (to `other.Function`)
+    /// and imports `LoadLibraryA` and ordinal 7 from kernel32.dll
+    fn fake_file() -> Vec<u8> {
+        const RDATA_RVA: u32 = 0x2000;
+        const RDATA_RAW: u32 = 0x400;
+
+        let mut file = fake_headers(&[
+            (".text", 0x1000, 0x100, IMAGE_SCN_CNT_CODE),
+            (".rdata", RDATA_RVA, 0x200, 0x40000040),
+        ]);
+        write_u32(&mut file, NT + 8, 0x5E8B1C2D);
+        // .text has no raw data
+        let text_header = OPTIONAL_HEADER + OPTIONAL_HEADER_SIZE;
+        write_u32(&mut file, text_header + 16, 0);
+        write_u32(&mut file, text_header + 20, 0);
+        let rdata_header = OPTIONAL_HEADER + OPTIONAL_HEADER_SIZE + SECTION_HEADER_SIZE;
+        write_u32(&mut file, rdata_header + 20, RDATA_RAW);
+        file.resize((RDATA_RAW + 0x200) as usize, 0);
+
+        let rva = |offset: u32| RDATA_RVA + offset;
+        let raw = |offset: u32| (RDATA_RAW + offset) as usize;
+
+        // export directory at the start of .rdata
+        write_u32(&mut file, OPTIONAL_HEADER + 112, rva(0));
+        write_u32(&mut file, OPTIONAL_HEADER + 116, 0x100);
+        write_u32(&mut file, raw(16), 1); // ordinal base
+        write_u32(&mut file, raw(20), 3); // functions
+        write_u32(&mut file, raw(24), 2); // names
+        write_u32(&mut file, raw(28), rva(0x40));
+        write_u32(&mut file, raw(32), rva(0x50));
+        write_u32(&mut file, raw(36), rva(0x60));
+        write_u32(&mut file, raw(0x40), 0x1010);
+        write_u32(&mut file, raw(0x44), 0x1020);
+        write_u32(&mut file, raw(0x48), rva(0x90));
+        write_u32(&mut file, raw(0x50), rva(0x70));
+        write_u32(&mut file, raw(0x54), rva(0x80));
+        write_u16(&mut file, raw(0x60), 0);
+        write_u16(&mut file, raw(0x62), 2);
+        file[raw(0x70)..raw(0x70) + 16].copy_from_slice(b"CreateInterface\0");
+        file[raw(0x80)..raw(0x80) + 10].copy_from_slice(b"Forwarded\0");
+        file[raw(0x90)..raw(0x90) + 15].copy_from_slice(b"other.Function\0");
+
+        // import directory after it
+        write_u32(&mut file, OPTIONAL_HEADER + 120, rva(0x100));
+        write_u32(&mut file, OPTIONAL_HEADER + 124, 40);
+        write_u32(&mut file, raw(0x100), rva(0x140)); // original first thunk
+        write_u32(&mut file, raw(0x10C), rva(0x180)); // name
+        write_u32(&mut file, raw(0x110), rva(0x160)); // first thunk
+        file[raw(0x140)..raw(0x140) + 8].copy_from_slice(&(rva(0x190) as u64).to_le_bytes());
+        file[raw(0x148)..raw(0x148) + 8].copy_from_slice(&((1u64 << 63) | 7).to_le_bytes());
+        file[raw(0x180)..raw(0x180) + 13].copy_from_slice(b"kernel32.dll\0");
+        write_u16(&mut file, raw(0x190), 3);
+        file[raw(0x192)..raw(0x192) + 13].copy_from_slice(b"LoadLibraryA\0");
+
+        file
+    }
+
+    #[test]
+    fn sections_from_headers() {
+        let headers = fake_headers(&[
+            (
+                ".text",
+                0x1000,
+                0x200,
+                IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE,
+            ),
+            (".data", 0x2000, 0x100, 0xC0000040),
+        ]);
+        let pe = PeImage::from_image(&headers).unwrap();
+
+        assert_eq!(pe.size_of_headers(), headers.len() as u32);
+        assert_eq!(pe.size_of_image(), 0x2100);
+        assert!(pe.is_pe32_plus());
+
+        let sections = pe.sections();
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].name, ".text");
+        assert_eq!(sections[0].virtual_address, 0x1000);
+        assert!(sections[0].is_code());
+        assert_eq!(sections[1].name, ".data");
+        assert!(!sections[1].is_code());
+        assert_eq!(pe.section_of(0x2080), Some(sections[1].clone()));
+        assert_eq!(pe.section_of(0x3000), None);
+
+        assert!(PeImage::from_image(&headers[..0x40]).is_none());
+        assert!(PeImage::from_image(&[0; 0x100]).is_none());
+    }
+
+    #[test]
+    fn exports_and_imports_from_file() {
+        let file = fake_file();
+        let pe = PeImage::from_file(&file).unwrap();
+
+        assert_eq!(pe.timestamp(), 0x5E8B1C2D);
+        assert_eq!(pe.rva_to_offset(0x2010), Some(0x410));
+
+        let exports = pe.exports();
+        assert_eq!(exports.len(), 3);
+        assert_eq!(exports[0].name.as_deref(), Some("CreateInterface"));
+        assert_eq!(exports[0].ordinal, 1);
+        assert_eq!(exports[1].name, None);
+        assert_eq!(exports[1].ordinal, 2);
+        assert_eq!(exports[2].forwarder.as_deref(), Some("other.Function"));
+        assert_eq!(pe.export("CreateInterface"), Some(0x1010));
+        assert_eq!(pe.export("Forwarded"), None);
+        assert_eq!(pe.export("Missing"), None);
+
+        assert_eq!(
+            pe.imports(),
+            [
+                Import {
+                    dll: "kernel32.dll".to_string(),
+                    function: ImportedFunction::Name {
+                        name: "LoadLibraryA".to_string(),
+                        hint: 3
+                    },
+                    iat_rva: 0x2160,
+                },
+                Import {
+                    dll: "kernel32.dll".to_string(),
+                    function: ImportedFunction::Ordinal(7),
+                    iat_rva: 0x2168,
+                },
+            ]
+        );
+
+        // the same file mapped like the loader would
+        let mut image = vec![0; pe.size_of_image() as usize];
+        let headers = pe.size_of_headers() as usize;
+        image[..headers].copy_from_slice(&file[..headers]);
+        for section in pe.sections() {
+            let bytes = pe.section_bytes(&section).unwrap();
+            let start = section.virtual_address as usize;
+            image[start..start + bytes.len()].copy_from_slice(bytes);
+        }
+
+        let mapped = unsafe { PeImage::from_base(image.as_ptr()) }.unwrap();
+        assert_eq!(mapped.layout(), PeLayout::Image);
+        assert_eq!(mapped.exports(), pe.exports());
+        assert_eq!(mapped.imports(), pe.imports());
+    }
+
+    #[test]
+    fn corrupt_export_count() {
+        let mut file = fake_file();
+        // the function count of the export directory at the start of .rdata
+        write_u32(&mut file, 0x400 + 20, u32::MAX);
+        let pe = PeImage::from_file(&file).unwrap();
+
+        assert!(pe.exports().is_empty());
+        assert_eq!(pe.imports().len(), 2);
+
+        // the name count right after it
+        let mut file = fake_file();
+        write_u32(&mut file, 0x400 + 24, u32::MAX);
+        let pe = PeImage::from_file(&file).unwrap();
+
+        assert!(pe.exports().is_empty());
+        assert_eq!(pe.imports().len(), 2);
+    }
+}
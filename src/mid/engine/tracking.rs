@@ -0,0 +1,161 @@
+//! tracks every convar and concommand registered by rrplug so they can be removed before the plugin unloads
+
+use parking_lot::Mutex;
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    ffi::c_char,
+};
+
+use super::get_engine_data;
+use crate::{
+    bindings::cvar::{command::ConCommand, command::ConCommandBase, convar::ConVar},
+    errors::CVarQueryError,
+    high::UnsafeHandle,
+    mid::source_alloc::SOURCE_ALLOC,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrackedCVar {
+    ConVar(*mut ConVar),
+    ConCommand(*mut ConCommand),
+}
+
+impl TrackedCVar {
+    const fn base(self) -> *mut ConCommandBase {
+        // ConCommandBase is the first field of both
+        match self {
+            Self::ConVar(convar) => convar.cast(),
+            Self::ConCommand(command) => command.cast(),
+        }
+    }
+}
+
+static TRACKED_CVARS: Mutex<Vec<UnsafeHandle<TrackedCVar>>> = Mutex::new(Vec::new());
+
+pub(crate) fn track_convar(convar: *mut ConVar) {
+    TRACKED_CVARS
+        .lock()
+        .push(UnsafeHandle::internal_new(TrackedCVar::ConVar(convar)))
+}
+
+pub(crate) fn track_concommand(command: *mut ConCommand) {
+    TRACKED_CVARS
+        .lock()
+        .push(UnsafeHandle::internal_new(TrackedCVar::ConCommand(command)))
+}
+
+/// returns [`true`] if the convar or concommand was registered by rrplug and wasn't unregistered yet
+pub fn is_tracked(base: *const ConCommandBase) -> bool {
+    TRACKED_CVARS
+        .lock()
+        .iter()
+        .any(|tracked| std::ptr::eq(tracked.copy().base(), base))
+}
+
+/// the amount of convars and concommands registered by rrplug which weren't unregistered yet
+pub fn tracked_count() -> usize {
+    TRACKED_CVARS.lock().len()
+}
+
+/// unregisters a convar or concommand registered by rrplug and frees everything rrplug allocated for it
+///
+/// # Safety
+///
+/// the convar or concommand cannot be used after this
+pub(crate) unsafe fn unregister(base: *const ConCommandBase) -> Result<(), CVarQueryError> {
+    get_engine_data().ok_or(CVarQueryError::NoCVarInterface)?;
+
+    let tracked = {
+        let mut tracked_cvars = TRACKED_CVARS.lock();
+        let index = tracked_cvars
+            .iter()
+            .position(|tracked| std::ptr::eq(tracked.copy().base(), base))
+            .ok_or(CVarQueryError::NotRegisteredByPlugin)?;
+
+        tracked_cvars.swap_remove(index).copy()
+    };
+
+    unsafe { unregister_tracked(tracked) };
+    Ok(())
+}
+
+/// unregisters every convar and concommand registered by rrplug
+///
+/// # Safety
+///
+/// none of the convars or concommands can be used after this
+pub(crate) unsafe fn unregister_all() {
+    if get_engine_data().is_none() {
+        return;
+    }
+
+    let tracked_cvars = std::mem::take(&mut *TRACKED_CVARS.lock());
+    log::info!(
+        "unregistering {} convars and concommands",
+        tracked_cvars.len()
+    );
+
+    for tracked in tracked_cvars {
+        unsafe { unregister_tracked(tracked.copy()) }
+    }
+}
+
+unsafe fn unregister_tracked(tracked: TrackedCVar) {
+    let Some(engine_data) = get_engine_data() else {
+        return;
+    };
+
+    unsafe {
+        let base = tracked.base();
+        engine_data.get_cvar().unregister_concommand_base(base);
+
+        free_cstr((*base).m_pszName);
+        free_cstr((*base).m_pszHelpString);
+
+        match tracked {
+            TrackedCVar::ConVar(convar) => {
+                // the value string was allocated by the engine when the value was set
+                free_cstr((*convar).m_Value.m_pszString);
+                free_cstr((*convar).m_pszDefaultValue);
+                SOURCE_ALLOC.dealloc(convar.cast(), Layout::new::<ConVar>());
+            }
+            TrackedCVar::ConCommand(command) => {
+                SOURCE_ALLOC.dealloc(command.cast(), Layout::new::<ConCommand>())
+            }
+        }
+    }
+}
+
+unsafe fn free_cstr(ptr: *const c_char) {
+    if !ptr.is_null() {
+        unsafe { SOURCE_ALLOC.dealloc(ptr as *mut u8, Layout::new::<c_char>()) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::mem::MaybeUninit;
+
+    use super::*;
+
+    #[test]
+    fn tracking_without_engine() {
+        let mut command = unsafe { MaybeUninit::<ConCommand>::zeroed().assume_init() };
+        let command_ptr: *mut ConCommand = &mut command;
+
+        track_concommand(command_ptr);
+        assert!(is_tracked(command_ptr.cast()));
+
+        // nothing can be unregistered without the engine
+        assert!(matches!(
+            unsafe { unregister(command_ptr.cast()) },
+            Err(CVarQueryError::NoCVarInterface)
+        ));
+        assert!(is_tracked(command_ptr.cast()));
+
+        TRACKED_CVARS
+            .lock()
+            .retain(|tracked| !std::ptr::eq(tracked.copy().base(), command_ptr.cast()));
+        assert!(!is_tracked(command_ptr.cast()));
+    }
+}
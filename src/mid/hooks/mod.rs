@@ -0,0 +1,521 @@
+//! vtable and inline function hooks
+//!
+//! [`Hook`] either swaps a slot of a vtable (works for [`crate::bindings::cvar::RawCVar`], [`crate::bindings::class_types::cplayer::CPlayer`] vmethods and any other interface)
+//! or overwrites the start of a function with a jump to the detour; the overwritten instructions are moved to a trampoline which is what [`Hook::original`] calls.
+//!
+//! hooks start disabled and are toggled with [`Hook::enable`] and [`Hook::disable`] which have to be called while the hooked function can't run (usually from `on_dll_load`). every hook still in place is removed when the plugin unloads.
+//!
+//! functions found with [`crate::offset_functions`] can be hooked directly
+//!
+//! ```no_run
+//! use rrplug::prelude::*;
+//! use rrplug::{exports::OnceCell, mid::hooks::Hook, offset_functions};
+//!
+//! offset_functions! {
+//!     CBUF + Cbuf for WhichDll::Engine => {
+//!         cbuf_execute = unsafe extern "C" fn() where offset(0x1204B0);
+//!     }
+//! }
+//!
+//! static CBUF_EXECUTE_HOOK: OnceCell<Hook<unsafe extern "C" fn()>> = OnceCell::new();
+//!
+//! unsafe extern "C" fn cbuf_execute_detour() {
+//!     log::info!("executing the command buffer");
+//!
+//!     unsafe { (CBUF_EXECUTE_HOOK.wait().original())() }
+//! }
+//!
+//! fn on_dll_load(engine_data: Option<&EngineData>, dll_ptr: &DLLPointer) {
+//!     _ = unsafe { Cbuf::try_init(dll_ptr, &CBUF) };
+//!
+//!     if let Some(cbuf) = CBUF.get() {
+//!         match unsafe { Hook::inline(cbuf.cbuf_execute, cbuf_execute_detour as _) } {
+//!             Ok(hook) => _ = unsafe { CBUF_EXECUTE_HOOK.get_or_init(|| hook).enable() },
+//!             Err(err) => err.log(),
+//!         }
+//!     }
+//! }
+//! ```
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    ffi::c_void,
+    marker::PhantomData,
+    mem,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{
+    errors::HookError,
+    high::UnsafeHandle,
+    interfaces::external::SourceInterface,
+    mid::memory::{alloc_executable, free_executable, write_bytes},
+};
+
+pub(crate) mod x64;
+
+/// the most bytes a single x86-64 instruction can take
+const MAX_INSTRUCTION_LEN: usize = 15;
+
+enum HookTarget {
+    Vtable {
+        slot: *mut usize,
+        original: usize,
+        detour: usize,
+    },
+    Inline {
+        target: *mut u8,
+        original: Vec<u8>,
+        patch: Vec<u8>,
+        trampoline: *mut u8,
+        trampoline_len: usize,
+    },
+}
+
+struct HookEntry {
+    target: HookTarget,
+    enabled: bool,
+}
+
+/// every hook which wasn't removed yet keyed by its id
+static HOOKS: Lazy<Mutex<HashMap<u64, UnsafeHandle<HookEntry>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static NEXT_HOOK_ID: AtomicU64 = AtomicU64::new(0);
+
+/// handle to a vtable or inline hook
+///
+/// `F` has to be a function pointer type matching the hooked function
+///
+/// dropping the handle doesn't remove the hook
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct Hook<F: Copy> {
+    id: u64,
+    original: usize,
+    _function: PhantomData<F>,
+}
+
+impl<F: Copy> Hook<F> {
+    /// hooks the slot `index` of a vtable
+    ///
+    /// # Safety
+    ///
+    /// `vtable` has to point to a vtable with more than `index` entries and `F` has to be the signature of the function in the slot
+    pub unsafe fn vtable(
+        vtable: *mut *const c_void,
+        index: usize,
+        detour: F,
+    ) -> Result<Self, HookError> {
+        if vtable.is_null() {
+            return Err(HookError::NullPointer);
+        }
+
+        let slot = unsafe { vtable.add(index) }.cast::<usize>();
+        let original = unsafe { slot.read() };
+
+        Self::register(
+            HookTarget::Vtable {
+                slot,
+                original,
+                detour: fn_to_usize(detour),
+            },
+            original,
+        )
+    }
+
+    /// hooks the slot `index` of the vtable of a c++ object like [`crate::bindings::class_types::cplayer::CPlayer`]
+    ///
+    /// this hooks every object which shares the vtable
+    ///
+    /// # Safety
+    ///
+    /// see [`Hook::vtable`]; the vtable pointer has to be the first field of `object`
+    pub unsafe fn object(
+        object: *const c_void,
+        index: usize,
+        detour: F,
+    ) -> Result<Self, HookError> {
+        if object.is_null() {
+            return Err(HookError::NullPointer);
+        }
+
+        unsafe { Self::vtable(*object.cast::<*mut *const c_void>(), index, detour) }
+    }
+
+    /// hooks the function `index` of a source interface like [`crate::bindings::cvar::RawCVar`]
+    ///
+    /// # Safety
+    ///
+    /// see [`Hook::vtable`]
+    pub unsafe fn interface(
+        interface: &impl SourceInterface,
+        index: usize,
+        detour: F,
+    ) -> Result<Self, HookError> {
+        unsafe { Self::vtable(interface.get_vtable().as_ptr().cast(), index, detour) }
+    }
+
+    /// overwrites the start of `target` with a jump to `detour`
+    ///
+    /// the overwritten instructions are moved to a trampoline which can be called through [`Hook::original`]
+    ///
+    /// # Safety
+    ///
+    /// `target` has to be a function with at least 14 bytes of code and `detour` has to have the same signature
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the start of the function uses rip relative operands or instructions which can't be decoded
+    pub unsafe fn inline(target: F, detour: F) -> Result<Self, HookError> {
+        let target = fn_to_usize(target) as *mut u8;
+        if target.is_null() {
+            return Err(HookError::NullPointer);
+        }
+
+        let code = unsafe {
+            std::slice::from_raw_parts(target, x64::ABSOLUTE_JMP_LEN + MAX_INSTRUCTION_LEN)
+        };
+        let relocated = x64::relocate(code, target as usize, x64::ABSOLUTE_JMP_LEN)?;
+
+        let mut patch = x64::absolute_jmp(fn_to_usize(detour)).to_vec();
+        patch.resize(relocated.stolen, 0x90);
+
+        let trampoline_len = relocated.trampoline.len();
+        let trampoline = alloc_executable(trampoline_len)?;
+        unsafe {
+            std::ptr::copy_nonoverlapping(relocated.trampoline.as_ptr(), trampoline, trampoline_len)
+        };
+
+        let hook = Self::register(
+            HookTarget::Inline {
+                target,
+                original: code[..relocated.stolen].to_vec(),
+                patch,
+                trampoline,
+                trampoline_len,
+            },
+            trampoline as usize,
+        );
+
+        if hook.is_err() {
+            unsafe { free_executable(trampoline, trampoline_len) }
+        }
+
+        hook
+    }
+
+    fn register(target: HookTarget, original: usize) -> Result<Self, HookError> {
+        let mut hooks = HOOKS.lock();
+
+        let address = target.address();
+        if hooks
+            .values()
+            .any(|entry| entry.get().target.address() == address)
+        {
+            return Err(HookError::AlreadyHooked(address));
+        }
+
+        let id = NEXT_HOOK_ID.fetch_add(1, Ordering::Relaxed);
+        hooks.insert(
+            id,
+            UnsafeHandle::internal_new(HookEntry {
+                target,
+                enabled: false,
+            }),
+        );
+
+        Ok(Self {
+            id,
+            original,
+            _function: PhantomData,
+        })
+    }
+
+    /// redirects calls to the detour
+    ///
+    /// # Safety
+    ///
+    /// the patched bytes aren't written atomically so nothing can be executing the start of the hooked function (or reading the vtable slot) while this runs
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the hook was removed or the memory couldn't be written
+    pub unsafe fn enable(&self) -> Result<(), HookError> {
+        unsafe { self.set_enabled(true) }
+    }
+
+    /// restores the original function without removing the hook
+    ///
+    /// # Safety
+    ///
+    /// see [`Hook::enable`]
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the hook was removed or the memory couldn't be written
+    pub unsafe fn disable(&self) -> Result<(), HookError> {
+        unsafe { self.set_enabled(false) }
+    }
+
+    unsafe fn set_enabled(&self, enabled: bool) -> Result<(), HookError> {
+        let mut hooks = HOOKS.lock();
+        let entry = hooks.get_mut(&self.id).ok_or(HookError::NotHooked)?;
+
+        unsafe { entry.get_mut().set_enabled(enabled) }
+    }
+
+    /// returns [`true`] if calls currently go to the detour
+    pub fn is_enabled(&self) -> bool {
+        HOOKS
+            .lock()
+            .get(&self.id)
+            .map(|entry| entry.get().enabled)
+            .unwrap_or_default()
+    }
+
+    /// returns [`true`] if the hook wasn't removed yet
+    pub fn is_hooked(&self) -> bool {
+        HOOKS.lock().contains_key(&self.id)
+    }
+
+    /// disables and removes the hook
+    ///
+    /// the trampoline of inline hooks is freed so [`Hook::original`] can't be called after this
+    ///
+    /// # Safety
+    ///
+    /// see [`Hook::enable`]; the trampoline can't be executing either
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the hook was already removed or the memory couldn't be restored
+    pub unsafe fn unhook(self) -> Result<(), HookError> {
+        let mut hooks = HOOKS.lock();
+        let entry = hooks.get_mut(&self.id).ok_or(HookError::NotHooked)?;
+        unsafe { entry.get_mut().set_enabled(false)? };
+
+        if let Some(entry) = hooks.remove(&self.id) {
+            unsafe { entry.take().free() }
+        }
+
+        Ok(())
+    }
+
+    /// the original function
+    ///
+    /// for inline hooks this is the trampoline
+    ///
+    /// # Safety
+    ///
+    /// the trampoline is freed when any clone of this hook is removed with [`Hook::unhook`] or when the plugin unloads,
+    /// so the returned function can't be called after that
+    pub unsafe fn original(&self) -> F {
+        usize_to_fn(self.original)
+    }
+}
+
+impl<F: Copy> Clone for Hook<F> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            original: self.original,
+            _function: PhantomData,
+        }
+    }
+}
+
+impl HookTarget {
+    fn address(&self) -> usize {
+        match self {
+            Self::Vtable { slot, .. } => *slot as usize,
+            Self::Inline { target, .. } => *target as usize,
+        }
+    }
+}
+
+impl HookEntry {
+    unsafe fn set_enabled(&mut self, enabled: bool) -> Result<(), HookError> {
+        if self.enabled == enabled {
+            return Ok(());
+        }
+
+        match &self.target {
+            HookTarget::Vtable {
+                slot,
+                original,
+                detour,
+            } => {
+                let function = if enabled { detour } else { original };
+                unsafe { write_bytes(slot.cast(), &function.to_ne_bytes())? }
+            }
+            HookTarget::Inline {
+                target,
+                original,
+                patch,
+                ..
+            } => unsafe { write_bytes(*target, if enabled { patch } else { original })? },
+        }
+
+        self.enabled = enabled;
+        Ok(())
+    }
+
+    unsafe fn free(self) {
+        if let HookTarget::Inline {
+            trampoline,
+            trampoline_len,
+            ..
+        } = self.target
+        {
+            unsafe { free_executable(trampoline, trampoline_len) }
+        }
+    }
+}
+
+/// disables and removes every hook
+///
+/// called by [`crate::entry`] before the plugin is allowed to unload
+///
+/// # Safety
+///
+/// the trampolines of inline hooks are freed so nothing can be executing them
+#[doc(hidden)]
+pub unsafe fn unhook_all() {
+    for (_, entry) in HOOKS.lock().drain() {
+        let mut entry = entry.take();
+
+        match unsafe { entry.set_enabled(false) } {
+            Ok(()) => unsafe { entry.free() },
+            // the trampoline could still be reached so it's leaked
+            Err(err) => err.log(),
+        }
+    }
+}
+
+fn fn_to_usize<F: Copy>(function: F) -> usize {
+    assert_eq!(
+        mem::size_of::<F>(),
+        mem::size_of::<usize>(),
+        "hooks only work with function pointers"
+    );
+    unsafe { mem::transmute_copy(&function) }
+}
+
+fn usize_to_fn<F: Copy>(function: usize) -> F {
+    assert_eq!(
+        mem::size_of::<F>(),
+        mem::size_of::<usize>(),
+        "hooks only work with function pointers"
+    );
+    unsafe { mem::transmute_copy(&function) }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    type VFunc = extern "C" fn(i32) -> i32;
+
+    extern "C" fn add_one(x: i32) -> i32 {
+        x + 1
+    }
+
+    extern "C" fn double(x: i32) -> i32 {
+        x * 2
+    }
+
+    extern "C" fn detour(x: i32) -> i32 {
+        -x
+    }
+
+    #[repr(C)]
+    struct Object {
+        vtable: *mut *const c_void,
+    }
+
+    fn call(object: &Object, index: usize) -> i32 {
+        let function = unsafe { *object.vtable.add(index) };
+        usize_to_fn::<VFunc>(function as usize)(10)
+    }
+
+    #[test]
+    fn vtable_hook_lifecycle() {
+        let mut vtable = [add_one as VFunc as *const c_void, double as VFunc as _];
+        let object = Object {
+            vtable: vtable.as_mut_ptr(),
+        };
+
+        let hook =
+            unsafe { Hook::<VFunc>::object(&object as *const Object as *const c_void, 1, detour) }
+                .unwrap();
+        assert!(hook.is_hooked());
+        assert!(!hook.is_enabled());
+        assert_eq!(call(&object, 1), 20);
+
+        unsafe { hook.enable() }.unwrap();
+        assert!(hook.is_enabled());
+        assert_eq!(call(&object, 0), 11);
+        assert_eq!(call(&object, 1), -10);
+        assert_eq!(unsafe { hook.original() }(10), 20);
+
+        assert_eq!(
+            unsafe { Hook::<VFunc>::vtable(vtable.as_mut_ptr(), 1, detour) },
+            Err(HookError::AlreadyHooked(
+                unsafe { vtable.as_mut_ptr().add(1) } as usize
+            ))
+        );
+
+        unsafe { hook.disable() }.unwrap();
+        assert_eq!(call(&object, 1), 20);
+        unsafe { hook.enable() }.unwrap();
+
+        unsafe { hook.clone().unhook() }.unwrap();
+        assert_eq!(call(&object, 1), 20);
+        assert!(!hook.is_hooked());
+        assert_eq!(unsafe { hook.enable() }, Err(HookError::NotHooked));
+        assert_eq!(unsafe { hook.unhook() }, Err(HookError::NotHooked));
+    }
+
+    #[test]
+    fn inline_hook_patch() {
+        // mov [rsp+8], rbx; sub rsp, 0x20; mov eax, 1; add rsp, 0x20; pop rbx; ret
+        let mut code = [
+            0x48, 0x89, 0x5C, 0x24, 0x08, 0x48, 0x83, 0xEC, 0x20, 0xB8, 0x01, 0x00, 0x00, 0x00,
+            0x48, 0x83, 0xC4, 0x20, 0x5B, 0xC3, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC,
+            0xCC, 0xCC,
+        ];
+        let original = code;
+        let target = code.as_mut_ptr();
+        let detour_address = detour as VFunc as usize;
+
+        let hook = unsafe { Hook::<VFunc>::inline(usize_to_fn(target as usize), detour) }.unwrap();
+        assert_eq!(code, original);
+
+        unsafe { hook.enable() }.unwrap();
+        assert_eq!(
+            code[..x64::ABSOLUTE_JMP_LEN],
+            x64::absolute_jmp(detour_address)
+        );
+        assert_eq!(
+            code[x64::ABSOLUTE_JMP_LEN..],
+            original[x64::ABSOLUTE_JMP_LEN..]
+        );
+
+        let trampoline = fn_to_usize(unsafe { hook.original() }) as *const u8;
+        let trampoline = unsafe { std::slice::from_raw_parts(trampoline, 28) };
+        assert_eq!(trampoline[..14], original[..14]);
+        assert_eq!(
+            trampoline[14..],
+            x64::absolute_jmp(target as usize + x64::ABSOLUTE_JMP_LEN)
+        );
+
+        unsafe { hook.disable() }.unwrap();
+        assert_eq!(code, original);
+        unsafe { hook.enable() }.unwrap();
+
+        unsafe { hook.clone().unhook() }.unwrap();
+        assert_eq!(code, original);
+        assert!(!hook.is_hooked());
+    }
+}
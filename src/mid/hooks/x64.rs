@@ -0,0 +1,326 @@
+//! a minimal x86-64 length decoder and relocator for the instructions stolen by inline detours
+//!
+//! only the lengths and relative operands are decoded; vex/evex encoded instructions aren't supported
+
+use crate::errors::HookError;
+
+/// `jmp [rip + 0]` followed by the absolute address
+pub(crate) const ABSOLUTE_JMP_LEN: usize = 14;
+
+/// what a decoded instruction needs when it's moved somewhere else
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InstructionKind {
+    /// can be copied as is
+    Plain,
+    /// `jmp rel8` or `jmp rel32`
+    Jmp,
+    /// `call rel32`
+    Call,
+    /// `jcc rel8` or `jcc rel32` with its condition code
+    Jcc(u8),
+    /// `loop`, `jrcxz` and friends which only have a rel8 form
+    ShortBranch,
+    /// uses a `[rip + disp32]` operand
+    RipRelative,
+}
+
+/// a decoded instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Instruction {
+    pub len: usize,
+    pub kind: InstructionKind,
+    /// the offset and size of the relative immediate for branches
+    pub relative: Option<(usize, usize)>,
+}
+
+impl Instruction {
+    /// the target of a relative branch at `address`
+    pub(crate) fn branch_target(&self, code: &[u8], address: usize) -> Option<usize> {
+        let (offset, size) = self.relative?;
+        let displacement = match size {
+            1 => *code.get(offset)? as i8 as isize,
+            4 => i32::from_le_bytes(code.get(offset..offset + 4)?.try_into().ok()?) as isize,
+            _ => return None,
+        };
+
+        Some(
+            address
+                .wrapping_add(self.len)
+                .wrapping_add_signed(displacement),
+        )
+    }
+}
+
+/// decodes the length of the first instruction in `code`
+pub(crate) fn decode(code: &[u8]) -> Option<Instruction> {
+    decode_unchecked(code).filter(|instruction| instruction.len <= code.len())
+}
+
+fn decode_unchecked(code: &[u8]) -> Option<Instruction> {
+    let mut i = 0;
+    let mut operand_size_16 = false;
+    let mut rex_w = false;
+
+    while matches!(
+        *code.get(i)?,
+        0xF0 | 0xF2 | 0xF3 | 0x2E | 0x36 | 0x3E | 0x26 | 0x64 | 0x65 | 0x66 | 0x67
+    ) {
+        operand_size_16 |= code[i] == 0x66;
+        i += 1;
+    }
+
+    if (0x40..=0x4F).contains(code.get(i)?) {
+        rex_w = code[i] & 0x8 != 0;
+        i += 1;
+    }
+
+    let imm_z = if operand_size_16 { 2 } else { 4 };
+    let opcode = *code.get(i)?;
+    i += 1;
+
+    let plain = |len| {
+        Some(Instruction {
+            len,
+            kind: InstructionKind::Plain,
+            relative: None,
+        })
+    };
+    let relative = |kind, len: usize, size| {
+        Some(Instruction {
+            len: len + size,
+            kind,
+            relative: Some((len, size)),
+        })
+    };
+
+    if opcode == 0x0F {
+        let opcode = *code.get(i)?;
+        i += 1;
+
+        return match opcode {
+            0x80..=0x8F => relative(InstructionKind::Jcc(opcode & 0xF), i, 4),
+            0x05..=0x09 | 0x0B | 0x0E | 0x30..=0x37 | 0x77 | 0xA0..=0xA2 | 0xA8..=0xAA => plain(i),
+            0xC8..=0xCF => plain(i),
+            0x38 => {
+                i += 1;
+                modrm(code, i, 0)
+            }
+            0x3A => {
+                i += 1;
+                modrm(code, i, 1)
+            }
+            0x0F | 0x70..=0x73 | 0xA4 | 0xAC | 0xBA | 0xC2 | 0xC4..=0xC6 => modrm(code, i, 1),
+            _ => modrm(code, i, 0),
+        };
+    }
+
+    match opcode {
+        0x70..=0x7F => relative(InstructionKind::Jcc(opcode & 0xF), i, 1),
+        0xE0..=0xE3 => relative(InstructionKind::ShortBranch, i, 1),
+        0xE8 => relative(InstructionKind::Call, i, 4),
+        0xE9 => relative(InstructionKind::Jmp, i, 4),
+        0xEB => relative(InstructionKind::Jmp, i, 1),
+
+        // alu ops in the first quarter of the map
+        0x00..=0x3F if opcode & 0x7 < 4 => modrm(code, i, 0),
+        0x00..=0x3F if opcode & 0x7 == 4 => plain(i + 1),
+        0x00..=0x3F if opcode & 0x7 == 5 => plain(i + imm_z),
+
+        0x50..=0x5F | 0x6C..=0x6F | 0x90..=0x99 | 0x9B..=0x9F | 0xA4..=0xA7 | 0xAA..=0xAF => {
+            plain(i)
+        }
+        0xC3 | 0xC9 | 0xCB | 0xCC | 0xCF | 0xD7 | 0xEC..=0xEF | 0xF1 | 0xF4 | 0xF5 => plain(i),
+        0xF8..=0xFD => plain(i),
+
+        0x63 | 0x84..=0x8F | 0xD0..=0xD3 | 0xD8..=0xDF | 0xFE | 0xFF => modrm(code, i, 0),
+        0x69 | 0x81 | 0xC7 => modrm(code, i, imm_z),
+        0x6B | 0x80 | 0x83 | 0xC0 | 0xC1 | 0xC6 => modrm(code, i, 1),
+        0xF6 | 0xF7 => {
+            // only test has an immediate
+            let reg = (*code.get(i)? >> 3) & 0x7;
+            let imm = match (reg, opcode) {
+                (0 | 1, 0xF6) => 1,
+                (0 | 1, _) => imm_z,
+                _ => 0,
+            };
+            modrm(code, i, imm)
+        }
+
+        0x68 | 0xA9 => plain(i + imm_z),
+        0x6A | 0xA8 | 0xB0..=0xB7 | 0xCD | 0xE4..=0xE7 => plain(i + 1),
+        0xB8..=0xBF if rex_w => plain(i + 8),
+        0xB8..=0xBF => plain(i + imm_z),
+        0xA0..=0xA3 => plain(i + 8),
+        0xC2 | 0xCA => plain(i + 2),
+        0xC8 => plain(i + 3),
+
+        _ => None,
+    }
+}
+
+/// decodes a modrm byte at `i` with its sib and displacement followed by an immediate of `imm` bytes
+fn modrm(code: &[u8], mut i: usize, imm: usize) -> Option<Instruction> {
+    let modrm = *code.get(i)?;
+    i += 1;
+
+    let mode = modrm >> 6;
+    let rm = modrm & 0x7;
+    let mut kind = InstructionKind::Plain;
+
+    if mode != 3 && rm == 4 {
+        let sib = *code.get(i)?;
+        i += 1;
+
+        if mode == 0 && sib & 0x7 == 5 {
+            i += 4;
+        }
+    }
+
+    i += match (mode, rm) {
+        (0, 5) => {
+            kind = InstructionKind::RipRelative;
+            4
+        }
+        (1, _) => 1,
+        (2, _) => 4,
+        _ => 0,
+    };
+
+    Some(Instruction {
+        len: i + imm,
+        kind,
+        relative: None,
+    })
+}
+
+/// writes `jmp [rip + 0]` to `to`
+pub(crate) fn absolute_jmp(to: usize) -> [u8; ABSOLUTE_JMP_LEN] {
+    let mut jmp = [0; ABSOLUTE_JMP_LEN];
+    jmp[..6].copy_from_slice(&[0xFF, 0x25, 0, 0, 0, 0]);
+    jmp[6..].copy_from_slice(&to.to_le_bytes());
+    jmp
+}
+
+/// the instructions moved out of a function and the code which replaces them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Relocated {
+    /// how many bytes were taken from the start of the function
+    pub stolen: usize,
+    /// the moved instructions followed by a jump back into the function
+    pub trampoline: Vec<u8>,
+}
+
+/// moves at least `min_len` bytes worth of instructions from `code` which lives at `address`
+///
+/// relative branches are rewritten to absolute ones so the trampoline can be anywhere in memory
+pub(crate) fn relocate(
+    code: &[u8],
+    address: usize,
+    min_len: usize,
+) -> Result<Relocated, HookError> {
+    let mut stolen = 0;
+    let mut trampoline = Vec::new();
+
+    while stolen < min_len {
+        let rest = &code[stolen..];
+        let instruction =
+            decode(rest).ok_or(HookError::UnsupportedInstruction(address + stolen))?;
+        let bytes = &rest[..instruction.len];
+        let target = instruction.branch_target(rest, address + stolen);
+
+        match (instruction.kind, target) {
+            (InstructionKind::Plain, _) => trampoline.extend_from_slice(bytes),
+            (InstructionKind::Jmp, Some(target)) => {
+                trampoline.extend_from_slice(&absolute_jmp(target))
+            }
+            (InstructionKind::Call, Some(target)) => {
+                // call [rip + 2]; jmp +8; address
+                trampoline.extend_from_slice(&[0xFF, 0x15, 0x02, 0, 0, 0, 0xEB, 0x08]);
+                trampoline.extend_from_slice(&target.to_le_bytes());
+            }
+            (InstructionKind::Jcc(condition), Some(target)) => {
+                // the inverted condition skips over the absolute jump
+                trampoline.extend_from_slice(&[0x70 | (condition ^ 1), ABSOLUTE_JMP_LEN as u8]);
+                trampoline.extend_from_slice(&absolute_jmp(target));
+            }
+            (InstructionKind::RipRelative, _) => {
+                return Err(HookError::RipRelativeInstruction(address + stolen))
+            }
+            _ => return Err(HookError::UnsupportedInstruction(address + stolen)),
+        }
+
+        stolen += instruction.len;
+    }
+
+    trampoline.extend_from_slice(&absolute_jmp(address + stolen));
+
+    Ok(Relocated { stolen, trampoline })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn instruction_lengths() {
+        let cases: &[(&[u8], usize)] = &[
+            (&[0x48, 0x89, 0x5C, 0x24, 0x08], 5),        // mov [rsp+8], rbx
+            (&[0x55], 1),                                // push rbp
+            (&[0x48, 0x83, 0xEC, 0x20], 4),              // sub rsp, 0x20
+            (&[0x48, 0x81, 0xEC, 0, 1, 0, 0], 7),        // sub rsp, 0x100
+            (&[0x48, 0x8B, 0x05, 0, 0, 0, 0], 7),        // mov rax, [rip]
+            (&[0x48, 0xB8, 1, 2, 3, 4, 5, 6, 7, 8], 10), // mov rax, imm64
+            (&[0xB8, 1, 2, 3, 4], 5),                    // mov eax, imm32
+            (&[0x66, 0xB8, 1, 2], 4),                    // mov ax, imm16
+            (&[0x0F, 0x1F, 0x44, 0x00, 0x00], 5),        // nop dword [rax+rax]
+            (&[0xF6, 0xC1, 0x01], 3),                    // test cl, 1
+            (&[0xF7, 0xD8], 2),                          // neg eax
+            (&[0x0F, 0x84, 0, 0, 0, 0], 6),              // jz rel32
+            (&[0xC3], 1),                                // ret
+        ];
+
+        for (code, len) in cases {
+            assert_eq!(decode(code).map(|i| i.len), Some(*len), "{code:02X?}");
+        }
+
+        assert_eq!(
+            decode(&[0x48, 0x8B, 0x05, 0, 0, 0, 0]).unwrap().kind,
+            InstructionKind::RipRelative
+        );
+        assert_eq!(decode(&[0x48, 0x81, 0xEC]), None);
+        assert_eq!(decode(&[0xC4, 0xE2, 0x79]), None);
+    }
+
+    #[test]
+    fn relocate_prologue() {
+        let address = 0x1000;
+        let code = [
+            0x48, 0x89, 0x5C, 0x24, 0x08, // mov [rsp+8], rbx
+            0x74, 0x10, // jz +0x10
+            0xE8, 0x00, 0x01, 0x00, 0x00, // call +0x100
+            0x48, 0x83, 0xEC, 0x20, // sub rsp, 0x20
+            0xC3,
+        ];
+
+        let relocated = relocate(&code, address, ABSOLUTE_JMP_LEN).unwrap();
+        assert_eq!(relocated.stolen, 16);
+
+        let mut expected = vec![0x48, 0x89, 0x5C, 0x24, 0x08];
+        expected.extend([0x75, 14]);
+        expected.extend(absolute_jmp(address + 7 + 0x10));
+        expected.extend([0xFF, 0x15, 0x02, 0, 0, 0, 0xEB, 0x08]);
+        expected.extend((address + 12 + 0x100_usize).to_le_bytes());
+        expected.extend([0x48, 0x83, 0xEC, 0x20]);
+        expected.extend(absolute_jmp(address + 16));
+        assert_eq!(relocated.trampoline, expected);
+
+        assert!(matches!(
+            relocate(&[0x48, 0x8B, 0x05, 0, 0, 0, 0], address, 5),
+            Err(HookError::RipRelativeInstruction(0x1000))
+        ));
+        assert!(matches!(
+            relocate(&[0x90, 0xE2, 0x00], address, 3),
+            Err(HookError::UnsupportedInstruction(0x1001))
+        ));
+    }
+}
@@ -0,0 +1,133 @@
+//! page protection and executable memory used by [`crate::mid::hooks`] and memory patches
+//!
+//! only windows actually changes protections; other targets are only used to run tests on memory that is already writable
+
+use std::ffi::c_void;
+
+use crate::errors::MemoryError;
+
+/// makes a region writable and restores the old protection when dropped
+///
+/// the instruction cache of the region is flushed on drop since it usually contains code
+pub(crate) struct Unprotected {
+    address: *const c_void,
+    size: usize,
+    old_protection: u32,
+}
+
+impl Unprotected {
+    /// # Safety
+    ///
+    /// the region has to be mapped memory
+    pub(crate) unsafe fn new(address: *const c_void, size: usize) -> Result<Self, MemoryError> {
+        Ok(Self {
+            address,
+            size,
+            old_protection: unsafe { sys::protect(address, size, sys::EXECUTE_READWRITE) }
+                .ok_or(MemoryError::Protect(address as usize))?,
+        })
+    }
+}
+
+impl Drop for Unprotected {
+    fn drop(&mut self) {
+        unsafe {
+            _ = sys::protect(self.address, self.size, self.old_protection);
+            sys::flush_instruction_cache(self.address, self.size);
+        }
+    }
+}
+
+/// copies `bytes` to `address` regardless of the protection of the page
+///
+/// # Safety
+///
+/// `address` has to be valid for `bytes.len()` bytes and nothing can be executing the bytes while they are written
+pub(crate) unsafe fn write_bytes(address: *mut u8, bytes: &[u8]) -> Result<(), MemoryError> {
+    let _unprotected = unsafe { Unprotected::new(address.cast(), bytes.len())? };
+    unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), address, bytes.len()) };
+
+    Ok(())
+}
+
+/// allocates `size` bytes of readable, writable and executable memory
+pub(crate) fn alloc_executable(size: usize) -> Result<*mut u8, MemoryError> {
+    let memory = unsafe { sys::alloc_executable(size) };
+
+    if memory.is_null() {
+        Err(MemoryError::Alloc(size))
+    } else {
+        Ok(memory)
+    }
+}
+
+/// # Safety
+///
+/// `memory` has to come from [`alloc_executable`] with the same `size` and can't be used after this
+pub(crate) unsafe fn free_executable(memory: *mut u8, size: usize) {
+    unsafe { sys::free_executable(memory, size) }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::ffi::c_void;
+    use windows::Win32::System::{
+        Diagnostics::Debug::FlushInstructionCache,
+        Memory::{
+            VirtualAlloc, VirtualFree, VirtualProtect, MEM_COMMIT, MEM_RELEASE, MEM_RESERVE,
+            PAGE_EXECUTE_READWRITE, PAGE_PROTECTION_FLAGS,
+        },
+        Threading::GetCurrentProcess,
+    };
+
+    pub const EXECUTE_READWRITE: u32 = PAGE_EXECUTE_READWRITE.0;
+
+    pub unsafe fn protect(address: *const c_void, size: usize, protection: u32) -> Option<u32> {
+        let mut old = PAGE_PROTECTION_FLAGS::default();
+        unsafe { VirtualProtect(address, size, PAGE_PROTECTION_FLAGS(protection), &mut old) }
+            .ok()
+            .map(|_| old.0)
+    }
+
+    pub unsafe fn flush_instruction_cache(address: *const c_void, size: usize) {
+        _ = unsafe { FlushInstructionCache(GetCurrentProcess(), Some(address), size) };
+    }
+
+    pub unsafe fn alloc_executable(size: usize) -> *mut u8 {
+        unsafe { VirtualAlloc(None, size, MEM_COMMIT | MEM_RESERVE, PAGE_EXECUTE_READWRITE) }.cast()
+    }
+
+    pub unsafe fn free_executable(memory: *mut u8, _: usize) {
+        _ = unsafe { VirtualFree(memory.cast(), 0, MEM_RELEASE) };
+    }
+}
+
+#[cfg(not(windows))]
+mod sys {
+    use std::{
+        alloc::{alloc_zeroed, dealloc, Layout},
+        ffi::c_void,
+    };
+
+    pub const EXECUTE_READWRITE: u32 = 0x40;
+
+    pub const unsafe fn protect(_: *const c_void, _: usize, protection: u32) -> Option<u32> {
+        Some(protection)
+    }
+
+    pub const unsafe fn flush_instruction_cache(_: *const c_void, _: usize) {}
+
+    // not executable but enough to inspect trampolines in tests
+    pub unsafe fn alloc_executable(size: usize) -> *mut u8 {
+        match Layout::from_size_align(size.max(1), 16) {
+            Ok(layout) => unsafe { alloc_zeroed(layout) },
+            Err(_) => std::ptr::null_mut(),
+        }
+    }
+
+    pub unsafe fn free_executable(memory: *mut u8, size: usize) {
+        if let Ok(layout) = Layout::from_size_align(size.max(1), 16) {
+            unsafe { dealloc(memory, layout) }
+        }
+    }
+}
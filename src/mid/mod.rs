@@ -1,7 +1,10 @@
 //! contains structs and functions with minimal abstraction
 
 pub mod engine;
+pub mod hooks;
+pub(crate) mod memory;
 pub mod northstar;
+pub mod pattern;
 pub mod reloading;
 pub mod server;
 pub mod source_alloc;
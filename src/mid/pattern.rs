@@ -0,0 +1,196 @@
+//! ida style pattern scanning
+//!
+//! patterns are hex bytes separated by spaces where `?` or `??` matches any byte like `"48 8B 05 ?? ?? ?? ?? E8"`
+//!
+//! works on any byte buffer; use [`crate::mid::engine::DLLPointer::find_pattern`] to scan the code sections of a dll
+//!
+//! ```
+//! use rrplug::mid::pattern::{resolve_rip_relative, Pattern};
+//!
+//! // mov rax, [rip + 0x10]; ret
+//! let code = [0x90, 0x48, 0x8B, 0x05, 0x10, 0x00, 0x00, 0x00, 0xC3];
+//! let pattern = Pattern::new("48 8B 05 ?? ?? ?? ?? C3").unwrap();
+//!
+//! let at = pattern.find(&code).unwrap();
+//! assert_eq!(at, 1);
+//! assert_eq!(resolve_rip_relative(&code, at, 3, 7), Some(0x18));
+//! ```
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::errors::PatternError;
+
+/// a parsed ida style pattern
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Pattern {
+    bytes: Vec<Option<u8>>,
+}
+
+impl Pattern {
+    /// parses a pattern like `"48 8B ?? ?? E8"`
+    ///
+    /// # Errors
+    ///
+    /// this function will return an error if the pattern is empty, only has wildcards or has something other than hex bytes and wildcards
+    pub fn new(pattern: &str) -> Result<Self, PatternError> {
+        let bytes = pattern
+            .split_whitespace()
+            .map(|byte| match byte {
+                "?" | "??" => Ok(None),
+                byte if byte.len() == 2 => u8::from_str_radix(byte, 16)
+                    .map(Some)
+                    .map_err(|_| PatternError::InvalidByte(byte.to_string())),
+                byte => Err(PatternError::InvalidByte(byte.to_string())),
+            })
+            .collect::<Result<Vec<Option<u8>>, PatternError>>()?;
+
+        if bytes.iter().all(Option::is_none) {
+            return Err(PatternError::Empty);
+        }
+
+        Ok(Self { bytes })
+    }
+
+    /// the amount of bytes the pattern matches
+    pub const fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// always [`false`] since patterns can't be empty
+    pub const fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// returns [`true`] if `bytes` start with the pattern
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        bytes.len() >= self.bytes.len()
+            && self
+                .bytes
+                .iter()
+                .zip(bytes)
+                .all(|(pattern, byte)| pattern.map(|pattern| pattern == *byte).unwrap_or(true))
+    }
+
+    /// the offset of the first match in `haystack`
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        self.find_iter(haystack).next()
+    }
+
+    /// the offsets of every match in `haystack`
+    pub fn find_iter<'a>(&'a self, haystack: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        // the first concrete byte is used to skip to candidates quickly
+        let (anchor_index, anchor) = self
+            .bytes
+            .iter()
+            .enumerate()
+            .find_map(|(index, byte)| Some((index, (*byte)?)))
+            .expect("patterns always have a concrete byte");
+
+        haystack
+            .iter()
+            .enumerate()
+            .skip(anchor_index)
+            .filter(move |(_, byte)| **byte == anchor)
+            .map(move |(index, _)| index - anchor_index)
+            .filter(move |start| self.matches(&haystack[*start..]))
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bytes = self
+            .bytes
+            .iter()
+            .map(|byte| {
+                byte.map(|byte| format!("{byte:02X}"))
+                    .unwrap_or_else(|| "??".to_string())
+            })
+            .collect::<Vec<String>>();
+
+        f.write_str(&bytes.join(" "))
+    }
+}
+
+/// resolves a rip relative operand of the instruction at `instruction` in `haystack`
+///
+/// `displacement` is where the i32 displacement starts in the instruction and `instruction_len` is the length of the whole instruction;
+/// for `48 8B 05 ?? ?? ?? ??` it's 3 and 7 and for `E8 ?? ?? ?? ??` it's 1 and 5
+///
+/// returns the target relative to the start of `haystack` which can be outside of `haystack`
+pub fn resolve_rip_relative(
+    haystack: &[u8],
+    instruction: usize,
+    displacement: usize,
+    instruction_len: usize,
+) -> Option<isize> {
+    let start = instruction.checked_add(displacement)?;
+    let displacement = i32::from_le_bytes(
+        haystack
+            .get(start..start.checked_add(4)?)?
+            .try_into()
+            .ok()?,
+    );
+
+    (instruction.checked_add(instruction_len)? as isize).checked_add(displacement as isize)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_patterns() {
+        let pattern = Pattern::new("48 8b ?? ? E8").unwrap();
+        assert_eq!(pattern.len(), 5);
+        assert_eq!(pattern.to_string(), "48 8B ?? ?? E8");
+
+        assert!(matches!(
+            Pattern::new("48 8G"),
+            Err(PatternError::InvalidByte(byte)) if byte == "8G"
+        ));
+        assert!(matches!(
+            Pattern::new("488B"),
+            Err(PatternError::InvalidByte(_))
+        ));
+        assert!(matches!(Pattern::new(""), Err(PatternError::Empty)));
+        assert!(matches!(Pattern::new("?? ??"), Err(PatternError::Empty)));
+    }
+
+    #[test]
+    fn scan_buffers() {
+        let code = [
+            0xCC, 0x48, 0x8B, 0x05, 0xF0, 0xFF, 0xFF, 0xFF, // mov rax, [rip - 0x10]
+            0xE8, 0x10, 0x00, 0x00, 0x00, // call +0x10
+            0x48, 0x8B, 0x0D, 0x00, 0x00, 0x00, 0x00, // mov rcx, [rip]
+        ];
+
+        let pattern = Pattern::new("?? 8B ?? ?? ?? ?? ??").unwrap();
+        assert_eq!(pattern.find_iter(&code).collect::<Vec<usize>>(), [1, 13]);
+
+        let mov = Pattern::new("48 8B 05 ?? ?? ?? ??")
+            .unwrap()
+            .find(&code)
+            .unwrap();
+        assert_eq!(resolve_rip_relative(&code, mov, 3, 7), Some(8 - 0x10));
+
+        let call = Pattern::new("E8 ?? ?? ?? ?? 48")
+            .unwrap()
+            .find(&code)
+            .unwrap();
+        assert_eq!(call, 8);
+        assert_eq!(resolve_rip_relative(&code, call, 1, 5), Some(13 + 0x10));
+
+        assert_eq!(resolve_rip_relative(&code, 13, 3, 7), Some(20));
+        assert_eq!(resolve_rip_relative(&code, 18, 1, 5), None);
+        assert_eq!(Pattern::new("48 8B 1D").unwrap().find(&code), None);
+        assert_eq!(Pattern::new("0D 00 00 00 00 00").unwrap().find(&code), None);
+    }
+}
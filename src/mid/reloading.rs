@@ -23,7 +23,9 @@ impl ReloadResponse {
     ///
     /// **unless** before calling this everything will cleaned up!
     ///
-    /// ex: convars, concommands, sqfunctions (sqvm reload), hooks, etc
+    /// ex: sqfunctions (sqvm reload), memory rrplug doesn't know about, etc
+    ///
+    /// convars and concommands registered through rrplug are unregistered automatically and hooks created with [`crate::mid::hooks::Hook`] or [`crate::high::engine::concommand_hooks`] are removed automatically
     pub const unsafe fn allow_reload() -> Self {
         Self {
             should_reload: true,
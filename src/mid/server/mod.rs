@@ -1 +1,19 @@
+//! server related stuff with minimal abstractions
 
+use std::ffi::c_void;
+
+use crate::{bindings::class_types::client::CClient, offset_functions};
+
+offset_functions! {
+    CLIENT_ARRAY + ClientArray for WhichDll::Engine => {
+        client_array = *mut CClient where offset(0x12A53F90);
+    }
+}
+
+offset_functions! {
+    SERVER_ENTITIES + ServerEntities for WhichDll::Server => verify {
+        code(0xFB820);
+    } {
+        get_entity_by_index = unsafe extern "C" fn(index: i32) -> *mut c_void where offset(0xFB820);
+    }
+}
@@ -60,8 +60,8 @@ pub struct SqFunctions {
 impl SqFunctions {
     #[doc(hidden)]
     pub fn fetch_functions(&self, dll: &DLLPointer) {
-        unsafe { ClientSQFunctions::try_init(dll, &SQUIRREL_CLIENT_FUNCS) };
-        unsafe { ServerSQFunctions::try_init(dll, &SQUIRREL_SERVER_FUNCS) };
+        _ = unsafe { ClientSQFunctions::try_init(dll, &SQUIRREL_CLIENT_FUNCS) };
+        _ = unsafe { ServerSQFunctions::try_init(dll, &SQUIRREL_SERVER_FUNCS) };
     }
 
     #[doc(hidden)]
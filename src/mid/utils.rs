@@ -31,3 +31,18 @@ pub fn to_cstring(s: &str) -> CString {
 pub fn try_cstring(s: &str) -> Result<CString, NulError> {
     CString::new(s)
 }
+
+/// reads a nul terminated string from a fixed size c array
+///
+/// the string is cut at the first invalid utf8 byte or at the end of the array if there is no nul
+#[inline]
+pub fn str_from_char_array(buf: &[c_char]) -> &str {
+    // c_char and u8 have the same layout
+    let bytes = unsafe { std::slice::from_raw_parts(buf.as_ptr().cast::<u8>(), buf.len()) };
+    let bytes = &bytes[..bytes.iter().position(|c| *c == 0).unwrap_or(bytes.len())];
+
+    match std::str::from_utf8(bytes) {
+        Ok(s) => s,
+        Err(err) => std::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap_or_default(),
+    }
+}
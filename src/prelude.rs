@@ -4,7 +4,7 @@
 
 pub use crate::{
     bindings::{
-        plugin_abi::PluginContext, squirrelclasstypes::ScriptContext,
+        cvar::CVarFlags, plugin_abi::PluginContext, squirrelclasstypes::ScriptContext,
         squirreldatatypes::HSquirrelVM, squirrelfunctions::SquirrelFunctions,
     },
     entry,
@@ -12,7 +12,10 @@ pub use crate::{
         self,
         engine::{
             concommands::CCommandResult,
-            convars::{ConVarRegister, ConVarStruct},
+            convars::{
+                typed::{TypedConVar, TypedConVarRegister},
+                ConVarRegister, ConVarStruct,
+            },
             EngineData, EngineGlobal, EngineToken,
         },
         northstar::PluginInfo,
@@ -26,6 +29,7 @@ pub use crate::{
         squirrel::SQFUNCTIONS,
     },
     plugin::Plugin,
+    static_concommand, static_convar,
 };
 pub use log;
 